@@ -0,0 +1,71 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use crate::cpu_info::effective_cpus;
+use crate::handler::Mode;
+use crate::workload::{TunedParams, Workload};
+
+// Fixed cost of one parallel run on top of the items themselves: waking the pool, splitting the
+// range and joining (measured in the tens of microseconds; rounded up)
+const PARALLEL_OVERHEAD_US: f64 = 100.0;
+
+// Workload, parameters and item length in bytes whose per-item cost was measured
+type CostKey = (&'static str, TunedParams, usize);
+
+// Per-item cost measured once per container and workload configuration, in microseconds
+static ITEM_COST_US: OnceLock<Mutex<HashMap<CostKey, f64>>> = OnceLock::new();
+
+// How mode "auto" chose its execution strategy (reported in the response)
+#[derive(Clone, Serialize)]
+pub struct AutoDecision {
+    pub chosen: Mode,
+    pub reason: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_cost_us: Option<f64>,  // one item on this container
+    pub measured_now: bool,         // false when an earlier invocation's measurement was reused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_sequential_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_parallel_ms: Option<f64>,
+}
+
+// Pick sequential or parallel for `count` items: measure one item (`sample`), then compare
+// count x cost against ceil(count / parallelism) x cost plus the fixed parallel overhead, where
+// parallelism is the worker count limited by the CPU quota (more workers only time-slice it)
+pub fn choose(
+    key: CostKey, workload: &dyn Workload, sample: &str, count: usize, workers: usize,
+) -> Result<AutoDecision, Box<dyn std::error::Error + Send + Sync>> {
+    let trivial = |chosen, reason| AutoDecision {
+        chosen, reason, item_cost_us: None, measured_now: false, estimated_sequential_ms: None, estimated_parallel_ms: None,
+    };
+    if workers <= 1 { return Ok(trivial(Mode::Sequential, "single_worker")); }
+    if count <= 1 { return Ok(trivial(Mode::Sequential, "single_item")); }
+
+    let costs = ITEM_COST_US.get_or_init(|| Mutex::new(HashMap::new()));
+    let cached = costs.lock().unwrap().get(&key).copied();
+    let (item_cost_us, measured_now) = match cached {
+        Some(cost) => (cost, false),
+        None => {
+            let start = Instant::now();
+            workload.execute_item(sample)?;
+            let cost = start.elapsed().as_secs_f64() * 1_000_000.0;
+            costs.lock().unwrap().insert(key, cost);
+            (cost, true)
+        }
+    };
+
+    let sequential_us = count as f64 * item_cost_us;
+    let parallelism = (workers as f64).min(effective_cpus()).max(1.0);
+    let parallel_us = (count as f64 / parallelism).ceil() * item_cost_us + PARALLEL_OVERHEAD_US;
+    let (chosen, reason) = if parallel_us < sequential_us {
+        (Mode::Parallel, "parallel_faster")
+    } else {
+        (Mode::Sequential, if parallelism <= 1.0 { "single_cpu" } else { "overhead_dominates" })
+    };
+    Ok(AutoDecision {
+        chosen, reason, item_cost_us: Some(item_cost_us), measured_now,
+        estimated_sequential_ms: Some(sequential_us / 1000.0),
+        estimated_parallel_ms: Some(parallel_us / 1000.0),
+    })
+}
@@ -0,0 +1,109 @@
+// Offline runner: executes a request in-process with function_handler, without the Lambda runtime,
+// SAM or cargo-lambda, and prints the response, to iterate on workloads and modes locally
+//
+//   local_bench [--workers 1,2,4] ['{"mode": "parallel", "workload": "sha256", "count": 100}']
+//
+// The request is read from stdin when it is not given (or is "-"). With --workers it runs once per
+// count, as that run's `workers`; otherwise WORKER_COUNT and the detected CPUs apply as on Lambda
+use lambda_runtime::Context;
+use serde_json::Value;
+use std::io::Read;
+use std::process::ExitCode;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use rust_multithread_lambda::error::HandlerError;
+use rust_multithread_lambda::handler::function_handler;
+use rust_multithread_lambda::schema;
+use rust_multithread_lambda::{lifecycle, prewarm};
+
+const USAGE: &str = "usage: local_bench [--workers N[,N...]] [REQUEST_JSON | -]";
+
+// (worker counts, request argument)
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(Vec<usize>, Option<String>), String> {
+    let (mut workers, mut request) = (Vec::new(), None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--workers" => {
+                let counts = args.next().ok_or("--workers needs a value")?;
+                workers = counts.split(',')
+                    .map(|count| count.trim().parse::<usize>().map_err(|_| format!("--workers entry '{}' is not a count", count.trim())))
+                    .collect::<Result<_, _>>()?;
+            }
+            "-h" | "--help" => return Err("runs a ProcessRequest with function_handler and prints the response".to_string()),
+            _ if request.is_none() => request = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    Ok((workers, request))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    lifecycle::mark_process_start();
+    // Logs go to stderr, so stdout only holds the responses (level from RUST_LOG, default warn)
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::builder().with_default_directive(LevelFilter::WARN.into()).from_env_lossy())
+        .with_target(false)
+        .init();
+
+    let (workers, request) = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let request = match request {
+        Some(request) if request != "-" => request,
+        _ => {
+            let mut input = String::new();
+            if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+                eprintln!("cannot read the request from stdin: {}", e);
+                return ExitCode::from(2);
+            }
+            input
+        }
+    };
+    let mut payload = match serde_json::from_str::<Value>(&request) {
+        Ok(payload) if payload.is_object() => payload,
+        Ok(_) => {
+            eprintln!("the request must be a JSON object");
+            return ExitCode::from(2);
+        }
+        Err(e) => {
+            eprintln!("invalid JSON: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    // The same cold start as the Lambda entrypoint
+    if let Err(e) = prewarm::initialize().await {
+        eprintln!("init failed: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let runs: Vec<Option<usize>> = if workers.is_empty() { vec![None] } else { workers.into_iter().map(Some).collect() };
+    let mut failed = false;
+    for workers in runs {
+        if let Some(workers) = workers { payload["workers"] = workers.into(); }
+        let (version, request) = match schema::parse(payload.clone()) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("invalid request: {}", e);
+                return ExitCode::from(2);
+            }
+        };
+        lifecycle::begin_invocation();
+        // No deadline outside Lambda: every item runs
+        let response = function_handler(request, &Context::default()).await
+            .and_then(|response| schema::respond(version, response).map_err(|e| HandlerError::Io(e.into())));
+        match response {
+            Ok(response) => println!("{}", serde_json::to_string_pretty(&response).unwrap_or_default()),
+            Err(e) => {
+                eprintln!("{}", e.to_json());
+                failed = true;
+            }
+        }
+    }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
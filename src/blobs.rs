@@ -0,0 +1,80 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::{Bytes, BytesMut};
+use serde::de::{Deserializer, Error as _, SeqAccess, Visitor};
+use std::sync::Arc;
+use crate::workload::Workload;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// `items_base64`: every string is decoded straight into one shared buffer as the request is
+// parsed (its base64 text is dropped right after), and the items are slices of that buffer, so
+// a large payload costs one allocation and is never copied again on its way to the workers
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Bytes>>, D::Error> {
+    struct Blobs;
+
+    impl<'de> Visitor<'de> for Blobs {
+        type Value = Option<Vec<Bytes>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("an array of base64 strings")
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> { Ok(None) }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> { Ok(None) }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let (mut buffer, mut ends) = (BytesMut::new(), Vec::new());
+            while let Some(text) = seq.next_element::<String>()? {
+                let start = buffer.len();
+                buffer.resize(start + base64::decoded_len_estimate(text.len()), 0);
+                let len = STANDARD.decode_slice(&text, &mut buffer[start..])
+                    .map_err(|e| A::Error::custom(format!("items_base64[{}] is not valid base64: {}", ends.len(), e)))?;
+                buffer.truncate(start + len);
+                ends.push(buffer.len());
+            }
+            let buffer = buffer.freeze();
+            let starts = std::iter::once(0).chain(ends.iter().copied());
+            Ok(Some(starts.zip(ends.iter().copied()).map(|(start, end)| buffer.slice(start..end)).collect()))
+        }
+    }
+
+    deserializer.deserialize_option(Blobs)
+}
+
+// Runs `inner` on binary items: the job's items are indices into `blobs` (as s3_checksum's are
+// object keys), and each blob reaches the workload as a borrowed slice through execute_bytes
+pub struct BlobItems {
+    pub blobs: Arc<[Bytes]>,
+    pub inner: Arc<dyn Workload>,
+}
+
+impl BlobItems {
+    fn blob(&self, item: &str) -> Result<&Bytes, Error> {
+        item.parse::<usize>().ok().and_then(|index| self.blobs.get(index)).ok_or_else(|| format!("no blob '{}'", item).into())
+    }
+}
+
+impl Workload for BlobItems {
+    fn name(&self) -> &'static str { self.inner.name() }
+    fn working_set_bytes(&self) -> usize { self.inner.working_set_bytes() }
+    fn payload_len(&self, item: &str) -> usize { self.blob(item).map_or(0, Bytes::len) }
+
+    fn execute_item(&self, item: &str) -> Result<String, Error> {
+        self.inner.execute_bytes(self.blob(item)?)
+    }
+
+    // Only the byte-oriented workloads accept blobs that are not UTF-8, and they are deterministic
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Error> {
+        let blob = self.blob(item)?;
+        match std::str::from_utf8(blob) {
+            Ok(text) => self.inner.verify(text, output),
+            Err(_) => Ok(self.inner.execute_bytes(blob)? == output),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use crate::error::{invalid, HandlerError};
+use crate::handler::{CheckpointConfig, CheckpointSummary, ItemFailure, ItemResult, ItemSink, Returns, S3OutputConfig};
+
+// Items between checkpoints when the request does not say
+pub const DEFAULT_EVERY: usize = 1000;
+const DIR: &str = "/tmp";
+const PREFIX: &str = "checkpoint-";
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// What a resumed invocation needs from the earlier ones: every item before `next_offset` is done
+// (completed or in `failures`), and with return "checksum" their outputs, in index order, which
+// the digest of the whole job is computed over
+#[derive(Default, Serialize, Deserialize)]
+pub struct Saved {
+    job_id: String,
+    workload: String,
+    returns: Returns,
+    total_items: usize,
+    pub next_offset: usize,
+    pub failures: Vec<ItemFailure>,
+    pub outputs: Vec<String>,
+    written_at_ms: u64,
+}
+
+impl Saved {
+    pub fn completed(&self) -> usize {
+        self.next_offset - self.failures.len()
+    }
+}
+
+// Where a job's checkpoint lives: /tmp, which only a follow-up reaching the same warm container
+// finds, or the S3 object of `checkpoint.s3`, which any container does
+enum Store {
+    Tmp(String),
+    S3(S3OutputConfig),
+}
+
+impl Store {
+    fn new(config: &CheckpointConfig) -> Self {
+        match &config.s3 {
+            Some(target) => Store::S3(target.clone()),
+            None => Store::Tmp(format!("{}/{}{}.json", DIR, PREFIX, config.job_id)),
+        }
+    }
+
+    fn location(&self) -> String {
+        match self {
+            Store::Tmp(path) => path.clone(),
+            Store::S3(target) => format!("s3://{}/{}", target.bucket, target.key),
+        }
+    }
+
+    async fn load(&self) -> Result<Option<Saved>, Error> {
+        let bytes = match self {
+            Store::Tmp(path) => match std::fs::read(path) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            },
+            #[cfg(feature = "s3")]
+            Store::S3(target) => {
+                let result = crate::s3_handler::client().await.get_object().bucket(&target.bucket).key(&target.key).send().await;
+                match result {
+                    Ok(output) => output.body.collect().await?.to_vec(),
+                    Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+                    Err(e) => return Err(format!("{}: {}", self.location(), aws_sdk_s3::error::DisplayErrorContext(e)).into()),
+                }
+            }
+            #[cfg(not(feature = "s3"))]
+            Store::S3(_) => return Err(S3_FEATURE.into()),
+        };
+        Ok(Some(serde_json::from_slice(&bytes).map_err(|e| format!("{} is not a checkpoint: {}", self.location(), e))?))
+    }
+
+    // The /tmp file is replaced with a rename, so a timeout mid-write leaves the previous one
+    async fn save(&self, saved: &Saved) -> Result<(), Error> {
+        let body = serde_json::to_vec(saved)?;
+        match self {
+            Store::Tmp(path) => {
+                let partial = format!("{}.partial", path);
+                std::fs::write(&partial, body)?;
+                std::fs::rename(&partial, path)?;
+            }
+            #[cfg(feature = "s3")]
+            Store::S3(target) => {
+                crate::s3_handler::client().await.put_object()
+                    .bucket(&target.bucket).key(&target.key)
+                    .content_type("application/json")
+                    .body(aws_sdk_s3::primitives::ByteStream::from(body))
+                    .send().await
+                    .map_err(|e| format!("{}: {}", self.location(), aws_sdk_s3::error::DisplayErrorContext(e)))?;
+            }
+            #[cfg(not(feature = "s3"))]
+            Store::S3(_) => return Err(S3_FEATURE.into()),
+        }
+        Ok(())
+    }
+
+    async fn remove(&self) -> Result<(), Error> {
+        match self {
+            Store::Tmp(path) => match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+            #[cfg(feature = "s3")]
+            Store::S3(target) => {
+                crate::s3_handler::client().await.delete_object().bucket(&target.bucket).key(&target.key).send().await
+                    .map_err(|e| format!("{}: {}", self.location(), aws_sdk_s3::error::DisplayErrorContext(e)))?;
+            }
+            #[cfg(not(feature = "s3"))]
+            Store::S3(_) => return Err(S3_FEATURE.into()),
+        }
+        Ok(())
+    }
+}
+
+// Validation rejects checkpoint.s3 without the "s3" feature
+#[cfg(not(feature = "s3"))]
+const S3_FEATURE: &str = "checkpoint.s3 requires building with the \"s3\" feature";
+
+// The checkpoint of `config.job_id`, if an earlier invocation left one. It must be of the same job
+// (workload, item count and return); one at the end of the job is stale (its removal failed) and
+// the job starts over
+pub async fn restore(config: &CheckpointConfig, workload: &str, returns: Returns, total_items: usize) -> Result<Option<Saved>, HandlerError> {
+    let store = Store::new(config);
+    let Some(saved) = store.load().await.map_err(HandlerError::Io)? else { return Ok(None) };
+    if saved.workload != workload || saved.total_items != total_items || saved.returns != returns {
+        return Err(invalid("checkpoint.job_id", format!(
+            "the checkpoint of job {} at {} is of a different job ({} items of {}, return {})",
+            config.job_id, store.location(), saved.total_items, saved.workload, serde_json::json!(saved.returns),
+        )));
+    }
+    if saved.next_offset >= total_items { return Ok(None); }
+    tracing::info!(job_id = %config.job_id, location = store.location(), next_offset = saved.next_offset, "resuming from checkpoint");
+    Ok(Some(saved))
+}
+
+// Checkpoints a job while it runs: results arrive through the sink as they complete, and every
+// `every` of them the contiguous prefix of finished items (completed, or failed with on_error
+// "continue") is written as the new checkpoint. Items finish out of order, so the ones past a gap
+// wait for the next checkpoint after it closes. When the run ends the checkpoint is written once
+// more, or removed if the job is done
+pub struct Checkpointer {
+    sink: ItemSink,
+    task: JoinHandle<(CheckpointSummary, Vec<String>)>,
+}
+
+impl Checkpointer {
+    // `restored` is what the run resumes from (None for a new job) and `failures` the run's shared
+    // failure list; `forward` receives every item afterwards
+    pub fn start(config: CheckpointConfig, workload: String, returns: Returns, total_items: usize, restored: Option<Saved>,
+                 failures: Arc<Mutex<Vec<ItemFailure>>>, forward: Option<ItemSink>) -> Self {
+        let (sink, mut items) = mpsc::unbounded_channel::<ItemResult>();
+        let store = Store::new(&config);
+        let every = config.every.unwrap_or(DEFAULT_EVERY);
+        let resumed_from = restored.as_ref().map(|saved| saved.next_offset);
+        let mut saved = restored.unwrap_or(Saved { job_id: config.job_id, workload, returns, total_items, ..Saved::default() });
+        let keep_outputs = returns == Returns::Checksum;
+        let task = tokio::spawn(async move {
+            let mut finished = BTreeMap::new();
+            let (mut received, mut written, mut write_errors) = (0, 0, 0);
+            while let Some(item) = items.recv().await {
+                finished.insert(item.index, keep_outputs.then(|| item.output.clone()));
+                if let Some(forward) = &forward {
+                    let _ = forward.send(item);
+                }
+                received += 1;
+                if received % every == 0 {
+                    advance(&mut saved, &mut finished, &failures);
+                    match store.save(&saved).await {
+                        Ok(()) => written += 1,
+                        Err(e) => {
+                            write_errors += 1;
+                            tracing::warn!(location = store.location(), error = %e, "checkpoint write failed");
+                        }
+                    }
+                }
+            }
+            advance(&mut saved, &mut finished, &failures);
+            let complete = saved.next_offset >= saved.total_items;
+            let result = if complete { store.remove().await } else { store.save(&saved).await.map(|()| written += 1) };
+            if let Err(e) = result {
+                write_errors += 1;
+                tracing::warn!(location = store.location(), error = %e, "final checkpoint write failed");
+            }
+            let summary = CheckpointSummary {
+                job_id: saved.job_id, location: store.location(), every, resumed_from, next_offset: saved.next_offset,
+                complete, written, write_errors, wait_ms: 0.0,
+            };
+            // Every completed item's output in index order: the prefix, then the items past its gap
+            let mut outputs = saved.outputs;
+            outputs.extend(finished.into_values().flatten());
+            (summary, outputs)
+        });
+        Checkpointer { sink, task }
+    }
+
+    pub fn sink(&self) -> ItemSink {
+        self.sink.clone()
+    }
+
+    // Wait for the last write; call once processing is done and every sink clone is dropped. With
+    // return "checksum", also the outputs of every item the job completed so far, restored ones
+    // included, in index order
+    pub async fn finish(self) -> Result<(CheckpointSummary, Vec<String>), Error> {
+        drop(self.sink);
+        let start = Instant::now();
+        let (mut summary, outputs) = self.task.await?;
+        summary.wait_ms = start.elapsed().as_secs_f64() * 1000.0;
+        Ok((summary, outputs))
+    }
+}
+
+// Move the prefix past the items that finished since, with the failures it now covers
+fn advance(saved: &mut Saved, finished: &mut BTreeMap<usize, Option<String>>, failures: &Mutex<Vec<ItemFailure>>) {
+    let failed: HashMap<usize, ItemFailure> = failures.lock().unwrap().iter()
+        .filter(|failure| failure.index >= saved.next_offset).map(|failure| (failure.index, failure.clone())).collect();
+    loop {
+        let index = saved.next_offset;
+        if let Some(output) = finished.remove(&index) {
+            saved.outputs.extend(output);
+        } else if let Some(failure) = failed.get(&index) {
+            saved.failures.push(failure.clone());
+        } else {
+            break;
+        }
+        saved.next_offset += 1;
+    }
+    saved.written_at_ms = now_ms();
+}
+
+// Validation accepts job IDs of [A-Za-z0-9._-], which name the /tmp file as they are
+pub fn valid_job_id(job_id: &str) -> bool {
+    (1..=128).contains(&job_id.len()) && job_id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
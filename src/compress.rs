@@ -0,0 +1,99 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::sync::Arc;
+use crate::handler::CompressionSummary;
+use crate::registry::{register_workload, Objects, WorkloadExecutor};
+use crate::workload::{log_lines, Codec, CompressSettings, Workload, WorkloadParams};
+
+// compress_response: the response fields that grow with the batch, and the zstd level they are
+// compressed at (fast, as it runs on the response path)
+const RESPONSE_FIELDS: [&str; 4] = ["results", "failures", "verification", "thread_stats"];
+const RESPONSE_LEVEL: i32 = 3;
+
+// Compress a generated buffer of buffer_kib KiB with the requested codec and level: CPU-bound,
+// with a working set (window and match tables) that grows with the level
+// Every item compresses the same buffer, built once per request; the output is the compressed size
+pub struct Compress {
+    settings: CompressSettings,
+    buffer: Vec<u8>,
+}
+
+impl Compress {
+    pub fn new(settings: CompressSettings) -> Self {
+        Compress { settings, buffer: log_lines(settings.buffer_kib as usize * 1024) }
+    }
+}
+
+impl Workload for Compress {
+    fn name(&self) -> &'static str { "compress" }
+    // The compressed copy each in-flight item builds (about the input size at worst); the input
+    // itself is allocated once
+    fn working_set_bytes(&self) -> usize { self.buffer.len() }
+
+    fn execute_item(&self, _item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let CompressSettings { codec, level, .. } = self.settings;
+        let compressed = match codec {
+            Codec::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+                encoder.write_all(&self.buffer)?;
+                encoder.finish()?
+            }
+            Codec::Zstd => zstd::bulk::compress(&self.buffer, level as i32)?,
+            Codec::Brotli => {
+                // 4 MiB window (lgwin 22), brotli's default
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, level, 22);
+                encoder.write_all(&self.buffer)?;
+                encoder.into_inner()
+            }
+        };
+        Ok(compressed.len().to_string())
+    }
+}
+
+struct CompressExecutor;
+
+impl WorkloadExecutor for CompressExecutor {
+    fn name(&self) -> &'static str { "compress" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Compress::new(params.compress)))
+    }
+}
+
+register_workload!(CompressExecutor);
+
+// Ratio and aggregate throughput over the items that completed, from their outputs (compressed sizes)
+// mb_per_sec counts input megabytes (10^6 bytes) per second of processing across all workers
+pub fn summarize<'a>(settings: CompressSettings, outputs: impl Iterator<Item = &'a str>, processing_ms: f64) -> CompressionSummary {
+    let (mut items, mut output_bytes) = (0u64, 0u64);
+    for output in outputs {
+        items += 1;
+        output_bytes += output.parse::<u64>().unwrap_or_default();
+    }
+    let input_bytes = items * settings.buffer_kib as u64 * 1024;
+    CompressionSummary {
+        codec: settings.codec,
+        level: settings.level,
+        input_bytes,
+        output_bytes,
+        ratio: if output_bytes > 0 { input_bytes as f64 / output_bytes as f64 } else { 0.0 },
+        mb_per_sec: input_bytes as f64 / 1_000_000.0 / (processing_ms / 1000.0).max(f64::EPSILON),
+    }
+}
+
+// Move the RESPONSE_FIELDS present in a serialized response into `compressed`: one JSON object of
+// them, zstd-compressed and base64-encoded, and describe that in `encoding` (which held the
+// request's marker until now), so a client knows which fields to look for inside the blob
+pub fn encode_response(map: &mut Map<String, Value>) -> Result<(), serde_json::Error> {
+    let fields: Map<String, Value> = RESPONSE_FIELDS.iter().filter_map(|name| Some((name.to_string(), map.remove(*name)?))).collect();
+    let json = serde_json::to_vec(&fields)?;
+    let compressed = zstd::bulk::compress(&json, RESPONSE_LEVEL).map_err(serde_json::Error::io)?;
+    map.insert("encoding".to_string(), json!({
+        "codec": "zstd", "transfer": "base64", "fields": fields.keys().collect::<Vec<_>>(),
+        "uncompressed_bytes": json.len(), "compressed_bytes": compressed.len(),
+    }));
+    map.insert("compressed".to_string(), STANDARD.encode(&compressed).into());
+    Ok(())
+}
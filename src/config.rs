@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use crate::lifecycle::ConfigSummary;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// The settings an SSM parameter may override; each is read through var() wherever it is used
+#[cfg(feature = "ssm")]
+const TUNABLES: &[&str] = &["WORKER_COUNT", "WORKER_COUNT_MAX", "BCRYPT_COST", "MAX_ITEMS", "DEADLINE_MARGIN_MS", "PROGRESS_INTERVAL_SECS"];
+// Seconds a loaded set of parameters is used before the next invocation reloads it
+const DEFAULT_TTL_SECS: u64 = 300;
+
+// The parameters as last loaded, by setting name
+struct Snapshot {
+    values: HashMap<String, String>,
+    loaded: Instant,
+}
+
+static SNAPSHOT: RwLock<Option<Snapshot>> = RwLock::new(None);
+
+/// A setting: the SSM parameter when one is loaded, else the environment variable of that name
+/// Parameters win so that retuning a deployed function only takes a parameter update
+pub fn var(name: &str) -> Option<String> {
+    parameter(name).or_else(|| env::var(name).ok())
+}
+
+/// The loaded SSM value of a setting, without the environment fallback
+pub fn parameter(name: &str) -> Option<String> {
+    SNAPSHOT.read().unwrap().as_ref().and_then(|snapshot| snapshot.values.get(name).cloned())
+}
+
+fn ttl() -> Duration {
+    Duration::from_secs(env::var("CONFIG_TTL_SECS").ok().and_then(|secs| secs.parse().ok()).unwrap_or(DEFAULT_TTL_SECS))
+}
+
+/// Cold start: the parameters under CONFIG_SSM_PREFIX, if it is set; failing to read them fails the
+/// init, as a function that silently ran on its defaults would be benchmarked with the wrong settings
+pub async fn load() -> Result<Option<ConfigSummary>, Error> {
+    let Ok(prefix) = env::var("CONFIG_SSM_PREFIX") else { return Ok(None) };
+    let start = Instant::now();
+    let (values, ignored) = fetch(&prefix).await?;
+    let mut applied: Vec<String> = values.keys().cloned().collect();
+    applied.sort();
+    tracing::info!(prefix, applied = ?applied, ignored = ?ignored, "SSM parameters loaded");
+    *SNAPSHOT.write().unwrap() = Some(Snapshot { values, loaded: Instant::now() });
+    Ok(Some(ConfigSummary { uri: format!("ssm:{}", prefix), applied, ignored, fetch_ms: start.elapsed().as_secs_f64() * 1000.0 }))
+}
+
+/// Reload the parameters once CONFIG_TTL_SECS have passed (0: never); called as an invocation starts
+/// A failed reload keeps the previous values until the TTL passes again
+pub async fn refresh() {
+    let stale = match SNAPSHOT.read().unwrap().as_ref() {
+        Some(snapshot) => !ttl().is_zero() && snapshot.loaded.elapsed() >= ttl(),
+        None => false,
+    };
+    let Some(prefix) = env::var("CONFIG_SSM_PREFIX").ok().filter(|_| stale) else { return };
+    let result = fetch(&prefix).await;
+    let mut snapshot = SNAPSHOT.write().unwrap();
+    let Some(snapshot) = snapshot.as_mut() else { return };
+    snapshot.loaded = Instant::now();
+    match result {
+        Ok((values, _)) => {
+            if values != snapshot.values { tracing::info!(prefix, values = ?values, "SSM parameters changed"); }
+            snapshot.values = values;
+        }
+        Err(e) => tracing::warn!(prefix, error = %e, "SSM parameter refresh failed, keeping the previous values"),
+    }
+}
+
+// The tunables under `prefix` by setting name ("/bench/worker_count" is WORKER_COUNT), and the
+// names of the other parameters there, which are ignored
+#[cfg(feature = "ssm")]
+async fn fetch(prefix: &str) -> Result<(HashMap<String, String>, Vec<String>), Error> {
+    let client = client().await;
+    let (mut values, mut ignored) = (HashMap::new(), Vec::new());
+    let mut pages = client.get_parameters_by_path().path(prefix).with_decryption(true).into_paginator().send();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|e| format!("SSM {}: {}", prefix, aws_sdk_ssm::error::DisplayErrorContext(e)))?;
+        for parameter in page.parameters() {
+            let (Some(name), Some(value)) = (parameter.name(), parameter.value()) else { continue };
+            let setting = name.strip_prefix(prefix).unwrap_or(name).trim_start_matches('/').to_ascii_uppercase();
+            if TUNABLES.contains(&setting.as_str()) {
+                values.insert(setting, value.to_string());
+            } else {
+                ignored.push(name.to_string());
+            }
+        }
+    }
+    Ok((values, ignored))
+}
+
+#[cfg(not(feature = "ssm"))]
+async fn fetch(_prefix: &str) -> Result<(HashMap<String, String>, Vec<String>), Error> {
+    Err("CONFIG_SSM_PREFIX requires building with the \"ssm\" feature".into())
+}
+
+// SSM client is created once per container and reused by every refresh
+#[cfg(feature = "ssm")]
+async fn client() -> &'static aws_sdk_ssm::Client {
+    static SSM_CLIENT: tokio::sync::OnceCell<aws_sdk_ssm::Client> = tokio::sync::OnceCell::const_new();
+    SSM_CLIENT.get_or_init(|| async {
+        aws_sdk_ssm::Client::new(&aws_config::load_from_env().await)
+    }).await
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use std::fs;
+use std::sync::OnceLock;
+
+// The sandbox's CPU quota cannot change during its lifetime, so it is read once
+static EFFECTIVE_CPUS: OnceLock<f64> = OnceLock::new();
+// Linux reports utime and stime in USER_HZ ticks, fixed at 100 per second for user space
+const USER_HZ: f64 = 100.0;
+
+// Logical CPUs visible to the process
+pub fn detected_cpus() -> usize {
+    num_cpus::get()
+}
+
+// CPU time the cgroup quota allows per period, in CPUs (e.g. 1.5), capped at the detected count
+// A quota below the visible CPU count means extra threads only time-slice the same share
+pub fn effective_cpus() -> f64 {
+    *EFFECTIVE_CPUS.get_or_init(|| {
+        let detected = detected_cpus() as f64;
+        cgroup_quota().map_or(detected, |quota| quota.min(detected))
+    })
+}
+
+// CPU time (user + system) all of the process's threads have consumed so far, from /proc/self/stat,
+// with one tick (10 ms) of resolution; None where it is unavailable
+pub fn process_cpu_time_ms() -> Option<f64> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name (field 2) is parenthesized and may contain spaces; utime and stime are fields 14 and 15
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let utime = fields.next()?.parse::<f64>().ok()?;
+    let stime = fields.next()?.parse::<f64>().ok()?;
+    Some((utime + stime) * 1000.0 / USER_HZ)
+}
+
+// Context switches of the whole process. Voluntary ones are a thread blocking or yielding;
+// involuntary ones are the scheduler preempting it, which climbs when runnable threads outnumber
+// the CPUs the quota grants
+// /proc/self/status only counts the main thread, and summing /proc/self/task/*/status loses the
+// threads that exit in between (std_threads, channels), so this uses getrusage(RUSAGE_SELF), which
+// keeps the counts of exited threads
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct ContextSwitches {
+    pub voluntary: u64,
+    pub involuntary: u64,
+}
+
+impl ContextSwitches {
+    pub fn capture() -> Self {
+        // SAFETY: getrusage only writes the rusage struct it is given
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 { return ContextSwitches::default(); }
+        ContextSwitches { voluntary: usage.ru_nvcsw as u64, involuntary: usage.ru_nivcsw as u64 }
+    }
+
+    // Switches between an `earlier` capture and this one
+    pub fn since(self, earlier: ContextSwitches) -> Self {
+        ContextSwitches {
+            voluntary: self.voluntary.saturating_sub(earlier.voluntary),
+            involuntary: self.involuntary.saturating_sub(earlier.involuntary),
+        }
+    }
+}
+
+// cgroup v2 "cpu.max" holds "<quota> <period>" or "max <period>" when unlimited;
+// cgroup v1 splits them into cpu.cfs_quota_us (-1 when unlimited) and cpu.cfs_period_us
+fn cgroup_quota() -> Option<f64> {
+    if let Ok(cpu_max) = fs::read_to_string("/sys/fs/cgroup/cpu.max") {
+        let mut fields = cpu_max.split_whitespace();
+        let quota = fields.next()?.parse::<f64>().ok()?;
+        let period = fields.next()?.parse::<f64>().ok()?;
+        return (quota > 0.0 && period > 0.0).then(|| quota / period);
+    }
+    for dir in ["/sys/fs/cgroup/cpu", "/sys/fs/cgroup/cpu,cpuacct"] {
+        let read = |file: &str| fs::read_to_string(format!("{}/{}", dir, file)).ok()?.trim().parse::<f64>().ok();
+        if let (Some(quota), Some(period)) = (read("cpu.cfs_quota_us"), read("cpu.cfs_period_us")) {
+            return (quota > 0.0 && period > 0.0).then(|| quota / period);
+        }
+    }
+    None
+}
@@ -0,0 +1,110 @@
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+use crate::registry::{register_workload, ObjectSource, Objects, WorkloadExecutor};
+use crate::workload::{Workload, WorkloadParams};
+
+// Rows in the built-in orders table
+const EMBEDDED_ROWS: usize = 5_000;
+// Parsed records, Arrow columns and the Parquet buffer together, per CSV row (generous)
+const ESTIMATED_ROW_BYTES: usize = 512;
+
+// Generated once per container, so items only pay for parsing, conversion and encoding
+static EMBEDDED_CSV: OnceLock<String> = OnceLock::new();
+
+// Columnar ETL: parse a CSV document with a header row, infer each column's type, and write the
+// rows as one Snappy-compressed Parquet file in memory
+// Transform: header names become snake_case, fields are trimmed, and empty fields become nulls;
+// a column is Int64, Float64 or Boolean when every non-empty field parses as one, else Utf8
+// Items are S3 object keys when the request has `source_s3`, CSV documents when they contain a
+// line break, and otherwise select the embedded table. The output is
+// "<rows>x<columns>:<Parquet bytes>:<hex SHA-256 of the Parquet file>"
+pub struct CsvToParquet {
+    pub objects: Option<Objects>,
+}
+
+impl Workload for CsvToParquet {
+    fn name(&self) -> &'static str { "csv_to_parquet" }
+    fn working_set_bytes(&self) -> usize { EMBEDDED_ROWS * ESTIMATED_ROW_BYTES }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let csv = match &self.objects {
+            Some(objects) => objects.get(item).ok_or_else(|| format!("object '{}' was not downloaded", item))?.as_slice(),
+            None if item.contains('\n') => item.as_bytes(),
+            None => embedded_csv().as_bytes(),
+        };
+        let batch = parse(csv)?;
+        let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), Some(
+            WriterProperties::builder().set_compression(Compression::SNAPPY).build(),
+        ))?;
+        writer.write(&batch)?;
+        let parquet = writer.into_inner()?;
+        Ok(format!("{}x{}:{}:{:x}", batch.num_rows(), batch.num_columns(), parquet.len(), Sha256::digest(&parquet)))
+    }
+}
+
+struct CsvToParquetExecutor;
+
+impl WorkloadExecutor for CsvToParquetExecutor {
+    fn name(&self) -> &'static str { "csv_to_parquet" }
+    fn object_source(&self) -> ObjectSource { ObjectSource::Optional }
+
+    fn prepare(&self, _params: &WorkloadParams, objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(CsvToParquet { objects }))
+    }
+}
+
+register_workload!(CsvToParquetExecutor);
+
+fn parse(csv: &[u8]) -> Result<RecordBatch, Box<dyn std::error::Error + Send + Sync>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(csv);
+    let headers: Vec<String> = reader.headers()?.iter().map(snake_case).collect();
+    let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+
+    let (mut fields, mut columns) = (Vec::with_capacity(headers.len()), Vec::<ArrayRef>::with_capacity(headers.len()));
+    for (i, name) in headers.iter().enumerate() {
+        let values: Vec<Option<&str>> = records.iter().map(|record| record.get(i).filter(|value| !value.is_empty())).collect();
+        let all = |parses: fn(&str) -> bool| values.iter().flatten().all(|value| parses(value));
+        let (data_type, column): (DataType, ArrayRef) = if all(|value| value.parse::<i64>().is_ok()) {
+            (DataType::Int64, Arc::new(values.iter().map(|value| value.and_then(|v| v.parse::<i64>().ok())).collect::<Int64Array>()))
+        } else if all(|value| value.parse::<f64>().is_ok()) {
+            (DataType::Float64, Arc::new(values.iter().map(|value| value.and_then(|v| v.parse::<f64>().ok())).collect::<Float64Array>()))
+        } else if all(|value| value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")) {
+            (DataType::Boolean, Arc::new(values.iter().map(|value| value.map(|v| v.eq_ignore_ascii_case("true"))).collect::<BooleanArray>()))
+        } else {
+            (DataType::Utf8, Arc::new(values.iter().copied().collect::<StringArray>()))
+        };
+        fields.push(Field::new(name, data_type, true));
+        columns.push(column);
+    }
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+}
+
+// "Order ID" -> "order_id"
+fn snake_case(header: &str) -> String {
+    let words: Vec<String> = header.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    words.join("_")
+}
+
+fn embedded_csv() -> &'static str {
+    EMBEDDED_CSV.get_or_init(|| {
+        // Padded fields and empty discounts, so the trim and null handling have work to do
+        let mut csv = String::from("Order ID,Customer Name,Amount,Quantity,Discount,Paid,Created At\n");
+        for i in 0..EMBEDDED_ROWS {
+            let discount = if i % 7 == 0 { String::new() } else { format!("{:.2}", (i % 20) as f64 / 100.0) };
+            csv.push_str(&format!(
+                "{}, customer_{:04} ,{:.2},{},{},{},2024-01-{:02}T{:02}:{:02}:00Z\n",
+                100_000 + i, i % 1_000, (i * 7919 % 100_000) as f64 / 100.0, 1 + i % 9, discount,
+                i % 3 != 0, 1 + i % 28, i % 24, i % 60,
+            ));
+        }
+        csv
+    })
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+use std::env;
+use crate::cpu_info::{detected_cpus, effective_cpus};
+use crate::handler::{Mode, WorkerSource};
+use crate::lifecycle::{self, Lifecycle};
+use crate::memory;
+use crate::platform::{platform, Platform};
+use crate::pool::{report, PoolReport};
+use crate::schema::CURRENT_VERSION;
+use crate::validation::{limits, Limits};
+
+// Cargo features and whether this binary was built with them
+const FEATURES: &[(&str, bool)] = &[
+    ("s3", cfg!(feature = "s3")), ("http", cfg!(feature = "http")), ("dynamodb", cfg!(feature = "dynamodb")),
+    ("dev-server", cfg!(feature = "dev-server")), ("streaming", cfg!(feature = "streaming")),
+    ("image", cfg!(feature = "image")), ("parquet", cfg!(feature = "parquet")), ("compress", cfg!(feature = "compress")),
+    ("profiling", cfg!(feature = "profiling")), ("jemalloc", cfg!(feature = "jemalloc")), ("mimalloc", cfg!(feature = "mimalloc")),
+    ("telemetry", cfg!(feature = "telemetry")), ("protobuf", cfg!(feature = "protobuf")), ("msgpack", cfg!(feature = "msgpack")),
+    ("firehose", cfg!(feature = "firehose")), ("fanout", cfg!(feature = "fanout")), ("sns", cfg!(feature = "sns")),
+];
+
+// Result of mode "describe": what this deployment accepts, so an orchestrator can check a
+// workload, mode or size before sending it rather than learning from a validation error
+// Only workloads (and modes) this build can run are listed; `unavailable` names the feature each other workload needs
+#[derive(Serialize)]
+pub struct Capabilities {
+    mode: Mode,
+    workloads: Vec<&'static str>,
+    unavailable: serde_json::Map<String, serde_json::Value>,
+    modes: Vec<&'static str>,
+    schema_versions: Vec<u32>,
+    features: Vec<&'static str>,
+    limits: Limits,
+    workers: WorkerConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<PoolReport>,  // the global pool's settings; None if it failed to build
+    platform: &'static Platform,
+    build: Build,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+}
+
+// How the worker count of a request without `workers` is chosen in this execution environment
+#[derive(Serialize)]
+struct WorkerConfig {
+    workers: usize,
+    worker_source: WorkerSource,
+    pool_threads: usize,  // threads of the global Rayon pool, sized at init
+    detected_cpus: usize,
+    effective_cpus: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    worker_count: Option<String>,      // WORKER_COUNT (SSM parameter or environment)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    worker_count_max: Option<String>,  // WORKER_COUNT_MAX
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_size_mb: Option<u64>,       // AWS_LAMBDA_FUNCTION_MEMORY_SIZE, None outside Lambda
+    allocator: &'static str,
+}
+
+// Embedded by build.rs when the binary was compiled
+#[derive(Serialize)]
+struct Build {
+    version: &'static str,
+    git_sha: &'static str,
+    rustc: &'static str,
+    target: &'static str,
+    profile: &'static str,
+}
+
+pub fn capabilities(workers: usize, worker_source: WorkerSource) -> Capabilities {
+    Capabilities {
+        mode: Mode::Describe,
+        workloads: crate::registry::names(),
+        unavailable: crate::registry::unavailable().into_iter()
+            .map(|(name, feature)| (name.to_string(), format!("requires the \"{}\" feature", feature).into()))
+            .collect(),
+        modes: Mode::ALL.iter().filter(|mode| (**mode != Mode::Fanout || cfg!(feature = "fanout")) && (**mode != Mode::Reduce || cfg!(feature = "s3"))).map(|mode| mode.as_str()).collect(),
+        schema_versions: (1..=CURRENT_VERSION).collect(),
+        features: FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect(),
+        limits: limits(workers),
+        workers: WorkerConfig {
+            workers, worker_source,
+            pool_threads: rayon::current_num_threads(),
+            detected_cpus: detected_cpus(),
+            effective_cpus: effective_cpus(),
+            worker_count: crate::config::var("WORKER_COUNT"),
+            worker_count_max: crate::config::var("WORKER_COUNT_MAX"),
+            memory_size_mb: env::var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE").ok().and_then(|mb| mb.parse().ok()),
+            allocator: memory::ALLOCATOR_NAME,
+        },
+        pool: crate::handler::global_pool_config().cloned().map(report),
+        platform: platform(),
+        build: Build {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("BUILD_GIT_SHA"),
+            rustc: env!("BUILD_RUSTC_VERSION"),
+            target: env!("BUILD_TARGET"),
+            profile: if cfg!(debug_assertions) { "debug" } else { "release" },
+        },
+        lifecycle: lifecycle::current(),
+    }
+}
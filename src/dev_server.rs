@@ -0,0 +1,58 @@
+use axum::body::Bytes;
+use axum::http::header::{HeaderName, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use lambda_runtime::{Context, Diagnostic, Error};
+use serde_json::{json, Value};
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+use tracing::Instrument;
+use rust_multithread_lambda::error::invalid;
+use rust_multithread_lambda::lifecycle;
+use rust_multithread_lambda::router::route;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:9000";
+
+static INVOCATIONS: AtomicU64 = AtomicU64::new(0);
+// One invocation at a time, as in a Lambda execution environment; concurrent HTTP requests queue
+static INVOKE: Mutex<()> = Mutex::const_new(());
+
+// Serve invocations over local HTTP instead of polling the Lambda Runtime API: the request body is
+// the event, routed exactly as on Lambda (ProcessRequest, batch envelope, SQS, ...), and the
+// response body is the function's result. Both `/` and the Invoke API path answer, so
+// `aws lambda invoke --endpoint-url http://127.0.0.1:9000` works too
+pub async fn serve() -> Result<(), Error> {
+    let addr = env::var("DEV_SERVER_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let app = Router::new()
+        .route("/", post(invoke))
+        .route("/2015-03-31/functions/{function}/invocations", post(invoke));
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!(addr, "dev server listening");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn invoke(body: Bytes) -> Response {
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return function_error(invalid("event", format!("invalid JSON: {}", e)).into()),
+    };
+    let _invocation = INVOKE.lock().await;
+    let mut context = Context::default();
+    context.request_id = format!("dev-{}", INVOCATIONS.fetch_add(1, Ordering::Relaxed) + 1);
+    let lifecycle = lifecycle::begin_invocation();
+    let span = tracing::info_span!("invocation", request_id = %context.request_id, cold_start = lifecycle.cold_start);
+    match route(payload, &context).instrument(span).await {
+        Ok(response) => ([(CONTENT_TYPE, "application/json")], response.to_string()).into_response(),
+        Err(e) => function_error(e.into()),
+    }
+}
+
+// The Invoke API reports a function error with status 200, the X-Amz-Function-Error header and
+// {"errorType", "errorMessage"} as the body, which the AWS CLI and SDKs already understand
+fn function_error(diagnostic: Diagnostic) -> Response {
+    let body = json!({ "errorType": diagnostic.error_type, "errorMessage": diagnostic.error_message });
+    ([(CONTENT_TYPE, "application/json"), (HeaderName::from_static("x-amz-function-error"), "Unhandled")], body.to_string()).into_response()
+}
@@ -0,0 +1,65 @@
+use lambda_runtime::Diagnostic;
+use serde_json::{json, Value};
+// Public here, as a dependent crate's WorkloadExecutor::validate reports with it
+pub use crate::validation::ValidationError;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Why an invocation failed; `code` is reported as the Lambda errorType, so callers and Step
+/// Functions (Catch/Retry ErrorEquals) can branch on the kind of failure without parsing messages
+#[derive(Debug, thiserror::Error)]
+pub enum HandlerError {
+    /// Rejected request input: every violated rule, one entry per field
+    #[error("{}", .0.iter().map(|error| error.message.as_str()).collect::<Vec<_>>().join("; "))]
+    Validation(Vec<ValidationError>),
+    /// The workload failed on an item (or its verification)
+    #[error("{0}")]
+    Hashing(BoxError),
+    /// Reading or writing an AWS service (S3, DynamoDB) or the response itself failed
+    #[error("{0}")]
+    Io(BoxError),
+    /// The deadline passed before any item could start
+    #[error("deadline reached before any of the {skipped} items could start")]
+    Timeout { skipped: usize },
+    /// Mode "health" found the thread pool missing or not running work
+    #[error("{0}")]
+    Unhealthy(String),
+}
+
+/// A Validation error of one field
+pub fn invalid(field: &str, message: impl Into<String>) -> HandlerError {
+    HandlerError::Validation(vec![ValidationError::new(field, message)])
+}
+
+impl HandlerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            HandlerError::Validation(_) => "ValidationError",
+            HandlerError::Hashing(_) => "HashingError",
+            HandlerError::Io(_) => "IoError",
+            HandlerError::Timeout { .. } => "TimeoutError",
+            HandlerError::Unhealthy(_) => "UnhealthyError",
+        }
+    }
+
+    /// {"code", "error", "errors" | "details"}: the error (all messages joined), plus the field
+    /// violations of a ValidationError or the details of a TimeoutError
+    /// Also the body of HTTP error responses and of streamed error lines
+    pub fn to_json(&self) -> Value {
+        let mut body = json!({ "code": self.code(), "error": self.to_string() });
+        match self {
+            HandlerError::Validation(errors) => body["errors"] = json!(errors),
+            HandlerError::Timeout { skipped } => body["details"] = json!({ "skipped": skipped }),
+            _ => {}
+        }
+        body
+    }
+}
+
+// Lambda error response: errorType is the code, errorMessage the JSON above (a string, which
+// Step Functions can parse from the Cause with States.StringToJson)
+impl From<HandlerError> for Diagnostic {
+    fn from(error: HandlerError) -> Self {
+        Diagnostic { error_type: error.code().to_string(), error_message: error.to_json().to_string() }
+    }
+}
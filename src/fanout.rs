@@ -0,0 +1,202 @@
+use aws_sdk_lambda::operation::RequestId;
+use aws_sdk_lambda::primitives::Blob;
+use aws_sdk_lambda::types::InvocationType;
+use aws_sdk_lambda::Client;
+use lambda_runtime::Context;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+use tokio::task::JoinSet;
+use crate::error::{invalid, HandlerError};
+use crate::handler::{resolve_workers, HandlerResponse, Mode, ProcessRequest};
+use crate::validation::{max_items, validate, ValidationError};
+
+// Slices of one fanout job, and Invoke calls in flight at a time while dispatching them
+const MAX_INVOCATIONS: usize = 1000;
+const MAX_IN_FLIGHT: usize = 32;
+
+// Lambda client is created once per container and reused across invocations
+static LAMBDA_CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+pub async fn client() -> &'static Client {
+    LAMBDA_CLIENT.get_or_init(|| async {
+        Client::new(&aws_config::load_from_env().await)
+    }).await
+}
+
+/// Result of mode "fanout": the job was split into `invocations` continuation slices of up to
+/// `items_per_invocation` items, each invoked asynchronously on `function_name` in `slice_mode`.
+/// The slices run (and report, through their own outputs) after this response is returned; with
+/// results_s3, `reduce` is the request that aggregates their parts (send it as is, with a
+/// `wait_seconds` to wait for slices still running)
+#[derive(Serialize)]
+pub struct FanoutResponse {
+    mode: Mode,
+    function_name: String,
+    slice_mode: Mode,
+    workload: String,
+    total_items: usize,
+    invocations: usize,
+    items_per_invocation: usize,
+    dispatched: usize,  // accepted by Lambda (status 202)
+    failed: usize,
+    duration_ms: f64,
+    sub_invocations: Vec<SubInvocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reduce: Option<Value>,
+}
+
+// One slice: the request ID of its asynchronous invocation, or why Invoke failed
+#[derive(Serialize)]
+struct SubInvocation {
+    index: usize, offset: usize, limit: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The payload is needed as JSON, since every slice is the same request with its own mode and
+/// continuation (and idempotency key), so this runs from the router rather than function_handler
+pub async fn handle(payload: Value, context: &Context) -> Result<HandlerResponse, HandlerError> {
+    let start = Instant::now();
+    crate::config::refresh().await;
+    let request: ProcessRequest = serde_json::from_value(payload.clone()).map_err(|e| invalid("request", format!("invalid request: {}", e)))?;
+    let config = request.fanout.unwrap_or_default();
+    let slice_mode = config.mode.unwrap_or(Mode::Parallel);
+    let total = request.count;
+
+    let mut errors = Vec::new();
+    if matches!(slice_mode, Mode::Fanout | Mode::Reduce | Mode::Benchmark | Mode::Sweep | Mode::Stress | Mode::Describe | Mode::Health) {
+        errors.push(ValidationError::new("fanout.mode", format!("fanout.mode must be a processing mode, not {}", slice_mode.as_str())));
+    }
+    if request.items.is_some() || request.items_base64.is_some() || request.pairs.is_some() {
+        errors.push(ValidationError::new("items", "fanout mode splits generated items; send explicit items as separate requests"));
+    }
+    if request.continuation.is_some() {
+        errors.push(ValidationError::new("continuation", "fanout mode sets the continuation of every slice itself"));
+    }
+    for (field, set) in [("output_s3", request.output_s3.is_some()), ("spill_s3", request.spill_s3.is_some()), ("profile_s3", request.profile_s3.is_some())] {
+        if set {
+            errors.push(ValidationError::new(field, format!("every slice would overwrite the same {} object; use persist or output_firehose, which key results by request", field)));
+        }
+    }
+    if let Some(results) = &config.results_s3 {
+        if cfg!(not(feature = "s3")) {
+            errors.push(ValidationError::new("fanout.results_s3", "fanout.results_s3 requires building with the \"s3\" feature"));
+        }
+        if results.bucket.is_empty() { errors.push(ValidationError::new("fanout.results_s3.bucket", "fanout.results_s3.bucket must not be empty")); }
+    }
+    if request.partial_s3.is_some() {
+        errors.push(ValidationError::new("partial_s3", "fanout mode sets the partial_s3 of every slice from fanout.results_s3"));
+    }
+    let function_name = config.function_name.or_else(|| std::env::var("AWS_LAMBDA_FUNCTION_NAME").ok()).filter(|name| !name.is_empty());
+    if function_name.is_none() {
+        errors.push(ValidationError::new("fanout.function_name", "fanout.function_name is required outside Lambda"));
+    }
+    // By default every slice is as large as MAX_ITEMS allows
+    let invocations = config.invocations.unwrap_or_else(|| total.div_ceil(max_items()).max(1));
+    if !(1..=MAX_INVOCATIONS).contains(&invocations) {
+        errors.push(ValidationError::new("fanout.invocations", format!("fanout.invocations must be between 1 and {}", MAX_INVOCATIONS)));
+    } else if invocations > total.max(1) {
+        errors.push(ValidationError::new("fanout.invocations", format!("fanout.invocations of {} exceeds count {}", invocations, total)));
+    }
+    if !errors.is_empty() { return Err(HandlerError::Validation(errors)); }
+    let function_name = function_name.unwrap_or_default();
+
+    // A few items over an even split would leave the last slice empty, so the slice count follows
+    // from the slice size
+    let limit = total.div_ceil(invocations).max(1);
+    // The parts of this job go under its own prefix, named after the fanout's request ID
+    let job_prefix = config.results_s3.as_ref().map(|results| {
+        let job_id = if context.request_id.is_empty() { format!("local-{}", now_ms()) } else { context.request_id.clone() };
+        match results.prefix.trim_end_matches('/') {
+            "" => format!("{}/", job_id),
+            prefix => format!("{}/{}/", prefix, job_id),
+        }
+    });
+    let slice = |index: usize, offset: usize| {
+        let mut slice = payload.clone();
+        if let Some(fields) = slice.as_object_mut() {
+            fields.remove("fanout");
+            fields.insert("mode".to_string(), slice_mode.as_str().into());
+            fields.insert("continuation".to_string(), json!({ "offset": offset, "limit": limit }));
+            if let Some(key) = &request.idempotency_key {
+                fields.insert("idempotency_key".to_string(), format!("{}#{}", key, index).into());
+            }
+            // Validation rejects results_s3 without the "s3" feature, which the map-reduce side needs
+            #[cfg(feature = "s3")]
+            if let (Some(results), Some(prefix)) = (&config.results_s3, &job_prefix) {
+                fields.insert("partial_s3".to_string(), json!({ "bucket": results.bucket, "key": crate::mapreduce::part_key(prefix, index) }));
+            }
+        }
+        slice
+    };
+    // Every slice differs only in its offset, so the first one stands for all of them
+    let first: ProcessRequest = serde_json::from_value(slice(0, 0)).map_err(|e| invalid("request", format!("invalid request: {}", e)))?;
+    validate(&first, request.workers.unwrap_or_else(|| resolve_workers().0), false)?;
+
+    let client = client().await;
+    let dispatched_at_ms = now_ms();
+    let mut calls = JoinSet::new();
+    let mut sub_invocations = Vec::with_capacity(invocations);
+    for (index, offset) in (0..total).step_by(limit).enumerate() {
+        if calls.len() == MAX_IN_FLIGHT {
+            sub_invocations.extend(calls.join_next().await.transpose().map_err(|e| HandlerError::Io(e.into()))?);
+        }
+        let body = serde_json::to_vec(&slice(index, offset)).map_err(|e| HandlerError::Io(e.into()))?;
+        calls.spawn(invoke(client, function_name.clone(), SubInvocation { index, offset, limit: limit.min(total - offset), request_id: None, status_code: None, error: None }, body));
+    }
+    while let Some(sub) = calls.join_next().await {
+        sub_invocations.push(sub.map_err(|e| HandlerError::Io(e.into()))?);
+    }
+    sub_invocations.sort_by_key(|sub| sub.index);
+
+    let failed = sub_invocations.iter().filter(|sub| sub.error.is_some()).count();
+    if failed == sub_invocations.len() {
+        let error = sub_invocations.first().and_then(|sub| sub.error.clone()).unwrap_or_default();
+        return Err(HandlerError::Io(format!("no slice could be invoked on {}: {}", function_name, error).into()));
+    }
+    let invocations = sub_invocations.len();
+    tracing::info!(request_id = %context.request_id, function_name, invocations, failed, "fanout dispatched");
+    Ok(HandlerResponse::Fanout(Box::new(FanoutResponse {
+        mode: Mode::Fanout,
+        function_name,
+        slice_mode,
+        workload: request.workload,
+        total_items: total,
+        invocations,
+        items_per_invocation: limit,
+        dispatched: invocations - failed,
+        failed,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        sub_invocations,
+        reduce: config.results_s3.zip(job_prefix).map(|(results, prefix)| json!({
+            "mode": "reduce",
+            "reduce": { "bucket": results.bucket, "prefix": prefix, "parts": invocations, "dispatched_at_ms": dispatched_at_ms },
+        })),
+    })))
+}
+
+// InvocationType Event queues the slice and returns at once with status 202 and its request ID
+async fn invoke(client: &'static Client, function_name: String, mut sub: SubInvocation, body: Vec<u8>) -> SubInvocation {
+    match client.invoke().function_name(&function_name).invocation_type(InvocationType::Event).payload(Blob::new(body)).send().await {
+        Ok(output) => {
+            sub.request_id = output.request_id().map(str::to_string);
+            sub.status_code = Some(output.status_code());
+        }
+        Err(e) => {
+            let error = aws_sdk_lambda::error::DisplayErrorContext(e).to_string();
+            tracing::warn!(index = sub.index, offset = sub.offset, error, "slice invocation failed");
+            sub.error = Some(error);
+        }
+    }
+    sub
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
@@ -0,0 +1,216 @@
+use apache_avro::headers::{GlueSchemaUuidHeader, RabinFingerprintHeader};
+use apache_avro::types::Value as Avro;
+use apache_avro::{GenericSingleObjectWriter, Schema, Uuid};
+use aws_sdk_firehose::primitives::Blob;
+use aws_sdk_firehose::types::Record;
+use aws_sdk_firehose::Client;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, OnceCell};
+use tokio::task::{JoinHandle, JoinSet};
+use crate::handler::{FirehoseConfig, FirehoseSummary, ItemResult, ItemSink};
+use crate::rate_limit::RateLimiter;
+
+// PutRecordBatch accepts at most 500 records and 4 MiB per call, and 1,000 KiB per record
+const BATCH_RECORDS: usize = 500;
+const BATCH_BYTES: usize = 4 * 1024 * 1024;
+const MAX_RECORD_BYTES: usize = 1000 * 1024;
+// Attempts per batch while Firehose keeps failing some of its records (throttling)
+const MAX_ATTEMPTS: u32 = 5;
+
+// The schema records are written with when the request brings none; a provided schema may use
+// any subset of these fields (see `record`), and declare others with a default or as nullable
+const DEFAULT_SCHEMA: &str = r#"{
+  "type": "record", "name": "ItemResult", "namespace": "rust_multithread_lambda",
+  "fields": [
+    {"name": "request_id", "type": "string"},
+    {"name": "index", "type": "long"},
+    {"name": "output", "type": "string"},
+    {"name": "duration_us", "type": "long"},
+    {"name": "thread_id", "type": "string"},
+    {"name": "workload", "type": "string"},
+    {"name": "mode", "type": "string"},
+    {"name": "workers", "type": "int"},
+    {"name": "architecture", "type": "string"}
+  ]
+}"#;
+
+// Firehose client is created once per container and reused across invocations
+static FIREHOSE_CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub async fn client() -> &'static Client {
+    FIREHOSE_CLIENT.get_or_init(|| async {
+        Client::new(&aws_config::load_from_env().await)
+    }).await
+}
+
+// What every record of a run shares, next to the fields of its ItemResult
+pub struct RunFields {
+    pub request_id: String, pub workload: String, pub mode: String, pub workers: usize,
+}
+
+// The schema of a request and how its records are framed: Avro single-object encoding (a
+// 0xC3 0x01 marker and the schema's Rabin fingerprint), or with `schema_version_id` the Glue
+// Schema Registry header (bytes 3, 0 and the schema version UUID) its deserializers expect
+struct Encoder {
+    schema: Schema,
+    writer: GenericSingleObjectWriter,
+}
+
+impl Encoder {
+    // Errors name the field of output_firehose they are about
+    fn new(config: &FirehoseConfig) -> Result<Self, (&'static str, String)> {
+        let schema = match &config.schema {
+            Some(schema) => Schema::parse(schema),
+            None => Schema::parse_str(DEFAULT_SCHEMA),
+        }.map_err(|e| ("schema", format!("invalid Avro schema: {}", e)))?;
+        if !matches!(schema, Schema::Record(_)) { return Err(("schema", "the Avro schema must be a record".to_string())); }
+        let writer = match &config.schema_version_id {
+            Some(id) => {
+                let uuid = Uuid::parse_str(id).map_err(|e| ("schema_version_id", format!("schema_version_id is not a UUID: {}", e)))?;
+                GenericSingleObjectWriter::new_with_capacity_and_header_builder(&schema, 256, GlueSchemaUuidHeader::from_uuid(uuid))
+            }
+            None => GenericSingleObjectWriter::new_with_capacity_and_header_builder(&schema, 256, RabinFingerprintHeader::from_schema(&schema)),
+        }.map_err(|e| ("schema", e.to_string()))?;
+        Ok(Encoder { schema, writer })
+    }
+
+    // Resolution against the schema keeps the fields it declares, widens int to long (and the
+    // like), wraps values of nullable unions and fills in defaults; a declared field that is
+    // neither available nor defaulted fails it
+    fn encode(&mut self, fields: &RunFields, item: &ItemResult) -> Result<Vec<u8>, Error> {
+        let value = record(fields, item).resolve(&self.schema)?;
+        let mut bytes = Vec::new();
+        self.writer.write_value(value, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+// Check a request's schema before running anything: it parses, is a record, and a record of
+// this function resolves against it
+pub fn check(config: &FirehoseConfig) -> Result<(), (&'static str, String)> {
+    let mut encoder = Encoder::new(config)?;
+    let fields = RunFields { request_id: String::new(), workload: String::new(), mode: String::new(), workers: 1 };
+    let item = ItemResult { index: 0, output: String::new(), duration_us: 0, thread_id: String::new() };
+    encoder.encode(&fields, &item).map(drop).map_err(|e| ("schema", format!("the Avro schema does not fit the item results: {}", e)))
+}
+
+fn record(fields: &RunFields, item: &ItemResult) -> Avro {
+    Avro::Record(vec![
+        ("request_id".to_string(), Avro::String(fields.request_id.clone())),
+        ("index".to_string(), Avro::Long(item.index as i64)),
+        ("output".to_string(), Avro::String(item.output.clone())),
+        ("duration_us".to_string(), Avro::Long(item.duration_us as i64)),
+        ("thread_id".to_string(), Avro::String(item.thread_id.clone())),
+        ("workload".to_string(), Avro::String(fields.workload.clone())),
+        ("mode".to_string(), Avro::String(fields.mode.clone())),
+        ("workers".to_string(), Avro::Int(fields.workers as i32)),
+        ("architecture".to_string(), Avro::String(std::env::consts::ARCH.to_string())),
+    ])
+}
+
+// Sends item results to a Firehose delivery stream while the workers are still hashing: results
+// arrive through the sink as they complete, are encoded as Avro records, and every full batch is
+// sent by its own tokio task
+pub struct FirehoseWriter {
+    delivery_stream: String,
+    sink: ItemSink,
+    task: JoinHandle<Result<Sent, Error>>,
+}
+
+#[derive(Default)]
+struct Sent {
+    records: usize, batches: usize, failed: usize, bytes: usize,
+}
+
+impl FirehoseWriter {
+    // `forward` receives every item afterwards (the next sink, when there is one), and every
+    // PutRecordBatch call waits for `limiter`
+    pub fn start(config: FirehoseConfig, fields: RunFields, limiter: RateLimiter, forward: Option<ItemSink>) -> Result<Self, Error> {
+        let mut encoder = Encoder::new(&config).map_err(|(_, e)| e)?;
+        let (sink, mut items) = mpsc::unbounded_channel::<ItemResult>();
+        let stream = config.delivery_stream.clone();
+        let task = tokio::spawn(async move {
+            let client = client().await;
+            let mut sends = JoinSet::new();
+            let mut batch = Vec::with_capacity(BATCH_RECORDS);
+            let (mut batch_bytes, mut sent) = (0, Sent::default());
+            while let Some(item) = items.recv().await {
+                let bytes = encoder.encode(&fields, &item)?;
+                if let Some(forward) = &forward {
+                    let _ = forward.send(item);
+                }
+                if bytes.len() > MAX_RECORD_BYTES {
+                    tracing::warn!(bytes = bytes.len(), "Avro record over the Firehose record limit, not sent");
+                    sent.failed += 1;
+                    continue;
+                }
+                if batch.len() == BATCH_RECORDS || batch_bytes + bytes.len() > BATCH_BYTES {
+                    sends.spawn(send_batch(client, limiter.clone(), stream.clone(), std::mem::take(&mut batch)));
+                    sent.batches += 1;
+                    batch_bytes = 0;
+                }
+                batch_bytes += bytes.len();
+                sent.bytes += bytes.len();
+                batch.push(Record::builder().data(Blob::new(bytes)).build()?);
+            }
+            if !batch.is_empty() {
+                sends.spawn(send_batch(client, limiter.clone(), stream.clone(), batch));
+                sent.batches += 1;
+            }
+
+            while let Some(result) = sends.join_next().await {
+                let (records, failed) = result??;
+                sent.records += records;
+                sent.failed += failed;
+            }
+            Ok(sent)
+        });
+        Ok(FirehoseWriter { delivery_stream: config.delivery_stream, sink, task })
+    }
+
+    pub fn sink(&self) -> ItemSink {
+        self.sink.clone()
+    }
+
+    // Wait for the outstanding sends; call once processing is done and every sink clone is dropped
+    pub async fn finish(self) -> Result<FirehoseSummary, Error> {
+        drop(self.sink);
+        let start = Instant::now();
+        let sent = self.task.await??;
+        if sent.failed > 0 {
+            tracing::warn!(delivery_stream = %self.delivery_stream, failed = sent.failed, "records not delivered after retries");
+        }
+        Ok(FirehoseSummary {
+            delivery_stream: self.delivery_stream,
+            records_sent: sent.records,
+            batches: sent.batches,
+            failed: sent.failed,
+            bytes: sent.bytes,
+            wait_ms: start.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+// Send one batch, resubmitting the records Firehose failed with exponential backoff; the
+// responses line up with the records sent, so the failed ones are picked out by position
+// Returns (sent, still failed)
+async fn send_batch(client: &'static Client, limiter: RateLimiter, stream: String, records: Vec<Record>) -> Result<(usize, usize), Error> {
+    let total = records.len();
+    let mut pending = records;
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(50 << attempt)).await;
+        }
+        limiter.acquire().await;
+        let output = client.put_record_batch().delivery_stream_name(&stream).set_records(Some(pending.clone())).send().await
+            .map_err(|e| format!("{}: {}", stream, aws_sdk_firehose::error::DisplayErrorContext(e)))?;
+        if output.failed_put_count == 0 { return Ok((total, 0)); }
+        pending = pending.into_iter().zip(output.request_responses)
+            .filter(|(_, response)| response.error_code.is_some())
+            .map(|(record, _)| record)
+            .collect();
+    }
+    Ok((total - pending.len(), pending.len()))
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use crate::workload::splitmix64;
+
+// The items every request without `items` generated before templates existed ("password_000042")
+pub const DEFAULT_TEMPLATE: &str = "password_{index}";
+// Longest accepted item_template, and the accepted item_length
+pub const MAX_TEMPLATE_LEN: usize = 256;
+pub const ITEM_LENGTH_RANGE: std::ops::RangeInclusive<usize> = 1..=1024 * 1024;
+// Characters of `{random}` when the request sets no item_length
+const DEFAULT_RANDOM_LEN: usize = 16;
+const ALPHABET: &[u8; 62] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+// Synthetic items for requests without `items`: `template` with `{index}` replaced by the item's
+// index (zero-padded to six digits) and `{random}` by alphanumerics drawn from a generator seeded
+// by `seed` and the index, so a request yields the same items in every container and every slice
+// With `length`, every item is exactly that many bytes: `{random}` (appended when the template has
+// none) fills what the rest of the template leaves, and a longer template is cut
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+pub struct ItemGenerator {
+    pub template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+    pub seed: u64,
+}
+
+impl Default for ItemGenerator {
+    fn default() -> Self {
+        ItemGenerator { template: DEFAULT_TEMPLATE.to_string(), length: None, seed: 0 }
+    }
+}
+
+impl ItemGenerator {
+    pub fn item(&self, index: usize) -> String {
+        let index_text = format!("{:06}", index);
+        let (head, tail) = match self.template.split_once("{random}") {
+            Some((head, tail)) => (head.replace("{index}", &index_text), Some(tail.replace("{index}", &index_text))),
+            None => (self.template.replace("{index}", &index_text), None),
+        };
+        let fixed = head.len() + tail.as_ref().map_or(0, String::len);
+        let random = match (self.length, &tail) {
+            (Some(length), _) => length.saturating_sub(fixed),
+            (None, Some(_)) => DEFAULT_RANDOM_LEN,
+            (None, None) => 0,
+        };
+        let mut item = String::with_capacity(fixed + random);
+        item.push_str(&head);
+        // Eight characters per draw; the modulo bias is irrelevant for benchmark input
+        let mut state = self.seed ^ (index as u64).wrapping_mul(0xd1b5_4a32_d192_ed03);
+        let mut remaining = random;
+        while remaining > 0 {
+            let bytes = splitmix64(&mut state).to_le_bytes();
+            for byte in &bytes[..remaining.min(8)] { item.push(ALPHABET[*byte as usize % ALPHABET.len()] as char); }
+            remaining = remaining.saturating_sub(8);
+        }
+        if let Some(tail) = tail { item.push_str(&tail); }
+        // Validation keeps templates ASCII, so any length is a character boundary
+        if let Some(length) = self.length { item.truncate(length); }
+        item
+    }
+}
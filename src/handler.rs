@@ -1,114 +1,2302 @@
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
-use std::sync::Once;
-use std::time::Instant;
-use std::collections::HashSet;
-use std::sync::Mutex;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use lambda_runtime::Context;
+use futures::stream::{FuturesUnordered, StreamExt};
+use hdrhistogram::Histogram;
+use lru::LruCache;
 use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+use tracing::{info_span, Instrument};
+use crate::adaptive::{self, AutoDecision};
+use crate::blobs::BlobItems;
+use crate::pepper::{PepperSummary, Peppered};
+use crate::describe::Capabilities;
+use crate::config;
+use crate::cpu_info::{detected_cpus, effective_cpus, process_cpu_time_ms, ContextSwitches};
+use crate::error::HandlerError;
+use crate::generator::ItemGenerator;
+use crate::lifecycle::{self, Lifecycle};
+use crate::memory::{self, MemoryGuard, MemoryStats};
+use crate::spill::{Spill, SpillSummary};
+use crate::metrics::{self, Metric};
+use crate::nested::NestingSummary;
+use crate::platform::{platform, Platform};
+use crate::pool::PoolConfig;
+use crate::progress::{progress_interval, Heartbeat};
+use crate::validation::{validate, Validated};
+use crate::xray;
+use crate::workload::{Codec, TunedParams, Workload};
 
-static INIT: Once = Once::new();
+// Outcome of building the global Rayon pool at cold start: its configuration, or why it failed
+static GLOBAL_POOL: OnceLock<Result<PoolConfig, String>> = OnceLock::new();
 
-#[derive(Deserialize)]
+const DEFAULT_DEADLINE_MARGIN_MS: u64 = 1000;
+// Longest wait between two attempts of an item, however large the exponential backoff grows
+const MAX_RETRY_DELAY_MS: u64 = 10_000;
+// Mode "channels": slots per consumer in the work and result channels (the producer's lead)
+const CHANNEL_SLOTS_PER_WORKER: usize = 2;
+// Mode "stress": most windows in the throughput timeline (900 seconds are 15-second windows)
+const MAX_STRESS_INTERVALS: u64 = 60;
+// Lambda allocates vCPUs in proportion to memory: one full vCPU per 1,769 MB, at most 6 (10,240 MB)
+const MB_PER_VCPU: usize = 1769;
+const MAX_LAMBDA_VCPUS: usize = 6;
+// Responses kept per container for requests with `cache: true`
+const RESULT_CACHE_CAPACITY: usize = 32;
+// Jobs accepted in one batch envelope
+pub(crate) const MAX_BATCH_JOBS: usize = 64;
+// Responses kept per container for requests with an `idempotency_key`
+const IDEMPOTENCY_CAPACITY: usize = 256;
+
+// Lives as long as the execution environment, so warm invocations see earlier results
+static RESULT_CACHE: OnceLock<Mutex<LruCache<CacheKey, ProcessResponse>>> = OnceLock::new();
+// Completed responses by idempotency key, with the fingerprint of the request that ran, replayed
+// to retries that reach the same container
+static IDEMPOTENCY_STORE: OnceLock<Mutex<LruCache<String, (String, ProcessResponse)>>> = OnceLock::new();
+// Sequential ms per item from earlier invocations in this environment (sequential or benchmark mode)
+static SEQUENTIAL_BASELINE: OnceLock<Mutex<HashMap<BaselineKey, f64>>> = OnceLock::new();
+
+/// One invocation's work: the workload, its items (given, generated or read from S3) and how they
+/// run; every field but `mode` has a default. validation.rs checks the whole request up front
+#[derive(Deserialize, Serialize)]
 pub struct ProcessRequest {
-    count: usize, mode: String,
+    /// Absent or 2 (schema::CURRENT_VERSION); v1 requests are up-converted before they get here
+    pub schema_version: Option<u32>,
+    #[serde(default)]
+    pub count: usize,
+    pub mode: Mode,
+    #[serde(default = "default_workload")]
+    pub workload: String,
+    /// "hash" (default) runs the workload on every item; "verify" checks bcrypt `pairs` instead
+    #[serde(default)]
+    pub operation: Operation,
+    /// Caller-supplied payloads; when present, `count` is ignored
+    pub items: Option<Vec<String>>,
+    /// Binary items as base64 strings, decoded once into a shared buffer (blobs.rs); as `items`
+    #[serde(default, deserialize_with = "crate::blobs::deserialize", skip_serializing)]
+    pub items_base64: Option<Vec<Bytes>>,
+    /// Generated items (no `items`): a template with {index} and {random} placeholders, the exact
+    /// byte length of every item, and the seed of the {random} characters (see generator.rs)
+    pub item_template: Option<String>,
+    pub item_length: Option<usize>,
+    pub seed: Option<u64>,
+    /// "fail_fast" (default): the first failed item fails the request; "continue": report it in `failures`
+    #[serde(default)]
+    pub on_error: OnError,
+    /// Attempts per item, with exponential backoff between them; without it every item runs once
+    pub retry: Option<RetryPolicy>,
+    /// (password, hash) pairs for operation "verify", which take the place of `items`
+    pub pairs: Option<Vec<VerifyPair>>,
+    /// Items per Rayon task; when set, parallel runs use par_chunks instead of par_iter
+    pub chunk_size: Option<usize>,
+    #[serde(default)]
+    pub return_items: bool,
+    /// What the response carries of the outputs: "summary" (default), "items" (as return_items)
+    /// or "checksum", one digest of every output instead of the outputs themselves
+    #[serde(default, rename = "return")]
+    pub returns: Returns,
+    /// Move `results` and the other per-item fields into one zstd-compressed, base64 blob
+    /// (requires the "compress" feature)
+    #[serde(default)]
+    pub compress_response: bool,
+    /// bcrypt cost factor (4-14); defaults to BCRYPT_COST env var or 10
+    pub cost: Option<u32>,
+    /// Argon2id parameters; each defaults to the argon2 crate's recommended value
+    pub memory_kib: Option<u32>,
+    pub iterations: Option<u32>,
+    pub lanes: Option<u32>,
+    /// PBKDF2-HMAC-SHA256 rounds; defaults to the pbkdf2 crate's recommended 600,000
+    pub rounds: Option<u32>,
+    /// scrypt parameters (cost 2^log_n, block size r, parallelization p); default to the scrypt crate's
+    pub log_n: Option<u32>,
+    pub r: Option<u32>,
+    pub p: Option<u32>,
+    /// Longest thumbnail edge in pixels for image_resize (16-4096, default 256)
+    pub max_dimension: Option<u32>,
+    /// compress codec ("gzip", "zstd" or "brotli", default zstd), its level, and KiB per item (default 1024)
+    pub codec: Option<Codec>,
+    pub level: Option<u32>,
+    pub buffer_kib: Option<u32>,
+    /// Random points per monte_carlo item (1000-100,000,000, default 1,000,000)
+    pub samples: Option<u32>,
+    /// Largest bit length of the smaller factorize prime (8-31, default 28)
+    pub factor_bits: Option<u32>,
+    /// Matrix dimension N for matmul / matrix_multiply (16-2048, default 256)
+    pub matrix_size: Option<u32>,
+    /// Salts nested_hash hashes every item with, split across the pool with rayon::join (1-256, default 16)
+    pub salts: Option<u32>,
+    /// Regular expressions the grep workload counts matching lines for (1-16)
+    pub patterns: Option<Vec<String>>,
+    /// json_transform projection: output field -> JSON Pointer of the source value, in output order
+    pub mapping: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Serve an identical earlier request from this container's result cache
+    #[serde(default)]
+    pub cache: bool,
+    /// Client-chosen request ID: a repeated key returns the stored response instead of running again
+    pub idempotency_key: Option<String>,
+    /// Write every item result to this DynamoDB table (requires the "dynamodb" feature)
+    pub persist: Option<PersistConfig>,
+    /// Send every item result as an Avro record to this Firehose delivery stream (requires the "firehose" feature)
+    pub output_firehose: Option<FirehoseConfig>,
+    /// Publish a completion message (job id, counts, duration, where the results went) to this SNS
+    /// topic once processing finishes (requires the "sns" feature)
+    pub notify_topic_arn: Option<String>,
+    /// Calls per second the persist, output_firehose, output_s3 and source_s3 requests may make
+    /// together; defaults to DOWNSTREAM_RPS, unlimited without it (rate_limit.rs)
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Bucket the s3_checksum workload downloads `items` (object keys) from (requires the "s3" feature)
+    pub source_s3: Option<S3SourceConfig>,
+    /// Write results to S3 as gzip NDJSON instead of inline (requires the "s3" feature)
+    pub output_s3: Option<S3OutputConfig>,
+    /// Upload the /tmp file of a return "items" batch whose results spilled (requires the "s3" feature)
+    pub spill_s3: Option<S3OutputConfig>,
+    /// Sample the processing phase with pprof and write the report to /tmp (requires the "profiling"
+    /// feature), in `profile_format`, also uploaded to `profile_s3` when set (requires "s3")
+    #[serde(default)]
+    pub profile: bool,
+    pub profile_format: Option<ProfileFormat>,
+    pub profile_s3: Option<S3OutputConfig>,
+    /// Process only a slice of a larger job; the response says where the next invocation starts
+    pub continuation: Option<Continuation>,
+    /// Checkpoint the job as it runs, so that an invocation that timed out is resumed by the next one
+    /// with the same checkpoint.job_id
+    pub checkpoint: Option<CheckpointConfig>,
+    /// Mode "fanout": how the job is split into asynchronously invoked slices (requires the "fanout" feature)
+    pub fanout: Option<FanoutConfig>,
+    /// Write this invocation's response to S3 as one part of a map-reduce job; fanout sets it on
+    /// every slice of a job with results_s3 (requires the "s3" feature)
+    pub partial_s3: Option<S3OutputConfig>,
+    /// Mode "reduce": the job prefix whose parts are aggregated (requires the "s3" feature)
+    pub reduce: Option<ReduceConfig>,
+    /// Worker count for this invocation only; Rayon modes run on a pool built for the request
+    pub workers: Option<usize>,
+    /// Pool sizes mode "sweep" runs the items on, one after another
+    pub worker_counts: Option<Vec<usize>>,
+    /// spawn_blocking tasks mode "async_limited" lets run at once (defaults to the worker count)
+    pub permits: Option<usize>,
+    /// How long mode "stress" keeps generating and running items (1-900 seconds)
+    pub duration_seconds: Option<u32>,
+    /// Milliseconds mode "best_effort" has, from the start of the invocation, to start items (1-900,000)
+    pub time_budget_ms: Option<u32>,
+    /// Append the pepper loaded from PEPPER_SECRET_ID to every item before hashing (pepper.rs)
+    #[serde(default)]
+    pub pepper: bool,
+    /// Priority of every item (0-255, higher first), in item order; Rayon runs the levels one after another
+    pub priorities: Option<Vec<u8>>,
 }
 
-#[derive(Serialize)]
+/// Execution strategy; request names are snake_case, and unknown names fail deserialization
+/// Auto runs Sequential or Parallel, whichever a measured per-item cost says is faster (adaptive.rs)
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Sequential,
+    Parallel,
+    Auto,
+    Benchmark,
+    TokioBlocking,
+    StdThreads,
+    Pipeline,
+    Sweep,
+    Channels,
+    AsyncLimited,
+    Stress,
+    BestEffort,
+    Fanout,
+    Reduce,
+    Describe,
+    Health,
+}
+
+impl Mode {
+    /// Every mode, in the order of the README's list, as mode "describe" reports them
+    pub const ALL: [Mode; 16] = [
+        Mode::Sequential, Mode::Parallel, Mode::Auto, Mode::Benchmark, Mode::TokioBlocking, Mode::StdThreads,
+        Mode::Pipeline, Mode::Sweep, Mode::Channels, Mode::AsyncLimited, Mode::Stress, Mode::BestEffort, Mode::Fanout,
+        Mode::Reduce, Mode::Describe, Mode::Health,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Sequential => "sequential",
+            Mode::Parallel => "parallel",
+            Mode::Auto => "auto",
+            Mode::Benchmark => "benchmark",
+            Mode::TokioBlocking => "tokio_blocking",
+            Mode::StdThreads => "std_threads",
+            Mode::Pipeline => "pipeline",
+            Mode::Sweep => "sweep",
+            Mode::Channels => "channels",
+            Mode::AsyncLimited => "async_limited",
+            Mode::Stress => "stress",
+            Mode::BestEffort => "best_effort",
+            Mode::Fanout => "fanout",
+            Mode::Reduce => "reduce",
+            Mode::Describe => "describe",
+            Mode::Health => "health",
+        }
+    }
+}
+
+/// "verify" checks `pairs` against their hashes instead of hashing `items`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    #[default]
+    Hash,
+    Verify,
+}
+
+/// What a response carries of the outputs: counts only, every item's output, or their SHA-256
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Returns {
+    #[default]
+    Summary,
+    Items,
+    Checksum,
+}
+
+/// Whether a failed item fails the request or is reported in `failures` while the rest run
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    #[default]
+    FailFast,
+    Continue,
+}
+
+/// Attempt n + 1 of a failed item starts base_delay_ms x 2^(n-1) after attempt n failed (at most
+/// MAX_RETRY_DELAY_MS), sleeping on the worker thread
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    #[serde(default = "default_retry_delay_ms")]
+    pub base_delay_ms: u64,
+}
+
+fn default_retry_delay_ms() -> u64 { 100 }
+
+/// A password and the PHC string it is checked against (operation "verify")
+#[derive(Deserialize, Serialize)]
+pub struct VerifyPair {
+    pub password: String,
+    pub hash: String,
+}
+
+/// The slice of a long job this invocation processes; the response's `continuation` is the next
+#[derive(Deserialize, Serialize)]
+pub struct Continuation {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,  // items per invocation, 1..=MAX_ITEMS (default MAX_ITEMS)
+}
+
+/// Pipeline mode: hashing overlapped with verification of the results
+/// overlap is the share of verification time that ran while hashing was still in progress;
+/// tail_ms is how long verification continued after the last hash
+#[derive(Clone, Serialize)]
+pub struct PipelineSummary {
+    pub verified: usize, pub failed: usize, pub verify_busy_ms: f64, pub tail_ms: f64,
+    pub end_to_end_ms: f64, pub overlap: f64,
+}
+
+/// `job_id` names the checkpoint ([A-Za-z0-9._-], up to 128), written every `every` items (default
+/// 1000) to `/tmp/checkpoint-<job_id>.json`, or to the `s3` object (requires the "s3" feature), which
+/// any container can resume from
+#[derive(Deserialize, Serialize)]
+pub struct CheckpointConfig {
+    pub job_id: String,
+    pub every: Option<usize>,
+    pub s3: Option<S3OutputConfig>,
+}
+
+/// Where a checkpointed job stands after this invocation: `resumed_from` is the offset it picked up
+/// at (none for a new job), and `complete` is set once every item is done, which removes the
+/// checkpoint; write_errors counts checkpoints that could not be written, wait_ms as for
+/// PersistSummary
+#[derive(Clone, Serialize)]
+pub struct CheckpointSummary {
+    pub job_id: String, pub location: String, pub every: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<usize>,
+    pub next_offset: usize, pub complete: bool, pub written: usize, pub write_errors: usize, pub wait_ms: f64,
+}
+
+/// Progress through a job split across invocations (flattened into the response)
+/// next_offset only advances past contiguous completed items, so after a deadline cut the next
+/// slice resumes at the first item that did not finish
+#[derive(Clone, Serialize)]
+pub struct ContinuationState {
+    offset: usize, next_offset: usize, total_items: usize, done: bool,
+}
+
+/// Slices default to as many as MAX_ITEMS needs (1-1000), sent to this function unless
+/// `function_name` names another, and run in mode "parallel" unless `mode` says otherwise
+/// With `results_s3`, every slice writes its response under `s3://<bucket>/<prefix>/<job id>/` for
+/// mode "reduce"
+#[derive(Default, Deserialize, Serialize)]
+pub struct FanoutConfig {
+    pub invocations: Option<usize>,
+    pub function_name: Option<String>,
+    pub mode: Option<Mode>,
+    pub results_s3: Option<ResultsS3Config>,
+}
+
+/// Where every fanout slice writes its response for mode "reduce"
+#[derive(Deserialize, Serialize)]
+pub struct ResultsS3Config {
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+}
+
+/// `parts` slices are expected under `prefix` (the job prefix a fanout response names); the
+/// reducer lists it every second for up to `wait_seconds` (0-900, default 0: one look) while some
+/// are missing. `dispatched_at_ms` (epoch ms, from the fanout response) enables the makespan
+#[derive(Deserialize, Serialize)]
+pub struct ReduceConfig {
+    pub bucket: String,
+    pub prefix: String,
+    pub parts: usize,
+    #[serde(default)]
+    pub wait_seconds: u64,
+    pub dispatched_at_ms: Option<u64>,
+}
+
+/// Bucket the items of S3-reading workloads are object keys in
+#[derive(Deserialize, Serialize)]
+pub struct S3SourceConfig {
+    pub bucket: String,
+}
+
+/// An S3 object written by the handler (output_s3, checkpoint.s3)
+#[derive(Clone, Deserialize, Serialize)]
+pub struct S3OutputConfig {
+    pub bucket: String, pub key: String,
+}
+
+/// DynamoDB table every item result is written to
+#[derive(Deserialize, Serialize)]
+pub struct PersistConfig {
+    pub table: String,
+}
+
+/// `schema` is an Avro record schema (JSON) the records are written with, by default the built-in
+/// one of src/firehose.rs; `schema_version_id` is its Glue Schema Registry version UUID, which
+/// swaps the single-object header for the registry's
+#[derive(Deserialize, Serialize)]
+pub struct FirehoseConfig {
+    pub delivery_stream: String,
+    pub schema: Option<serde_json::Value>,
+    pub schema_version_id: Option<String>,
+}
+
+/// Outcome of writing item results to DynamoDB
+/// wait_ms is how long the handler waited for writes after processing finished: near zero when
+/// the writes fully overlapped with the CPU work
+#[derive(Clone, Serialize)]
+pub struct PersistSummary {
+    pub table: String, pub items_written: usize, pub batches: usize, pub unprocessed: usize, pub wait_ms: f64,
+}
+
+/// The completion message published to notify_topic_arn: its SNS message ID, or why Publish failed.
+/// The results are already stored by then, so a failed notification does not fail the request
+#[derive(Clone, Serialize)]
+pub struct NotifySummary {
+    pub topic_arn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `requests_per_second` refills the token bucket and `burst` (1-10,000, default one second's worth)
+/// is its size, the calls that may go out at once after a quiet period
+#[derive(Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    pub requests_per_second: f64,
+    pub burst: Option<u32>,
+}
+
+/// Calls that went through the rate limiter: `throttled` of them waited for a token, wait_ms in
+/// total (the waits of concurrent calls overlap) and max_wait_ms the longest one
+#[derive(Clone, Serialize)]
+pub struct RateLimitSummary {
+    pub requests_per_second: f64, pub burst: u32, pub requests: usize, pub throttled: usize, pub wait_ms: f64, pub max_wait_ms: f64,
+}
+
+/// Outcome of sending item results to Firehose; `failed` counts records still rejected after
+/// retries (or over the record size limit), and `bytes` the Avro records sent
+#[derive(Clone, Serialize)]
+pub struct FirehoseSummary {
+    pub delivery_stream: String, pub records_sent: usize, pub batches: usize, pub failed: usize, pub bytes: usize, pub wait_ms: f64,
+}
+
+/// Compression ratio and throughput of a compress run (input MB per second across all workers)
+#[derive(Clone, Serialize)]
+pub struct CompressionSummary {
+    pub codec: Codec, pub level: u32, pub input_bytes: u64, pub output_bytes: u64, pub ratio: f64, pub mb_per_sec: f64,
+}
+
+/// Outcome of operation "verify": `matches[i]` is whether pair `offset + i` matched its hash
+#[derive(Clone, Serialize)]
+pub struct VerificationSummary {
+    pub verified: usize, pub matched: usize, pub mismatched: usize, pub matches: Vec<bool>,
+}
+
+/// Set on responses to requests with an `idempotency_key`; replayed is true for stored responses
+#[derive(Clone, Serialize)]
+pub struct IdempotencyState {
+    pub key: String, pub replayed: bool,
+}
+
+/// Retries of a request with a `retry` policy: retried_items failed at least once, recovered are
+/// the ones a later attempt completed
+#[derive(Clone, Serialize)]
+pub struct RetrySummary {
+    pub max_attempts: u32, pub base_delay_ms: u64, pub retries: usize, pub retried_items: usize, pub recovered: usize,
+}
+
+/// Concurrency mode "async_limited" achieved under its semaphore: peak_in_flight is the most items
+/// running at once, mean_in_flight the items' total run time over the processing duration
+#[derive(Clone, Serialize)]
+pub struct ConcurrencySummary {
+    pub permits: usize, pub peak_in_flight: usize, pub mean_in_flight: f64,
+}
+
+/// Objects downloaded for the s3_checksum workload before processing
+#[derive(Clone, Serialize)]
+pub struct S3SourceSummary {
+    pub bucket: String, pub objects: usize, pub bytes: usize, pub download_ms: f64, pub mib_per_sec: f64,
+    pub concurrency: usize,
+}
+
+/// Location and size of results written to S3; wait_ms as for PersistSummary
+#[derive(Clone, Serialize)]
+pub struct S3OutputSummary {
+    pub uri: String, pub items: usize, pub parts: usize, pub compressed_bytes: usize, pub wait_ms: f64,
+}
+
+/// "flamegraph": an SVG to open in a browser; "protobuf": the pprof format, for `go tool pprof`
+#[derive(Clone, Copy, Default, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileFormat {
+    #[default]
+    Flamegraph,
+    Protobuf,
+}
+
+/// Where a `profile` run's report went; samples are stack samples taken across all threads
+#[derive(Clone, Serialize)]
+pub struct ProfileSummary {
+    pub format: ProfileFormat, pub path: String, pub bytes: usize, pub samples: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_uri: Option<String>,
+}
+
+/// Mode "best_effort": the budget, the invocation time it took to use up (items started before it ran
+/// out still finish), and the items it left unstarted
+#[derive(Clone, Serialize)]
+pub struct BudgetSummary {
+    pub time_budget_ms: u32, pub elapsed_ms: f64, pub exhausted: bool, pub remaining: usize,
+}
+
+/// A request with `priorities`: the highest level present, and how soon after processing started its
+/// first and last results were in
+#[derive(Clone, Serialize)]
+pub struct PrioritySummary {
+    pub levels: usize, pub high_priority: u8, pub high_priority_items: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_high_priority_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_priority_done_ms: Option<f64>,
+}
+
+fn default_workload() -> String { "bcrypt".to_string() }
+
+/// Result of the item-processing modes; callers outside the crate read it as the JSON
+/// serialize_response makes of it
+#[derive(Clone, Serialize)]
 pub struct ProcessResponse {
-    processed: usize, duration_ms: u128, mode: String, workers: usize,
-    detected_cpus: usize, avg_ms_per_item: f64, memory_used_kb: u64,
-    threads_used: usize  // Actual threads that processed items (proves multi-threading)
+    processed: usize, duration_ms: u128, mode: Mode, workload: String, workers: usize,
+    worker_source: WorkerSource, detected_cpus: usize,
+    effective_cpus: f64,  // cgroup CPU quota, can be lower than detected_cpus
+    platform: &'static Platform,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recommended_workers: Option<usize>,  // vCPUs allocated for the function memory size
+    avg_ms_per_item: f64,
+    payload_bytes_total: u64,  // input bytes of the batch (decoded items_base64, s3_checksum object bodies)
+    // CPU time of all threads from the start of the invocation to the response, and that over
+    // the wall time: the number of CPUs kept busy on average (4.0 = four vCPUs fully used)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_time_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_utilization: Option<f64>,
+    context_switches: ContextSwitches,  // during the processing phase, all threads
+    // Against a sequential run of the same batch size and workload settings earlier in this container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speedup: Option<f64>,              // baseline ms per item / this run's ms per item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_efficiency: Option<f64>,  // speedup / workers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency: Option<LatencyStats>,
+    memory_used_kb: u64,  // same as memory.rss_kb
+    memory: MemoryStats,
+    allocator: &'static str,  // global allocator backend ("system", "jemalloc" or "mimalloc")
+    threads_used: usize,  // Actual threads that processed items (proves multi-threading)
+    completed: usize, skipped: usize,  // skipped > 0 when the invocation deadline cut the batch short
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunk_size: Option<usize>,
+    #[serde(flatten)]
+    tuned: TunedParams,  // cost, argon2, samples, etc., for the workloads that take them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generator: Option<ItemGenerator>,  // generated items with a non-default template, length or seed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pepper: Option<PepperSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Vec<ItemResult>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<ResultChecksum>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failures: Option<Vec<ItemFailure>>,  // with on_error "continue" or any panicked item, in index order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<RetrySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    concurrency: Option<ConcurrencySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<PrioritySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget: Option<BudgetSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aborted_reason: Option<&'static str>,  // "memory_pressure" or "ephemeral_storage_full": the batch was stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    persist: Option<PersistSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_firehose: Option<FirehoseSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notification: Option<NotifySummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limit: Option<RateLimitSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification: Option<VerificationSummary>,
+    // simd_hash only: the vector instructions detected on this host ("avx2", "neon" or "scalar")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instruction_set: Option<&'static str>,
+    // nested_hash only: how its per-item rayon::join tasks spread over the pool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nesting: Option<NestingSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<CompressionSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_s3: Option<S3SourceSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_s3: Option<S3OutputSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spill: Option<SpillSummary>,  // return "items" results too large to inline, in place of `results`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<ProfileSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pipeline: Option<PipelineSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto: Option<AutoDecision>,
+    #[serde(flatten)]
+    continuation: Option<ContinuationState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checkpoint: Option<CheckpointSummary>,
+    thread_stats: Vec<ThreadStat>,
+    cache_hit: bool,  // true when served from a previous invocation in this container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    idempotency: Option<IdempotencyState>,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+    phases: Phases,
+    // compress_response: serialize_response replaces it with the description of `compressed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+}
+
+impl ProcessResponse {
+    // The message notify_topic_arn subscribers get: enough to pick up the results without the
+    // response. Status "partial" means items were skipped (deadline, memory or /tmp); a
+    // continuation slice notifies too, with `done` set on the last one
+    #[cfg(feature = "sns")]
+    fn completion(&self, job_id: &str) -> serde_json::Value {
+        let mut output = serde_json::Map::new();
+        if self.results.is_some() { output.insert("inline".to_string(), true.into()); }
+        if let Some(summary) = &self.output_s3 { output.insert("s3".to_string(), summary.uri.clone().into()); }
+        if let Some(spill) = &self.spill {
+            output.insert("spill".to_string(), spill.s3_uri.clone().unwrap_or_else(|| spill.path.clone()).into());
+        }
+        if let Some(persist) = &self.persist { output.insert("dynamodb_table".to_string(), persist.table.clone().into()); }
+        if let Some(firehose) = &self.output_firehose { output.insert("firehose_delivery_stream".to_string(), firehose.delivery_stream.clone().into()); }
+        let partial = self.skipped > 0 || self.aborted_reason.is_some();
+        serde_json::json!({
+            "event": "job_completed",
+            "job_id": job_id,
+            "idempotency_key": self.idempotency.as_ref().map(|state| &state.key),
+            "status": if partial { "partial" } else { "completed" },
+            "mode": self.mode,
+            "workload": self.workload,
+            "processed": self.processed,
+            "completed": self.completed,
+            "skipped": self.skipped,
+            "failed": self.failures.as_ref().map_or(0, Vec::len),
+            "duration_ms": self.duration_ms,
+            "continuation": self.continuation.as_ref().map(|state| serde_json::json!({ "next_offset": state.next_offset, "total_items": state.total_items, "done": state.done })),
+            "output": output,
+        })
+    }
 }
 
-// CPU-intensive bcrypt hashing with cost factor 10
-fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
-    bcrypt::hash(password, 10)
+/// Result of mode "benchmark": the same items run sequentially and in parallel in one invocation
+#[derive(Serialize)]
+pub struct BenchmarkResponse {
+    processed: usize, mode: Mode, workload: String, workers: usize, worker_source: WorkerSource,
+    detected_cpus: usize, effective_cpus: f64, platform: &'static Platform, sequential_ms: f64, parallel_ms: f64,
+    speedup: f64,     // sequential_ms / parallel_ms
+    efficiency: f64,  // speedup / workers (1.0 = perfect linear scaling)
+    threads_used: usize,
+    #[serde(flatten)]
+    tuned: TunedParams,  // cost, argon2, samples, etc., for the workloads that take them
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+}
+
+/// Result of mode "sweep": the items once sequentially, then in parallel on a pool of each size in
+/// `worker_counts`, for a scaling curve from a single invocation
+#[derive(Serialize)]
+pub struct SweepResponse {
+    processed: usize, mode: Mode, workload: String, detected_cpus: usize, effective_cpus: f64,
+    platform: &'static Platform, sequential_ms: f64,
+    sweep: Vec<SweepPoint>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_worker_counts: Vec<usize>,  // not run: the invocation deadline was too close
+    #[serde(flatten)]
+    tuned: TunedParams,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+}
+
+/// serial_fraction is the Karp-Flatt metric, Amdahl's serial fraction as measured at this worker
+/// count: (1/speedup - 1/workers) / (1 - 1/workers). Constant across rows when Amdahl's law holds;
+/// growing with the worker count when overhead (contention, memory bandwidth) grows with it
+#[derive(Serialize)]
+pub struct SweepPoint {
+    workers: usize, duration_ms: f64, speedup: f64, efficiency: f64, threads_used: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serial_fraction: Option<f64>,  // None for a single worker
+}
+
+/// Result of mode "stress": items run back to back on every worker for `duration_seconds`
+/// timeline holds the items per second completed in each interval_seconds window, to show
+/// throughput drifting over a long invocation (CPU credits, thermal limits, noisy neighbours)
+#[derive(Serialize)]
+pub struct StressResponse {
+    processed: usize, mode: Mode, workload: String, workers: usize, worker_source: WorkerSource,
+    detected_cpus: usize, effective_cpus: f64, platform: &'static Platform,
+    duration_seconds: u32, elapsed_ms: f64, items_per_sec: f64, threads_used: usize,
+    interval_seconds: u64, timeline: Vec<f64>,
+    cut_short: bool,  // the invocation deadline came before duration_seconds did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generator: Option<ItemGenerator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_time_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_utilization: Option<f64>,
+    #[serde(flatten)]
+    tuned: TunedParams,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+}
+
+/// Result of mode "health": the global pool was built at cold start and one of its threads ran a
+/// SHA-256 of a known test vector; any other outcome is a HandlerError::Unhealthy instead
+#[derive(Serialize)]
+pub struct HealthResponse {
+    mode: Mode,
+    healthy: bool,
+    pool_initialized: bool,
+    pool_threads: usize,         // threads of the global pool
+    configured_workers: usize,   // the count it was built with (WORKER_COUNT or the detected CPUs)
+    hash_thread: usize,          // Rayon index of the pool thread that ran the hash
+    hash_us: f64,
+    duration_ms: f64,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+}
+
+/// What function_handler returns, by mode; serialized untagged, as the mode's response alone
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum HandlerResponse {
+    Process(Box<ProcessResponse>),
+    Benchmark(Box<BenchmarkResponse>),
+    Sweep(Box<SweepResponse>),
+    Stress(Box<StressResponse>),
+    Describe(Box<Capabilities>),
+    Health(Box<HealthResponse>),
+    #[cfg(feature = "fanout")]
+    Fanout(Box<crate::fanout::FanoutResponse>),
+    #[cfg(feature = "s3")]
+    Reduce(Box<crate::mapreduce::ReduceResponse>),
+    /// A response stored in DynamoDB by another container, replayed as it was serialized
+    #[cfg(feature = "dynamodb")]
+    Replayed(Box<serde_json::Value>),
+}
+
+// Wall-clock milliseconds per handler phase; each phase also runs inside a tracing span of the same
+// name and, on sampled invocations, becomes an X-Ray subsegment
+type Phases = BTreeMap<&'static str, f64>;
+
+fn run_phase<T>(phases: &mut Phases, name: &'static str, f: impl FnOnce() -> T) -> T {
+    let _span = info_span!("phase", name).entered();
+    let (start, wall_start) = (Instant::now(), SystemTime::now());
+    let result = f();
+    phases.insert(name, start.elapsed().as_secs_f64() * 1000.0);
+    xray::record(name, wall_start, SystemTime::now());
+    result
+}
+
+async fn run_phase_async<T>(phases: &mut Phases, name: &'static str, future: impl Future<Output = T>) -> T {
+    let (start, wall_start) = (Instant::now(), SystemTime::now());
+    let result = future.instrument(info_span!("phase", name)).await;
+    phases.insert(name, start.elapsed().as_secs_f64() * 1000.0);
+    xray::record(name, wall_start, SystemTime::now());
+    result
+}
+
+// How compress_response encodes the per-item fields
+const RESPONSE_ENCODING: &str = "zstd+base64";
+
+/// Serialize a handler response inside the "serialization" phase and record that phase's duration
+/// in the serialized `phases` map (it cannot be known before the response itself is serialized).
+/// Every response ends with the schema_version it follows.
+/// Serialization is the last phase of every front end, so the invocation's X-Ray trace is sent here
+pub fn serialize_response(response: HandlerResponse) -> Result<serde_json::Value, serde_json::Error> {
+    let mut phases = Phases::new();
+    let value = run_phase(&mut phases, "serialization", || {
+        #[allow(unused_mut)]
+        let mut value = serde_json::to_value(response)?;
+        #[cfg(feature = "compress")]
+        if let Some(map) = value.as_object_mut().filter(|map| map.get("encoding").and_then(serde_json::Value::as_str) == Some(RESPONSE_ENCODING)) {
+            crate::compress::encode_response(map)?;
+        }
+        Ok::<_, serde_json::Error>(value)
+    });
+    xray::flush();
+    let mut value = value?;
+    if let Some(map) = value.get_mut("phases").and_then(serde_json::Value::as_object_mut) {
+        map.insert("serialization".to_string(), phases["serialization"].into());
+    }
+    if let Some(map) = value.as_object_mut() {
+        map.insert("schema_version".to_string(), crate::schema::CURRENT_VERSION.into());
+        map.insert("container_stats".to_string(), serde_json::to_value(lifecycle::container_stats())?);
+        // The platform reports an invocation only after it returns, so a response carries the one before it
+        #[cfg(feature = "telemetry")]
+        if let Some(previous) = crate::telemetry::take_previous() {
+            map.insert("previous_invocation".to_string(), serde_json::to_value(previous)?);
+        }
+    }
+    Ok(value)
+}
+
+// Outcome of processing a single item, captured on the thread that ran it
+struct ItemOutcome {
+    index: usize, output: String, duration_us: u64, thread_id: std::thread::ThreadId,
+}
+
+/// Per-item detail returned with `return_items` or return "items" (for visualizing work distribution)
+#[derive(Clone, Serialize)]
+pub struct ItemResult {
+    pub index: usize, pub output: String, pub duration_us: u64, pub thread_id: String,
+}
+
+/// return "checksum": SHA-256 of the outputs of the `items` completed items in index order, each
+/// followed by "\n", so deterministic workloads give the same digest in every mode and container
+#[derive(Clone, Serialize)]
+pub struct ResultChecksum {
+    pub sha256: String, pub items: usize,
+}
+
+/// An item whose workload failed, with on_error "continue", or panicked under either policy
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ItemFailure {
+    pub index: usize, pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panic_message: Option<String>,
+}
+
+impl From<&ItemOutcome> for ItemResult {
+    fn from(outcome: &ItemOutcome) -> Self {
+        ItemResult {
+            index: outcome.index,
+            output: outcome.output.clone(),
+            duration_us: outcome.duration_us,
+            thread_id: format!("{:?}", outcome.thread_id),
+        }
+    }
+}
+
+/// Work done by one thread: how evenly the scheduler balanced the batch
+#[derive(Clone, Serialize)]
+pub struct ThreadStat {
+    thread_id: String, items: usize, busy_ms: f64,
+}
+
+// Group outcomes by executing thread, in order of each thread's first (lowest-index) item
+fn thread_stats(outcomes: &[ItemOutcome]) -> Vec<ThreadStat> {
+    let mut stats: Vec<(std::thread::ThreadId, usize, u64)> = Vec::new();
+    for outcome in outcomes {
+        match stats.iter_mut().find(|(id, _, _)| *id == outcome.thread_id) {
+            Some((_, items, busy_us)) => { *items += 1; *busy_us += outcome.duration_us; }
+            None => stats.push((outcome.thread_id, 1, outcome.duration_us)),
+        }
+    }
+    stats.into_iter().map(|(id, items, busy_us)| ThreadStat {
+        thread_id: format!("{:?}", id), items, busy_ms: busy_us as f64 / 1000.0,
+    }).collect()
+}
+
+// Longest per-item duration the latency histogram tracks (longer items are recorded at this value)
+const LATENCY_MAX_US: u64 = 15 * 60 * 1_000_000;
+
+/// Per-item latency distribution from an HDR histogram (microsecond resolution, 3 significant digits)
+/// thread_jitter_ms is the standard deviation of the threads' mean item latency: near zero when
+/// every thread saw the same per-item cost, larger under contention (shared caches, memory bandwidth)
+#[derive(Clone, Serialize)]
+pub struct LatencyStats {
+    pub p50_ms: f64, pub p90_ms: f64, pub p99_ms: f64, pub max_ms: f64, pub mean_ms: f64, pub thread_jitter_ms: f64,
+}
+
+fn latency_stats(outcomes: &[ItemOutcome], threads: &[ThreadStat]) -> Option<LatencyStats> {
+    let mut histogram = Histogram::<u64>::new_with_bounds(1, LATENCY_MAX_US, 3).ok()?;
+    for outcome in outcomes {
+        histogram.saturating_record(outcome.duration_us.max(1));
+    }
+    if histogram.is_empty() { return None; }
+    let ms = |us: u64| us as f64 / 1000.0;
+    let means: Vec<f64> = threads.iter().map(|thread| thread.busy_ms / thread.items as f64).collect();
+    let average = means.iter().sum::<f64>() / means.len() as f64;
+    let variance = means.iter().map(|mean| (mean - average).powi(2)).sum::<f64>() / means.len() as f64;
+    Some(LatencyStats {
+        p50_ms: ms(histogram.value_at_quantile(0.5)),
+        p90_ms: ms(histogram.value_at_quantile(0.9)),
+        p99_ms: ms(histogram.value_at_quantile(0.99)),
+        max_ms: ms(histogram.max()),
+        mean_ms: histogram.mean() / 1000.0,
+        thread_jitter_ms: variance.sqrt(),
+    })
+}
+
+/// Receives each item result as soon as it completes (streaming responses)
+pub type ItemSink = tokio::sync::mpsc::UnboundedSender<ItemResult>;
+
+type BatchResult = Result<(Vec<ItemOutcome>, usize), Box<dyn std::error::Error + Send + Sync>>;
+
+// One batch of items plus the per-item policy every execution strategy shares
+// Cheap to clone (Arc-backed) so it can move into 'static tasks (tokio spawn_blocking)
+#[derive(Clone)]
+struct Job {
+    items: Arc<[String]>,
+    workload: Arc<dyn Workload>,
+    cutoff: Option<Instant>,  // no new items are started after this instant
+    aborted: Option<Arc<AtomicBool>>,  // nor once the memory guard raises this
+    spill: Option<Arc<Spill>>,  // return "items" results on their way to the response
+    sink: Option<ItemSink>,
+    first_index: usize,  // index of items[0] in the whole job (continuation offset)
+    progress: Option<Arc<AtomicUsize>>,  // finished items, read by the progress heartbeat
+    failures: Option<Arc<Mutex<Vec<ItemFailure>>>>,  // collects panicked items, and failed ones unless fail_fast
+    fail_fast: bool,  // an item's error (not a panic) fails the job; always for jobs without failures
+    retry: Option<Arc<Retries>>,
+}
+
+// A retry policy and its counters, shared by every thread of the job
+struct Retries {
+    policy: RetryPolicy, retries: AtomicUsize, retried_items: AtomicUsize, recovered: AtomicUsize,
+}
+
+impl Retries {
+    fn summary(&self) -> RetrySummary {
+        RetrySummary {
+            max_attempts: self.policy.max_attempts,
+            base_delay_ms: self.policy.base_delay_ms,
+            retries: self.retries.load(Ordering::Relaxed),
+            retried_items: self.retried_items.load(Ordering::Relaxed),
+            recovered: self.recovered.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Job {
+    fn new(items: impl Into<Arc<[String]>>, workload: Arc<dyn Workload>, cutoff: Option<Instant>) -> Self {
+        Job { items: items.into(), workload, cutoff, aborted: None, spill: None, sink: None, first_index: 0, progress: None, failures: None, fail_fast: true, retry: None }
+    }
+
+    // Run the workload on one item, timing it and recording the executing thread
+    // Returns None without running the item once the cutoff has passed or the job was aborted, and
+    // for a failed item when failures are collected. A panic is caught here, outside the retries,
+    // as running the item again would only hit the same bug; rather than unwinding through the
+    // parallel iterator and failing the whole invocation, it becomes a failure of that item even
+    // under fail_fast
+    fn run_item(&self, index: usize) -> Result<Option<ItemOutcome>, Box<dyn std::error::Error + Send + Sync>> {
+        if self.cutoff.is_some_and(|cutoff| Instant::now() >= cutoff) { return Ok(None); }
+        if self.aborted.as_ref().is_some_and(|aborted| aborted.load(Ordering::Relaxed)) { return Ok(None); }
+        if self.spill.as_ref().is_some_and(|spill| spill.full()) { return Ok(None); }
+        let start = Instant::now();
+        let (result, panic_message) = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.execute(index))) {
+            Ok(result) => (result, None),
+            Err(payload) => {
+                crate::pool::record_panic();
+                let message = crate::pool::panic_message(payload.as_ref());
+                (Err(format!("{} panicked", self.workload.name()).into()), Some(message))
+            }
+        };
+        let output = match (result, &self.failures) {
+            (Ok(output), _) => output,
+            (Err(e), Some(failures)) if panic_message.is_some() || !self.fail_fast => {
+                failures.lock().unwrap().push(ItemFailure { index: self.first_index + index, error: e.to_string(), panic_message });
+                if let Some(progress) = &self.progress { progress.fetch_add(1, Ordering::Relaxed); }
+                return Ok(None);
+            }
+            (Err(e), _) => match panic_message {
+                Some(message) => return Err(format!("{}: {}", e, message).into()),
+                None => return Err(e),
+            },
+        };
+        let mut outcome = ItemOutcome {
+            index: self.first_index + index,
+            output,
+            duration_us: start.elapsed().as_micros() as u64,
+            thread_id: std::thread::current().id(),
+        };
+        if let Some(sink) = &self.sink {
+            let _ = sink.send(ItemResult::from(&outcome));  // receiver gone: client disconnected
+        }
+        if let Some(spill) = &self.spill {
+            if spill.offer(index, &ItemResult::from(&outcome))? { outcome.output = String::new(); }
+        }
+        if let Some(progress) = &self.progress {
+            progress.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(Some(outcome))
+    }
+
+    // The workload's output for one item, retrying failures under the job's retry policy
+    // No attempt starts after the cutoff: the last error is returned instead of waiting for it
+    fn execute(&self, index: usize) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(retry) = &self.retry else { return self.workload.execute_item(&self.items[index]) };
+        let mut attempt = 1;
+        loop {
+            let error = match self.workload.execute_item(&self.items[index]) {
+                Ok(output) => {
+                    if attempt > 1 { retry.recovered.fetch_add(1, Ordering::Relaxed); }
+                    return Ok(output);
+                }
+                Err(e) => e,
+            };
+            let delay = Duration::from_millis(retry.policy.base_delay_ms.saturating_mul(1 << (attempt - 1)).min(MAX_RETRY_DELAY_MS));
+            if attempt >= retry.policy.max_attempts || self.cutoff.is_some_and(|cutoff| Instant::now() + delay >= cutoff) {
+                return Err(error);
+            }
+            tracing::debug!(index = self.first_index + index, attempt, error = %error, "retrying item");
+            if attempt == 1 { retry.retried_items.fetch_add(1, Ordering::Relaxed); }
+            retry.retries.fetch_add(1, Ordering::Relaxed);
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    // Run a contiguous range of items in order on the current thread
+    fn run_range(&self, range: std::ops::Range<usize>) -> Result<Vec<ItemOutcome>, Box<dyn std::error::Error + Send + Sync>> {
+        let outcomes: Result<Vec<Option<ItemOutcome>>, _> = range.map(|index| self.run_item(index)).collect();
+        outcomes.map(|o| o.into_iter().flatten().collect())
+    }
+}
+
+// Items processed by the current thread since the last rayon_threads_used() call
+// A plain thread-local add per item, so tracking adds no contention to the parallel section
+thread_local! {
+    static ITEMS_ON_THREAD: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_item() {
+    count_items(1);
+}
+
+fn count_items(n: usize) {
+    ITEMS_ON_THREAD.with(|items| items.set(items.get() + n));
+}
+
+// Count the Rayon pool threads that processed at least one item, resetting every counter
+// Runs once per pool thread via broadcast, after the parallel section has finished
+fn rayon_threads_used() -> usize {
+    rayon::broadcast(|_| ITEMS_ON_THREAD.with(|items| items.replace(0)))
+        .into_iter()
+        .filter(|&items| items > 0)
+        .count()
 }
 
 // Process items one at a time (baseline for comparison)
-fn process_sequential(items: Vec<String>) -> Result<(Vec<String>, usize), Box<dyn std::error::Error + Send + Sync>> {
-    let results: Result<Vec<String>, _> = items
-        .iter().map(|item| hash_password(item)).collect();
-    results
-        .map(|r| (r, 1))
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+fn process_sequential(job: &Job) -> BatchResult {
+    job.run_range(0..job.items.len()).map(|r| (r, 1))
 }
 
 // Process items in parallel using Rayon's work-stealing scheduler
 // Thread pool size is configured once at cold start via init_thread_pool()
-fn process_parallel(items: Vec<String>) -> Result<(Vec<String>, usize), Box<dyn std::error::Error + Send + Sync>> {
-    let thread_ids: Mutex<HashSet<std::thread::ThreadId>> = Mutex::new(HashSet::new());
-    
-    let results: Result<Vec<String>, _> = items
+fn process_parallel(job: &Job) -> BatchResult {
+    let results: Result<Vec<Option<ItemOutcome>>, _> = (0..job.items.len())
+        .into_par_iter()
+        .map(|index| {
+            count_item();
+            job.run_item(index)
+        })
+        .collect();
+
+    let threads_used = rayon_threads_used();
+    results.map(|r| (r.into_iter().flatten().collect(), threads_used))
+}
+
+// Process fixed-size chunks in parallel: each Rayon task hashes a whole chunk sequentially
+// Larger chunks reduce scheduling overhead, smaller chunks give work stealing more room to balance
+fn process_chunked(job: &Job, chunk_size: usize) -> BatchResult {
+    let chunks: Result<Vec<Vec<ItemOutcome>>, _> = job.items
+        .par_chunks(chunk_size)
+        .enumerate()
+        .map(|(n, chunk)| {
+            count_items(chunk.len());
+            job.run_range(n * chunk_size..n * chunk_size + chunk.len())
+        })
+        .collect();
+
+    let threads_used = rayon_threads_used();
+    chunks.map(|c| (c.into_iter().flatten().collect(), threads_used))
+}
+
+// Item indices by priority, one level per distinct value, highest first, and the times process_prioritized
+// measures against the highest level
+struct Priorities {
+    levels: Vec<(u8, Vec<usize>)>,
+    first_high_ms: OnceLock<f64>,
+    high_done_ms: OnceLock<f64>,
+}
+
+impl Priorities {
+    fn new(priorities: &[u8]) -> Self {
+        let mut levels: BTreeMap<std::cmp::Reverse<u8>, Vec<usize>> = BTreeMap::new();
+        for (index, priority) in priorities.iter().enumerate() { levels.entry(std::cmp::Reverse(*priority)).or_default().push(index); }
+        let levels = levels.into_iter().map(|(priority, indices)| (priority.0, indices)).collect();
+        Priorities { levels, first_high_ms: OnceLock::new(), high_done_ms: OnceLock::new() }
+    }
+
+    fn summary(&self) -> PrioritySummary {
+        PrioritySummary {
+            levels: self.levels.len(),
+            high_priority: self.levels[0].0,
+            high_priority_items: self.levels[0].1.len(),
+            first_high_priority_ms: self.first_high_ms.get().copied(),
+            high_priority_done_ms: self.high_done_ms.get().copied(),
+        }
+    }
+}
+
+// Process the priority levels one after another, highest first: each level is spread over the pool
+// (or run in order on this thread when sequential), so no worker starts a lower-priority item while
+// a higher one is waiting. A single par_iter over the batch sorted by priority would not do: Rayon
+// splits it into ranges that the workers start on at the same time
+// Under a deadline, the items skipped are therefore the lowest-priority ones
+fn process_prioritized(job: &Job, priorities: &Priorities, sequential: bool) -> BatchResult {
+    let start = Instant::now();
+    let mut results = Vec::with_capacity(job.items.len());
+    for (level, (_, indices)) in priorities.levels.iter().enumerate() {
+        let run = |index: &usize| {
+            let outcome = job.run_item(*index);
+            if level == 0 && matches!(outcome, Ok(Some(_))) { priorities.first_high_ms.get_or_init(|| start.elapsed().as_secs_f64() * 1000.0); }
+            outcome
+        };
+        let outcomes: Vec<Option<ItemOutcome>> = if sequential {
+            indices.iter().map(run).collect::<Result<_, _>>()?
+        } else {
+            indices.par_iter().map(|index| { count_item(); run(index) }).collect::<Result<_, _>>()?
+        };
+        if level == 0 { let _ = priorities.high_done_ms.set(start.elapsed().as_secs_f64() * 1000.0); }
+        results.extend(outcomes.into_iter().flatten());
+    }
+    // In index order, as every other strategy returns them
+    results.sort_by_key(|outcome| outcome.index);
+    let threads_used = if sequential { 1 } else { rayon_threads_used() };
+    Ok((results, threads_used))
+}
+
+// Split items into `workers` contiguous chunks, each processed on its own scoped OS thread
+// Static partitioning without work stealing: the slowest chunk determines the batch duration
+fn process_std_threads(job: &Job, workers: usize) -> BatchResult {
+    let len = job.items.len();
+    let chunk_len = len.div_ceil(workers.max(1));
+
+    let chunks: Vec<Result<Vec<ItemOutcome>, _>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..len)
+            .step_by(chunk_len)
+            .map(|start| scope.spawn(move || job.run_range(start..(start + chunk_len).min(len))))
+            .collect();
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err("worker thread panicked".into())))
+            .collect()
+    });
+
+    let threads_used = chunks.len();
+    let results: Result<Vec<Vec<ItemOutcome>>, _> = chunks.into_iter().collect();
+    results.map(|c| (c.into_iter().flatten().collect(), threads_used))
+}
+
+// Explicit producer/consumer pipeline: a producer thread feeds item indices into a bounded
+// crossbeam channel, `workers` consumer threads take them as they free up, and this thread collects
+// the outcomes from a second bounded channel (fan-out/fan-in). Load balancing comes from the shared
+// queue rather than work stealing; the bounds keep the producer at most a few items ahead
+fn process_channels(job: &Job, workers: usize) -> BatchResult {
+    let workers = workers.max(1);
+    let (work_tx, work_rx) = crossbeam_channel::bounded::<usize>(workers * CHANNEL_SLOTS_PER_WORKER);
+    let (result_tx, result_rx) = crossbeam_channel::bounded(workers * CHANNEL_SLOTS_PER_WORKER);
+    let stop = AtomicBool::new(false);
+
+    let collected = std::thread::scope(|scope| {
+        let stop = &stop;
+        scope.spawn(move || {
+            for index in 0..job.items.len() {
+                if stop.load(Ordering::Relaxed) || work_tx.send(index).is_err() { break; }
+            }
+        });  // dropping work_tx lets the consumers drain the queue and exit
+        for _ in 0..workers {
+            let (work_rx, result_tx) = (work_rx.clone(), result_tx.clone());
+            scope.spawn(move || {
+                for index in work_rx {
+                    if stop.load(Ordering::Relaxed) || result_tx.send(job.run_item(index)).is_err() { break; }
+                }
+            });
+        }
+        drop((work_rx, result_tx));
+
+        let mut outcomes = Vec::with_capacity(job.items.len());
+        let mut error = None;
+        for result in result_rx.iter() {
+            match result {
+                Ok(outcome) => outcomes.extend(outcome),
+                Err(e) => { error = Some(e); break; }
+            }
+        }
+        // Unblock everyone on the first error: consumers stop taking items, and sends fail
+        stop.store(true, Ordering::Relaxed);
+        drop(result_rx);
+        error.map_or(Ok(outcomes), Err)
+    });
+
+    let mut outcomes = collected?;
+    outcomes.sort_by_key(|outcome| outcome.index);
+    let threads_used = outcomes.iter().map(|outcome| outcome.thread_id).collect::<HashSet<_>>().len();
+    Ok((outcomes, threads_used))
+}
+
+// Distribute items across `workers` tokio spawn_blocking tasks pulling from a shared cursor
+// Tokio's blocking pool is sized for blocking I/O (up to 512 threads) rather than CPU cores,
+// so this measures it against Rayon's fixed work-stealing pool for the same worker count
+async fn process_tokio_blocking(job: Job, workers: usize) -> BatchResult {
+    let len = job.items.len();
+    let next = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..workers.min(len)).map(|_| {
+        let (job, next) = (job.clone(), next.clone());
+        tokio::task::spawn_blocking(move || {
+            let mut outcomes = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= len { break; }
+                match job.run_item(index) {
+                    Ok(outcome) => outcomes.extend(outcome),
+                    Err(e) => {
+                        next.store(len, Ordering::Relaxed);  // stop the other tasks
+                        return Err(e);
+                    }
+                }
+            }
+            Ok(outcomes)
+        })
+    }).collect();
+
+    let mut outcomes = Vec::with_capacity(len);
+    for task in tasks {
+        outcomes.extend(task.await??);
+    }
+    outcomes.sort_by_key(|outcome| outcome.index);
+    let threads_used = outcomes.iter().map(|outcome| outcome.thread_id).collect::<HashSet<_>>().len();
+    Ok((outcomes, threads_used))
+}
+
+// Wrap every item in its own spawn_blocking task behind a semaphore of `permits`: a task starts
+// only once it holds a permit, so at most `permits` items run at once however many are queued,
+// instead of the blocking pool growing a thread per item. FuturesUnordered drives the tasks and
+// yields them as they finish; `peak` records the most items that were in flight together
+async fn process_async_limited(job: Job, permits: usize, peak: Arc<AtomicUsize>) -> BatchResult {
+    let semaphore = Arc::new(Semaphore::new(permits.max(1)));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let mut tasks: FuturesUnordered<_> = (0..job.items.len()).map(|index| {
+        let (job, semaphore, in_flight, peak) = (job.clone(), semaphore.clone(), in_flight.clone(), peak.clone());
+        async move {
+            // Closed after a failure: items still waiting never start
+            let Ok(_permit) = semaphore.acquire_owned().await else { return Ok(None) };
+            tokio::task::spawn_blocking(move || {
+                peak.fetch_max(in_flight.fetch_add(1, Ordering::Relaxed) + 1, Ordering::Relaxed);
+                let outcome = job.run_item(index);
+                in_flight.fetch_sub(1, Ordering::Relaxed);
+                outcome
+            }).await?
+        }
+    }).collect();
+
+    let mut outcomes = Vec::with_capacity(job.items.len());
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok(outcome) => outcomes.extend(outcome),
+            Err(e) => {
+                semaphore.close();
+                return Err(e);
+            }
+        }
+    }
+    outcomes.sort_by_key(|outcome| outcome.index);
+    let threads_used = outcomes.iter().map(|outcome| outcome.thread_id).collect::<HashSet<_>>().len();
+    Ok((outcomes, threads_used))
+}
+
+/// Process caller-provided items in parallel and return outputs in input order
+/// Shared by the event-source handlers (S3, ...) that bring their own items
+#[cfg(feature = "s3")]
+pub fn process_batch(items: Vec<String>, workload: Arc<dyn Workload>) -> Result<(Vec<String>, usize), Box<dyn std::error::Error + Send + Sync>> {
+    let (outcomes, threads_used) = process_parallel(&Job::new(items, workload, None))?;
+    Ok((outcomes.into_iter().map(|o| o.output).collect(), threads_used))
+}
+
+/// Process items in parallel, keeping each item's own result instead of failing the whole batch
+/// Used by event sources that acknowledge records individually (SQS partial batch failures)
+/// A panic is caught as in Job::run_item, so it fails only its record
+pub fn process_each(items: Vec<String>, workload: &dyn Workload) -> (Vec<Result<String, String>>, usize) {
+    let results: Vec<Result<String, String>> = items
         .par_iter()
         .map(|item| {
-            thread_ids.lock().unwrap().insert(std::thread::current().id());
-            hash_password(item)
+            count_item();
+            match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| workload.execute_item(item))) {
+                Ok(result) => result.map_err(|e| e.to_string()),
+                Err(payload) => {
+                    crate::pool::record_panic();
+                    Err(format!("{} panicked: {}", workload.name(), crate::pool::panic_message(payload.as_ref())))
+                }
+            }
         })
         .collect();
-    
-    let threads_used = thread_ids.lock().unwrap().len();
-    results
-        .map(|r| (r, threads_used))
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+
+    let threads_used = rayon_threads_used();
+    (results, threads_used)
+}
+
+/// Where the worker count came from, reported so callers can tell an explicit setting from a default
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkerSource {
+    Env,       // WORKER_COUNT, used as given
+    Config,    // the WORKER_COUNT parameter under CONFIG_SSM_PREFIX (config.rs)
+    Memory,    // recommended for the configured function memory size
+    Detected,  // detected vCPUs (outside Lambda)
+    Clamped,   // WORKER_COUNT or the detected count, limited to 1..=WORKER_COUNT_MAX
+    Request,   // the request's `workers` field (invocation-scoped pool)
 }
 
-// Get worker count from env var or detect CPUs, clamped to 1-6
+/// vCPUs Lambda allocates for AWS_LAMBDA_FUNCTION_MEMORY_SIZE; None outside Lambda
+/// Independent of what num_cpus sees through the cgroup, which can differ from the allocation
+pub fn recommended_workers() -> Option<usize> {
+    let memory_mb = env::var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE").ok()?.parse::<usize>().ok()?;
+    Some(memory_mb.div_ceil(MB_PER_VCPU).clamp(1, MAX_LAMBDA_VCPUS))
+}
+
+/// Resolve the worker count: WORKER_COUNT if set (as an SSM parameter or in the environment), else the memory-based recommendation, else detected
+/// vCPUs, capped by WORKER_COUNT_MAX (default: detected vCPUs)
+pub fn resolve_workers() -> (usize, WorkerSource) {
+    let max = config::var("WORKER_COUNT_MAX")
+        .and_then(|max| max.parse::<usize>().ok())
+        .unwrap_or_else(detected_cpus)
+        .max(1);
+    let (requested, source) = match config::var("WORKER_COUNT").and_then(|count| count.parse::<usize>().ok()) {
+        Some(count) if config::parameter("WORKER_COUNT").is_some() => (count, WorkerSource::Config),
+        Some(count) => (count, WorkerSource::Env),
+        None => match recommended_workers() {
+            Some(count) => (count, WorkerSource::Memory),
+            None => (detected_cpus(), WorkerSource::Detected),
+        },
+    };
+    let workers = requested.clamp(1, max);
+    (workers, if workers == requested { source } else { WorkerSource::Clamped })
+}
+
+/// The pool size resolve_workers settles on
 pub fn get_worker_count() -> usize {
-    if let Ok(count_str) = env::var("WORKER_COUNT") {
-        if let Ok(count) = count_str.parse::<usize>() {
-            return count.clamp(1, 6);
+    resolve_workers().0
+}
+
+// Bytes of stack and heap each pool thread touches during warm-up
+const WARMUP_TOUCH_BYTES: usize = 64 * 1024;
+
+/// Run a tiny job on every pool thread during init, so the first invocation does not pay for
+/// waking the threads and faulting in their stacks and allocator arenas; returns milliseconds
+pub fn warmup() -> f64 {
+    let start = Instant::now();
+    rayon::broadcast(|_| {
+        let mut stack = [0u8; WARMUP_TOUCH_BYTES];
+        std::hint::black_box(&mut stack);
+        std::hint::black_box(vec![1u8; WARMUP_TOUCH_BYTES]);
+    });
+    // One work-stealing pass over a few items per thread, as a real batch would do
+    let items = rayon::current_num_threads() * 4;
+    std::hint::black_box((0..items).into_par_iter().map(|i| std::hint::black_box(i * i)).sum::<usize>());
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+// Threads of the global pool built at cold start, None if building it failed
+fn global_pool_size() -> Option<usize> {
+    global_pool_config().map(|config| config.threads)
+}
+
+// Settings of the global pool, None if building it failed
+pub(crate) fn global_pool_config() -> Option<&'static PoolConfig> {
+    GLOBAL_POOL.get().and_then(|pool| pool.as_ref().ok())
+}
+
+// The global pool's settings sized for `workers`, for pools built per request or sweep point
+fn pool_builder(workers: usize) -> rayon::ThreadPoolBuilder {
+    global_pool_config().map_or_else(|| PoolConfig::from_env(workers), |config| config.with_threads(workers)).builder()
+}
+
+// SHA-256("abc"), the FIPS 180-2 test vector
+const HEALTH_DIGEST: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+// Mode "health": checks the cold start built the global pool, then runs one hash on a pool thread
+// (rayon::join called from outside the pool blocks until a worker has run it)
+fn health_check(start: Instant) -> Result<HealthResponse, HandlerError> {
+    let configured_workers = match GLOBAL_POOL.get() {
+        Some(Ok(config)) => config.threads,
+        Some(Err(e)) => return Err(HandlerError::Unhealthy(format!("the thread pool failed to initialize: {}", e))),
+        None => return Err(HandlerError::Unhealthy("the thread pool was not initialized".to_string())),
+    };
+    let hash_start = Instant::now();
+    let ((hash_thread, digest), ()) = rayon::join(|| (rayon::current_thread_index(), Sha256::digest(b"abc")), || ());
+    let hash_us = hash_start.elapsed().as_secs_f64() * 1_000_000.0;
+    let digest = format!("{:x}", digest);
+    if digest != HEALTH_DIGEST {
+        return Err(HandlerError::Unhealthy(format!("SHA-256 self-test produced {}", digest)));
+    }
+    let Some(hash_thread) = hash_thread else {
+        return Err(HandlerError::Unhealthy("the hash did not run on a pool thread".to_string()));
+    };
+    Ok(HealthResponse {
+        mode: Mode::Health, healthy: true, pool_initialized: true,
+        pool_threads: rayon::current_num_threads(), configured_workers, hash_thread, hash_us,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        lifecycle: lifecycle::current(),
+    })
+}
+
+// Invocation-scoped Rayon pool for a request that sets `workers`, so worker counts can be compared
+// on a warm container; the global pool keeps the size chosen at cold start
+fn scoped_pool(phases: &mut Phases, workers: Option<usize>) -> Result<Option<rayon::ThreadPool>, HandlerError> {
+    let Some(workers) = workers else { return Ok(None) };
+    run_phase(phases, "thread_pool", || pool_builder(workers).build())
+        .map(Some)
+        .map_err(|e| HandlerError::Io(e.into()))
+}
+
+// Run `f` on `pool` if there is one (Rayon calls inside use that pool), otherwise on the global pool
+fn in_pool<T: Send>(pool: Option<&rayon::ThreadPool>, f: impl FnOnce() -> T + Send) -> T {
+    match pool {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
+/// Initialize Rayon global thread pool (only once per Lambda container)
+/// A failure is kept for mode "health": Rayon then falls back to a default-sized pool on first use
+pub fn init_thread_pool(config: PoolConfig) {
+    GLOBAL_POOL.get_or_init(|| {
+        config.builder()
+            .build_global()
+            .map(|_| config)
+            .map_err(|e| e.to_string())
+    });
+}
+
+// Run the same items sequentially then in parallel, so both timings come from the same container state
+// The deadline cutoff is not applied here: a partial run on either side would skew the speedup
+fn run_benchmark(job: &Job, workers: usize, worker_source: WorkerSource, chunk_size: Option<usize>, tuned: TunedParams) -> Result<BenchmarkResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let workload = job.workload.as_ref();
+    let start = Instant::now();
+    process_sequential(job)?;
+    let sequential_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let key = BaselineKey { count: job.items.len(), payload_bytes: payload_bytes(workload, &job.items), workload: workload.name(), tuned: tuned.clone() };
+    sequential_baseline().lock().unwrap().insert(key, sequential_ms / job.items.len() as f64);
+
+    let start = Instant::now();
+    let (_, threads_used) = match chunk_size {
+        Some(size) => process_chunked(job, size)?,
+        None => process_parallel(job)?,
+    };
+    let parallel_ms = start.elapsed().as_secs_f64() * 1000.0;
+    xray::annotate("threads_used", threads_used);
+
+    let speedup = if parallel_ms > 0.0 { sequential_ms / parallel_ms } else { 0.0 };
+    metrics::emit(&[("Mode", Mode::Benchmark.as_str()), ("Workload", workload.name())], &[
+        Metric::count("ProcessedItems", job.items.len() as f64),
+        Metric::milliseconds("DurationMs", parallel_ms),
+        Metric::milliseconds("SequentialDurationMs", sequential_ms),
+        Metric::count("ThreadsUsed", threads_used as f64),
+        Metric::none("Speedup", speedup),
+    ]);
+    Ok(BenchmarkResponse {
+        processed: job.items.len(),
+        mode: Mode::Benchmark,
+        workload: workload.name().to_string(),
+        workers,
+        worker_source,
+        detected_cpus: detected_cpus(),
+        effective_cpus: effective_cpus(),
+        platform: platform(),
+        sequential_ms,
+        parallel_ms,
+        speedup,
+        efficiency: speedup / workers as f64,
+        threads_used,
+        tuned,
+        lifecycle: lifecycle::current(),
+    })
+}
+
+// Mode "sweep": a sequential baseline, then one parallel run per worker count (ascending), each on
+// its own pool; the pools are built outside the timed runs. A run that would end past `cutoff`,
+// judged by the previous run's duration, is skipped with the rest
+fn run_sweep(job: &Job, worker_counts: &[usize], chunk_size: Option<usize>, cutoff: Option<Instant>, tuned: TunedParams) -> Result<SweepResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let workload = job.workload.as_ref();
+    let start = Instant::now();
+    process_sequential(job)?;
+    let sequential_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let key = BaselineKey { count: job.items.len(), payload_bytes: payload_bytes(workload, &job.items), workload: workload.name(), tuned: tuned.clone() };
+    sequential_baseline().lock().unwrap().insert(key, sequential_ms / job.items.len() as f64);
+
+    let mut worker_counts = worker_counts.to_vec();
+    worker_counts.sort_unstable();
+    worker_counts.dedup();
+    let (mut sweep, mut skipped) = (Vec::with_capacity(worker_counts.len()), Vec::new());
+    let mut last_ms = sequential_ms;
+    for workers in worker_counts {
+        if !skipped.is_empty() || cutoff.is_some_and(|cutoff| Instant::now() + Duration::from_secs_f64(last_ms / 1000.0) >= cutoff) {
+            skipped.push(workers);
+            continue;
         }
+        let pool = pool_builder(workers).build()?;
+        let start = Instant::now();
+        let (_, threads_used) = pool.install(|| match chunk_size {
+            Some(size) => process_chunked(job, size),
+            None => process_parallel(job),
+        })?;
+        last_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let speedup = if last_ms > 0.0 { sequential_ms / last_ms } else { 0.0 };
+        let inverse = 1.0 / workers as f64;
+        let serial_fraction = (workers > 1 && speedup > 0.0).then(|| (1.0 / speedup - inverse) / (1.0 - inverse));
+        metrics::emit(&[("Mode", Mode::Sweep.as_str()), ("Workload", workload.name()), ("Workers", &workers.to_string())], &[
+            Metric::milliseconds("DurationMs", last_ms),
+            Metric::none("Speedup", speedup),
+        ]);
+        sweep.push(SweepPoint { workers, duration_ms: last_ms, speedup, efficiency: speedup * inverse, threads_used, serial_fraction });
+    }
+    if !skipped.is_empty() {
+        tracing::warn!(skipped = ?skipped, "sweep cut short: the deadline is too close for the remaining worker counts");
     }
-    num_cpus::get().clamp(1, 6)
+    Ok(SweepResponse {
+        processed: job.items.len(),
+        mode: Mode::Sweep,
+        workload: workload.name().to_string(),
+        detected_cpus: detected_cpus(),
+        effective_cpus: effective_cpus(),
+        platform: platform(),
+        sequential_ms,
+        sweep,
+        skipped_worker_counts: skipped,
+        tuned,
+        lifecycle: lifecycle::current(),
+    })
 }
 
-// Initialize Rayon global thread pool (only once per Lambda container)
-pub fn init_thread_pool(workers: usize) {
-    INIT.call_once(|| {
-        let _ = rayon::ThreadPoolBuilder::new()
-            .num_threads(workers)
-            .build_global();
+// Mode "stress": every pool thread generates and runs items back to back until `duration` has
+// passed, or `cutoff` if that comes first; an item started before the end still completes
+// Completions are counted per `interval` of the run for the timeline; the first error (or panic) stops it
+fn run_stress(workload: &dyn Workload, generator: &ItemGenerator, workers: usize, worker_source: WorkerSource, duration_seconds: u32, cutoff: Option<Instant>, tuned: TunedParams) -> Result<StressResponse, Box<dyn std::error::Error + Send + Sync>> {
+    // At most MAX_STRESS_INTERVALS points, in whole seconds
+    let interval_seconds = (duration_seconds as u64).div_ceil(MAX_STRESS_INTERVALS);
+    let intervals = (duration_seconds as u64).div_ceil(interval_seconds) as usize;
+    let completed: Vec<AtomicUsize> = (0..intervals).map(|_| AtomicUsize::new(0)).collect();
+    let (next, threads_used, stop) = (AtomicUsize::new(0), AtomicUsize::new(0), AtomicBool::new(false));
+    let error = Mutex::new(None);
+    let cpu_start = process_cpu_time_ms();
+    let start = Instant::now();
+    let target = start + Duration::from_secs(duration_seconds as u64);
+    let end = cutoff.map_or(target, |cutoff| cutoff.min(target));
+    rayon::broadcast(|_| {
+        let mut ran = false;
+        while !stop.load(Ordering::Relaxed) && Instant::now() < end {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| workload.execute_item(&generator.item(index))))
+                .unwrap_or_else(|payload| {
+                    crate::pool::record_panic();
+                    Err(format!("{} panicked: {}", workload.name(), crate::pool::panic_message(payload.as_ref())).into())
+                });
+            if let Err(e) = result {
+                stop.store(true, Ordering::Relaxed);
+                error.lock().unwrap().get_or_insert(e);
+                break;
+            }
+            let slot = (start.elapsed().as_secs() / interval_seconds) as usize;
+            completed[slot.min(intervals - 1)].fetch_add(1, Ordering::Relaxed);
+            ran = true;
+        }
+        if ran { threads_used.fetch_add(1, Ordering::Relaxed); }
     });
+    if let Some(e) = error.into_inner().unwrap() { return Err(e); }
+    let elapsed = start.elapsed().as_secs_f64();
+    let cpu_time_ms = cpu_start.zip(process_cpu_time_ms()).map(|(start, end)| end - start);
+
+    // The last window can be shorter than the others, or longer by the items that overran the end
+    let timeline: Vec<f64> = completed.iter().enumerate().map(|(i, count)| {
+        let window_start = (i as u64 * interval_seconds) as f64;
+        let window = if i + 1 == intervals { elapsed - window_start } else { interval_seconds as f64 };
+        if window > 0.0 { count.load(Ordering::Relaxed) as f64 / window } else { 0.0 }
+    }).collect();
+    let processed = completed.iter().map(|count| count.load(Ordering::Relaxed)).sum::<usize>();
+    let items_per_sec = if elapsed > 0.0 { processed as f64 / elapsed } else { 0.0 };
+    let threads_used = threads_used.into_inner();
+    let cut_short = end < target;
+    if cut_short {
+        tracing::warn!(duration_seconds, elapsed_s = elapsed, "stress run cut short: the invocation deadline came first");
+    }
+    xray::annotate("threads_used", threads_used);
+    metrics::emit(&[("Mode", Mode::Stress.as_str()), ("Workload", workload.name())], &[
+        Metric::count("ProcessedItems", processed as f64),
+        Metric::milliseconds("DurationMs", elapsed * 1000.0),
+        Metric::count("ThreadsUsed", threads_used as f64),
+        Metric::none("ItemsPerSec", items_per_sec),
+    ]);
+    Ok(StressResponse {
+        processed,
+        mode: Mode::Stress,
+        workload: workload.name().to_string(),
+        workers,
+        worker_source,
+        detected_cpus: detected_cpus(),
+        effective_cpus: effective_cpus(),
+        platform: platform(),
+        duration_seconds,
+        elapsed_ms: elapsed * 1000.0,
+        items_per_sec,
+        threads_used,
+        interval_seconds,
+        timeline,
+        cut_short,
+        generator: (*generator != ItemGenerator::default()).then(|| generator.clone()),
+        cpu_time_ms,
+        cpu_utilization: cpu_time_ms.filter(|_| elapsed > 0.0).map(|cpu_ms| cpu_ms / (elapsed * 1000.0)),
+        tuned,
+        lifecycle: lifecycle::current(),
+    })
+}
+
+// Let the runtime move other tasks (DynamoDB writes) off this worker while it runs CPU-bound work
+// Only possible on the multi-threaded runtime; elsewhere `f` simply runs in place
+fn run_blocking<T>(f: impl FnOnce() -> T) -> T {
+    use tokio::runtime::{Handle, RuntimeFlavor};
+    match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+        Ok(RuntimeFlavor::MultiThread) => tokio::task::block_in_place(f),
+        _ => f(),
+    }
+}
+
+// Everything that determines a generated-items response (caller-supplied items are never cached)
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    count: usize, mode: Mode, workload: &'static str, tuned: TunedParams,
+    chunk_size: Option<usize>, returns: Returns, workers: usize, permits: usize, generator: ItemGenerator, pepper: bool,
+}
+
+fn idempotency_store() -> &'static Mutex<LruCache<String, (String, ProcessResponse)>> {
+    IDEMPOTENCY_STORE.get_or_init(|| Mutex::new(LruCache::new(NonZeroUsize::new(IDEMPOTENCY_CAPACITY).unwrap())))
+}
+
+// SHA-256 of the whole request (items_base64 as its decoded bytes), which a reused idempotency_key
+// has to match for its stored response to be replayed
+fn request_fingerprint(request: &ProcessRequest) -> String {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, request).expect("a request serializes");
+    for blob in request.items_base64.iter().flatten() {
+        hasher.update((blob.len() as u64).to_le_bytes());
+        hasher.update(blob);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn reused_key(key: &str) -> HandlerError {
+    crate::error::invalid("idempotency_key", format!("idempotency_key {:?} was already used by a different request", key))
+}
+
+fn result_cache() -> &'static Mutex<LruCache<CacheKey, ProcessResponse>> {
+    RESULT_CACHE.get_or_init(|| Mutex::new(LruCache::new(NonZeroUsize::new(RESULT_CACHE_CAPACITY).unwrap())))
+}
+
+// Batches comparable for speedup: same size and the same per-item work
+#[derive(PartialEq, Eq, Hash)]
+struct BaselineKey {
+    count: usize, payload_bytes: u64, workload: &'static str, tuned: TunedParams,
+}
+
+fn payload_bytes(workload: &dyn Workload, items: &[String]) -> u64 {
+    items.iter().map(|item| workload.payload_len(item) as u64).sum()
+}
+
+fn sequential_baseline() -> &'static Mutex<HashMap<BaselineKey, f64>> {
+    SEQUENTIAL_BASELINE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Latest instant at which a new item may start: the invocation deadline minus DEADLINE_MARGIN_MS
+// (default 1000ms), leaving time to serialize a partial response instead of timing out
+fn item_cutoff(context: &Context) -> Option<Instant> {
+    if context.deadline == 0 { return None; }  // no deadline outside the Lambda runtime
+    let margin_ms = config::var("DEADLINE_MARGIN_MS")
+        .and_then(|ms| ms.parse::<u64>().ok()).unwrap_or(DEFAULT_DEADLINE_MARGIN_MS);
+    let remaining = context.deadline().duration_since(SystemTime::now()).unwrap_or_default();
+    Some(Instant::now() + remaining.saturating_sub(Duration::from_millis(margin_ms)))
 }
 
-// Read RSS memory from /proc/self/statm (Linux only)
-fn get_memory_usage_kb() -> u64 {
-    std::fs::read_to_string("/proc/self/statm")
-        .ok().and_then(|s| s.split_whitespace().nth(1)?.parse::<u64>().ok())
-        .map(|pages| pages * 4).unwrap_or(0)
+/// Main Lambda handler - runs the requested workload over items sequentially or in parallel
+pub async fn function_handler(request: ProcessRequest, context: &Context) -> Result<HandlerResponse, HandlerError> {
+    Box::pin(counted(tracked(request, context, None, None))).await
+}
+
+#[cfg(feature = "dynamodb")]
+use crate::jobs::TrackedJob;
+#[cfg(not(feature = "dynamodb"))]
+type TrackedJob = std::convert::Infallible;
+
+/// What a job's COMPLETED record counts (src/jobs.rs)
+pub struct JobCounts {
+    pub processed: usize, pub completed: usize, pub skipped: usize, pub failed: usize,
 }
 
-// Main Lambda handler - processes items sequentially or in parallel
-pub async fn function_handler(request: ProcessRequest) -> Result<ProcessResponse, Box<dyn std::error::Error + Send + Sync>> {
-    if request.count == 0 { return Err("count must be greater than 0".into()); }
-    if request.count > 1000 { return Err("count exceeds maximum of 1000 items".into()); }
+impl HandlerResponse {
+    #[cfg(feature = "dynamodb")]
+    fn job_counts(&self) -> JobCounts {
+        let all = |processed: usize| JobCounts { processed, completed: processed, skipped: 0, failed: 0 };
+        match self {
+            HandlerResponse::Process(process) => JobCounts {
+                processed: process.processed, completed: process.completed, skipped: process.skipped,
+                failed: process.failures.as_ref().map_or(0, Vec::len),
+            },
+            HandlerResponse::Benchmark(benchmark) => all(benchmark.processed),
+            HandlerResponse::Sweep(sweep) => all(sweep.processed),
+            HandlerResponse::Stress(stress) => all(stress.processed),
+            _ => all(0),
+        }
+    }
+}
 
-    let items: Vec<String> = (0..request.count)
-        .map(|i| format!("password_{:06}", i)).collect();
+// With JOB_TABLE set, the job's status transitions are recorded around the run; `batch_index`
+// tells the jobs of one batch invocation apart. Health checks and describe are not jobs
+async fn tracked(request: ProcessRequest, context: &Context, sink: Option<ItemSink>, batch_index: Option<usize>) -> Result<HandlerResponse, HandlerError> {
+    #[cfg(feature = "dynamodb")]
+    if !matches!(request.mode, Mode::Health | Mode::Describe) {
+        if let Some(job) = TrackedJob::receive(&request, context, batch_index).await {
+            let result = Box::pin(run_request(request, context, sink, Some(&job))).await;
+            match &result {
+                Ok(response) => job.complete(response.job_counts()).await,
+                Err(e) => job.fail(e).await,
+            }
+            return result;
+        }
+    }
+    #[cfg(not(feature = "dynamodb"))]
+    let _ = batch_index;
+    Box::pin(run_request(request, context, sink, None)).await
+}
 
-    let workers = get_worker_count();
-    let mode = match request.mode.as_str() {
-        "sequential" => "sequential",
-        "parallel" => "parallel",
-        _ => if workers > 1 { "parallel" } else { "sequential" }
+// Run a job and add it to the container's lifetime statistics
+async fn counted(job: impl Future<Output = Result<HandlerResponse, HandlerError>>) -> Result<HandlerResponse, HandlerError> {
+    let start = Instant::now();
+    let response = job.await;
+    let items = match &response {
+        Ok(HandlerResponse::Process(process)) if !process.cache_hit && !process.idempotency.as_ref().is_some_and(|state| state.replayed) => process.processed,
+        Ok(HandlerResponse::Benchmark(benchmark)) => benchmark.processed,
+        Ok(HandlerResponse::Sweep(sweep)) => sweep.processed,
+        Ok(HandlerResponse::Stress(stress)) => stress.processed,
+        _ => 0,
     };
+    lifecycle::record_job(items, start.elapsed().as_secs_f64() * 1000.0);
+    response
+}
+
+/// Several jobs in one invocation, run back-to-back on the same warm pool (`{"jobs": [...]}`)
+#[derive(Deserialize)]
+pub struct BatchRequest {
+    pub jobs: Vec<ProcessRequest>,
+}
+
+/// A direct invocation's payload: a batch envelope when it has `jobs`, otherwise a single job
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Invocation {
+    Batch(BatchRequest),
+    Single(Box<ProcessRequest>),
+}
 
+/// Run every job of a batch in order; a failed job becomes its error body (HandlerError::to_json)
+/// in the response array rather than failing the jobs after it
+pub async fn batch_handler(batch: BatchRequest, context: &Context) -> Result<serde_json::Value, HandlerError> {
+    if batch.jobs.is_empty() || batch.jobs.len() > MAX_BATCH_JOBS {
+        return Err(crate::error::invalid("jobs", format!("jobs must have between 1 and {} entries", MAX_BATCH_JOBS)));
+    }
     let start = Instant::now();
-    let (results, threads_used) = match mode {
-        "sequential" => process_sequential(items)?,
-        _ => process_parallel(items)?,
+    let (mut results, mut failed) = (Vec::with_capacity(batch.jobs.len()), 0);
+    for (index, job) in batch.jobs.into_iter().enumerate() {
+        let result = match Box::pin(counted(tracked(job, context, None, Some(index)))).await {
+            Ok(response) => serialize_response(response).map_err(|e| HandlerError::Io(e.into())),
+            Err(e) => Err(e),
+        };
+        results.push(result.unwrap_or_else(|e| {
+            tracing::warn!(job = index, code = e.code(), error = %e, "batch job failed");
+            failed += 1;
+            e.to_json()
+        }));
+    }
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    tracing::info!(jobs = results.len(), failed, duration_ms, "batch complete");
+    Ok(serde_json::json!({ "jobs": results, "succeeded": results.len() - failed, "failed": failed, "duration_ms": duration_ms }))
+}
+
+/// Same as function_handler, but every item result is also sent to `sink` as it completes.
+/// The returned summary never embeds `results`, since the items were already streamed
+#[cfg(feature = "streaming")]
+pub async fn function_handler_streaming(request: ProcessRequest, context: &Context, sink: ItemSink) -> Result<HandlerResponse, HandlerError> {
+    Box::pin(counted(tracked(request, context, Some(sink), None))).await
+}
+
+async fn run_request(request: ProcessRequest, context: &Context, sink: Option<ItemSink>, job: Option<&TrackedJob>) -> Result<HandlerResponse, HandlerError> {
+    memory::reset_heap_peak();
+    let (invocation_start, cpu_start) = (Instant::now(), process_cpu_time_ms());
+    xray::begin(context.xray_trace_id.as_deref());
+    config::refresh().await;
+    let (workers, worker_source) = match request.workers {
+        Some(workers) => (workers, WorkerSource::Request),
+        None => resolve_workers(),
+    };
+    // A worker count retuned since cold start no longer matches the global pool, so it gets a pool
+    // of its own, as a request's `workers` does
+    let pool_workers = request.workers.or_else(|| (global_pool_size() != Some(workers)).then_some(workers));
+    // Nothing to run: the deployment's capabilities or its health, whatever else the request holds
+    if request.mode == Mode::Health {
+        return health_check(invocation_start).map(|response| HandlerResponse::Health(Box::new(response)));
+    }
+    if request.mode == Mode::Describe {
+        return Ok(HandlerResponse::Describe(Box::new(crate::describe::capabilities(workers, worker_source))));
+    }
+    // Nor does reducing a map-reduce job, which only reads its parts from S3
+    if request.mode == Mode::Reduce {
+        #[cfg(feature = "s3")]
+        return crate::mapreduce::reduce(request.reduce, context).await.map(|response| HandlerResponse::Reduce(Box::new(response)));
+        #[cfg(not(feature = "s3"))]
+        return Err(crate::error::invalid("mode", "reduce mode requires building with the \"s3\" feature"));
+    }
+    let Validated { workload, params, generator, total, offset, count } = validate(&request, workers, sink.is_some())?;
+
+    // A key seen before replays its response: first from this container, then from the shared table
+    let idempotency_key = request.idempotency_key.clone().map(|key| (key, request_fingerprint(&request)));
+    if let Some((key, fingerprint)) = &idempotency_key {
+        let stored = idempotency_store().lock().unwrap().get(key).cloned();
+        if let Some((stored_fingerprint, stored)) = stored {
+            if stored_fingerprint != *fingerprint { return Err(reused_key(key)); }
+            tracing::info!(idempotency_key = %key, "replaying stored response");
+            return Ok(HandlerResponse::Process(Box::new(ProcessResponse {
+                idempotency: Some(IdempotencyState { key: key.clone(), replayed: true }), lifecycle: lifecycle::current(), ..stored
+            })));
+        }
+        #[cfg(feature = "dynamodb")]
+        if let Some(table) = crate::idempotency::table() {
+            if let Some((stored_fingerprint, mut stored)) = crate::idempotency::load(&table, key).await.map_err(HandlerError::Io)? {
+                if stored_fingerprint.is_some_and(|stored_fingerprint| stored_fingerprint != *fingerprint) { return Err(reused_key(key)); }
+                tracing::info!(idempotency_key = %key, table, "replaying stored response");
+                stored["idempotency"]["replayed"] = true.into();
+                return Ok(HandlerResponse::Replayed(Box::new(stored)));
+            }
+        }
+    }
+    // A checkpointed job that an earlier invocation left unfinished picks up where it stopped
+    let returns = if request.return_items { Returns::Items } else { request.returns };
+    let restored = match &request.checkpoint {
+        Some(config) => crate::checkpoint::restore(config, workload.name(), returns, total).await?,
+        None => None,
+    };
+    let (offset, count) = restored.as_ref().map_or((offset, count), |saved| (saved.next_offset, total - saved.next_offset));
+    #[cfg(feature = "dynamodb")]
+    if let Some(job) = job { job.running(count, workers).await; }
+    #[cfg(not(feature = "dynamodb"))]
+    let _ = job;
+
+    let continued = request.continuation.is_some();
+    let generated = request.items.is_none() && request.pairs.is_none() && request.items_base64.is_none();
+    let cacheable = request.cache && generated && sink.is_none() && request.priorities.is_none()
+        && request.persist.is_none() && request.output_firehose.is_none() && request.notify_topic_arn.is_none() && request.output_s3.is_none() && request.partial_s3.is_none() && request.checkpoint.is_none() && !request.profile && !continued;
+    let mut phases = Phases::new();
+    let blobs: Option<Arc<[Bytes]>> = request.items_base64.map(Into::into);
+    let items: Vec<String> = run_phase(&mut phases, "generation", || match (request.pairs, request.items, &blobs) {
+        (Some(pairs), _, _) => pairs.into_iter().skip(offset).take(count).map(|pair| format!("{}:{}", pair.hash, pair.password)).collect(),
+        (None, Some(items), _) => items.into_iter().skip(offset).take(count).collect(),
+        // Indices into the blobs, which BlobItems resolves
+        (None, None, Some(_)) => (offset..offset + count).map(|i| i.to_string()).collect(),
+        (None, None, None) => crate::prewarm::generate(&generator, offset, count),
+    });
+    let custom_generator = (generated && generator != ItemGenerator::default()).then(|| generator.clone());
+
+    // One bucket for every downstream call of the run, whichever sink or worker's result it is for
+    let limiter = crate::rate_limit::RateLimiter::new(request.rate_limit.as_ref());
+    // S3-sourced items: fetch the objects now, so processing measures only the CPU work on them
+    #[cfg(feature = "s3")]
+    let (workload, source_s3) = match &request.source_s3 {
+        Some(source) => {
+            let (objects, summary) = run_phase_async(&mut phases, "download", crate::s3_checksum::download(&source.bucket, &items, &limiter))
+                .await.map_err(HandlerError::Io)?;
+            // Validation only accepts source_s3 for the workloads that read objects
+            let workload = crate::registry::find(workload.name()).and_then(|executor| executor.prepare(&params, Some(objects))).unwrap_or(workload);
+            (workload, Some(summary))
+        }
+        None => (workload, None),
     };
-    let duration_ms = start.elapsed().as_millis();
+    #[cfg(not(feature = "s3"))]
+    let source_s3: Option<S3SourceSummary> = None;
+    let workload: Arc<dyn Workload> = match blobs {
+        Some(blobs) => Arc::new(BlobItems { blobs, inner: workload }),
+        None => workload,
+    };
+    // Validation only accepts `pepper` once one is loaded
+    let (workload, pepper): (Arc<dyn Workload>, _) = match crate::pepper::get().filter(|_| request.pepper) {
+        Some(pepper) => (Arc::new(Peppered::new(pepper, workload)), Some(pepper.summary.clone())),
+        None => (workload, None),
+    };
+    let payload_bytes = payload_bytes(workload.as_ref(), &items);
 
-    Ok(ProcessResponse {
-        processed: results.len(),
+    let tuned = params.tuned(workload.name());
+    xray::annotate("workload", workload.name());
+    if request.mode == Mode::Benchmark {
+        xray::annotate("mode", Mode::Benchmark.as_str());
+        xray::annotate("workers", workers);
+        let pool = scoped_pool(&mut phases, pool_workers)?;
+        let job = Job::new(items, workload, None);
+        let response = in_pool(pool.as_ref(), || run_benchmark(&job, workers, worker_source, request.chunk_size, tuned))
+            .map_err(HandlerError::Hashing)?;
+        return Ok(HandlerResponse::Benchmark(Box::new(response)));
+    }
+    if request.mode == Mode::Sweep {
+        xray::annotate("mode", Mode::Sweep.as_str());
+        let job = Job::new(items, workload, None);
+        let worker_counts = request.worker_counts.unwrap_or_default();
+        let cutoff = item_cutoff(context);
+        let response = run_blocking(|| run_sweep(&job, &worker_counts, request.chunk_size, cutoff, tuned)).map_err(HandlerError::Hashing)?;
+        return Ok(HandlerResponse::Sweep(Box::new(response)));
+    }
+    if request.mode == Mode::Stress {
+        xray::annotate("mode", Mode::Stress.as_str());
+        xray::annotate("workers", workers);
+        let pool = scoped_pool(&mut phases, pool_workers)?;
+        let (duration_seconds, cutoff) = (request.duration_seconds.unwrap_or_default(), item_cutoff(context));
+        let response = run_blocking(|| in_pool(pool.as_ref(), || run_stress(workload.as_ref(), &generator, workers, worker_source, duration_seconds, cutoff, tuned)))
+            .map_err(HandlerError::Hashing)?;
+        return Ok(HandlerResponse::Stress(Box::new(response)));
+    }
+    let auto = match request.mode {
+        Mode::Auto => {
+            let key = (workload.name(), tuned.clone(), workload.payload_len(&items[0]));
+            let decision = run_phase(&mut phases, "calibration", || adaptive::choose(key, workload.as_ref(), &items[0], count, workers))
+                .map_err(HandlerError::Hashing)?;
+            tracing::info!(chosen = decision.chosen.as_str(), reason = decision.reason, item_cost_us = decision.item_cost_us, "auto mode decision");
+            Some(decision)
+        }
+        _ => None,
+    };
+    let mode = auto.as_ref().map_or(request.mode, |decision| decision.chosen);
+    let chunk_size = if matches!(mode, Mode::Parallel | Mode::BestEffort) { request.chunk_size } else { None };
+    let permits = if mode == Mode::AsyncLimited { request.permits.unwrap_or(workers) } else { 0 };
+    xray::annotate("mode", mode.as_str());
+    xray::annotate("workers", if mode == Mode::Sequential { 1 } else { workers });
+
+    let cache_key = cacheable.then(|| CacheKey {
+        count, mode, workload: workload.name(), tuned: tuned.clone(), chunk_size, returns, workers, permits, generator, pepper: pepper.is_some(),
+    });
+    if let Some(key) = &cache_key {
+        if let Some(cached) = result_cache().lock().unwrap().get(key) {
+            tracing::info!(mode = mode.as_str(), workload = workload.name(), count, "serving cached result");
+            let memory = MemoryStats::capture();
+            return Ok(HandlerResponse::Process(Box::new(ProcessResponse {
+                cache_hit: true, lifecycle: lifecycle::current(), memory_used_kb: memory.rss_kb, memory,
+                encoding: request.compress_response.then_some(RESPONSE_ENCODING), ..cached.clone()
+            })));
+        }
+    }
+
+    // Results flow from the workers to the pipeline verifier, the checkpointer, the DynamoDB
+    // persister, the Firehose writer, the S3 writer, then the stream
+    let inline_results = sink.is_none() && request.output_s3.is_none();
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    #[cfg(feature = "s3")]
+    let s3_writer = request.output_s3.map(|target| crate::s3_output::S3Writer::start(target.bucket, target.key, limiter.clone(), sink.clone()));
+    #[cfg(feature = "s3")]
+    let sink = s3_writer.as_ref().map(|writer| writer.sink()).or(sink);
+    #[cfg(not(feature = "s3"))]
+    let s3_writer: Option<std::convert::Infallible> = None;
+    #[cfg(feature = "firehose")]
+    let firehose = match request.output_firehose {
+        Some(config) => {
+            let fields = crate::firehose::RunFields { request_id: context.request_id.clone(), workload: workload.name().to_string(), mode: mode.as_str().to_string(), workers };
+            Some(crate::firehose::FirehoseWriter::start(config, fields, limiter.clone(), sink.clone()).map_err(HandlerError::Io)?)
+        }
+        None => None,
+    };
+    #[cfg(feature = "firehose")]
+    let sink = firehose.as_ref().map(|writer| writer.sink()).or(sink);
+    #[cfg(not(feature = "firehose"))]
+    let firehose: Option<std::convert::Infallible> = None;
+    #[cfg(feature = "dynamodb")]
+    let persister = request.persist.map(|config| crate::persist::Persister::start(config.table, context.request_id.clone(), limiter.clone(), sink.clone()));
+    #[cfg(feature = "dynamodb")]
+    let sink = persister.as_ref().map(|persister| persister.sink()).or(sink);
+    #[cfg(not(feature = "dynamodb"))]
+    let persister: Option<std::convert::Infallible> = None;
+    // What the checkpoint keeps of restored items is only their failures and completed count, and
+    // with return "checksum" their outputs, which the checkpointer hands back at the end
+    let (restored_completed, restored_failures) = restored.as_ref().map_or((0, Vec::new()), |saved| (saved.completed(), saved.failures.clone()));
+    let checkpointer = request.checkpoint.map(|config| {
+        crate::checkpoint::Checkpointer::start(config, workload.name().to_string(), returns, total, restored, failures.clone(), sink.clone())
+    });
+    let sink = checkpointer.as_ref().map(|checkpointer| checkpointer.sink()).or(sink);
+    let items: Arc<[String]> = items.into();
+    let verifier = (mode == Mode::Pipeline).then(|| {
+        crate::pipeline::Verifier::start(items.clone(), offset, workload.clone(), workers, sink.clone())
+    });
+    let sink = verifier.as_ref().map(|verifier| verifier.sink()).or(sink);
+
+    let heartbeat = progress_interval().map(|interval| Heartbeat::start(count, interval));
+    let progress = heartbeat.as_ref().map(Heartbeat::counter);
+    let guard = memory::guard_threshold_kb().map(MemoryGuard::start);
+    // The outputs bcrypt_verify's and compress's summaries read stay in memory when spilled
+    let keep_outputs = workload.name() == "bcrypt_verify" || tuned.compress.is_some();
+    let spill = (returns == Returns::Items && inline_results).then(|| Spill::new(count, &context.request_id, keep_outputs)).flatten().map(Arc::new);
+    let retry = request.retry.map(|policy| Arc::new(Retries {
+        policy, retries: AtomicUsize::new(0), retried_items: AtomicUsize::new(0), recovered: AtomicUsize::new(0),
+    }));
+    // best_effort stops starting items at the end of its budget, or at the deadline margin if that comes first
+    let budget_end = request.time_budget_ms.map(|ms| invocation_start + Duration::from_millis(ms.into()));
+    let cutoff = match (item_cutoff(context), budget_end) {
+        (Some(deadline), Some(budget)) => Some(deadline.min(budget)),
+        (deadline, budget) => deadline.or(budget),
+    };
+    let job = Job {
+        aborted: guard.as_ref().map(MemoryGuard::flag), spill: spill.clone(), sink, first_index: offset, progress, failures: Some(failures.clone()),
+        fail_fast: request.on_error == OnError::FailFast, retry: retry.clone(),
+        ..Job::new(items, workload.clone(), cutoff)
+    };
+    let peak_in_flight = Arc::new(AtomicUsize::new(0));
+    // Validation only accepts priorities in sequential and parallel (without chunk_size) runs
+    let priorities = request.priorities.as_ref().map(|priorities| Priorities::new(&priorities[offset..offset + count]));
+    #[cfg(feature = "profiling")]
+    let profiler = request.profile.then(crate::profiling::Profiler::start).transpose().map_err(HandlerError::Io)?;
+    if workload.name() == "nested_hash" { crate::nested::reset(); }
+    let switches_before = ContextSwitches::capture();
+    let (results, threads_used) = if mode == Mode::TokioBlocking {
+        run_phase_async(&mut phases, "processing", process_tokio_blocking(job, workers)).await
+    } else if mode == Mode::AsyncLimited {
+        run_phase_async(&mut phases, "processing", process_async_limited(job, permits, peak_in_flight.clone())).await
+    } else {
+        // Only the Rayon modes need the invocation-scoped pool
+        let pool = match mode {
+            Mode::Parallel | Mode::Pipeline | Mode::BestEffort => scoped_pool(&mut phases, pool_workers)?,
+            _ => None,
+        };
+        let process = || in_pool(pool.as_ref(), || match (mode, chunk_size) {
+            (Mode::Sequential | Mode::Parallel | Mode::BestEffort, None) if priorities.is_some() => {
+                process_prioritized(&job, priorities.as_ref().unwrap(), mode == Mode::Sequential)
+            }
+            (Mode::Sequential, _) => process_sequential(&job),
+            (Mode::StdThreads, _) => process_std_threads(&job, workers),
+            (Mode::Channels, _) => process_channels(&job, workers),
+            (_, Some(size)) => process_chunked(&job, size),
+            _ => process_parallel(&job),
+        });
+        let result = run_phase(&mut phases, "processing", || {
+            if persister.is_some() || firehose.is_some() || s3_writer.is_some() || verifier.is_some() || checkpointer.is_some() { run_blocking(process) } else { process() }
+        });
+        drop(job);  // closes the sink
+        result
+    }.map_err(HandlerError::Hashing)?;
+    let context_switches = ContextSwitches::capture().since(switches_before);
+    #[cfg(feature = "profiling")]
+    let profile = match profiler {
+        Some(profiler) => {
+            let finish = profiler.finish(request.profile_format.unwrap_or_default(), &context.request_id, request.profile_s3);
+            Some(run_phase_async(&mut phases, "profile", finish).await.map_err(HandlerError::Io)?)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "profiling"))]
+    let profile = None;
+    drop(heartbeat);
+    let memory_pressure = guard.as_ref().is_some_and(MemoryGuard::tripped);
+    let storage_full = spill.as_ref().is_some_and(|spill| spill.full());
+    drop(guard);
+    xray::annotate("threads_used", threads_used);
+    let pipeline = match verifier {
+        Some(verifier) => Some(run_phase_async(&mut phases, "verification", verifier.finish()).await.map_err(HandlerError::Hashing)?),
+        None => None,
+    };
+    #[cfg(feature = "dynamodb")]
+    let persist = match persister {
+        Some(persister) => Some(run_phase_async(&mut phases, "persist", persister.finish()).await.map_err(HandlerError::Io)?),
+        None => None,
+    };
+    #[cfg(not(feature = "dynamodb"))]
+    let persist = None;
+    #[cfg(feature = "firehose")]
+    let output_firehose = match firehose {
+        Some(writer) => Some(run_phase_async(&mut phases, "output_firehose", writer.finish()).await.map_err(HandlerError::Io)?),
+        None => None,
+    };
+    #[cfg(not(feature = "firehose"))]
+    let output_firehose = None;
+    #[cfg(feature = "s3")]
+    let output_s3 = match s3_writer {
+        Some(writer) => Some(run_phase_async(&mut phases, "output_s3", writer.finish()).await.map_err(HandlerError::Io)?),
+        None => None,
+    };
+    #[cfg(not(feature = "s3"))]
+    let output_s3 = None;
+    // Runs without downstream calls report a limit only when the request set one
+    let rate_limit = limiter.summary().filter(|summary| summary.requests > 0 || request.rate_limit.is_some());
+    let (checkpoint, job_outputs) = match checkpointer {
+        Some(checkpointer) => {
+            let (summary, outputs) = run_phase_async(&mut phases, "checkpoint", checkpointer.finish()).await.map_err(HandlerError::Io)?;
+            (Some(summary), Some(outputs))
+        }
+        None => (None, None),
+    };
+    let duration_ms = phases["processing"] as u128;
+    let mut failures = std::mem::take(&mut *failures.lock().unwrap());
+    failures.sort_by_key(|failure| failure.index);
+    let failed = failures.len();
+    let skipped = count - results.len() - failed;
+    // A resumed job reports the failures of the whole job
+    let failures = [restored_failures, failures].concat();
+    let failures: Option<Vec<ItemFailure>> = (request.on_error == OnError::Continue || !failures.is_empty()).then_some(failures);
+    let budget = request.time_budget_ms.zip(budget_end).map(|(time_budget_ms, end)| BudgetSummary {
+        time_budget_ms,
+        elapsed_ms: invocation_start.elapsed().as_secs_f64() * 1000.0,
+        exhausted: Instant::now() >= end,
+        remaining: skipped,
+    });
+    // Only a guard that tripped before the last item started left anything out
+    let aborted_reason = match (memory_pressure, storage_full) {
+        _ if skipped == 0 => None,
+        (true, _) => Some("memory_pressure"),
+        (_, true) => Some("ephemeral_storage_full"),
+        _ => None,
+    };
+    // Nothing to return: fail so the caller can retry with more time instead of getting an empty result
+    if results.is_empty() && failed == 0 && aborted_reason.is_none() { return Err(HandlerError::Timeout { skipped }); }
+    if let Some(reason) = aborted_reason {
+        tracing::warn!(completed = results.len(), skipped, reason, "batch aborted, returning partial results");
+    } else if skipped > 0 && budget.as_ref().is_some_and(|budget| budget.exhausted) {
+        tracing::info!(completed = results.len(), skipped, "time budget used up, returning partial results");
+    } else if skipped > 0 {
+        tracing::warn!(completed = results.len(), skipped, "deadline approaching, returning partial results");
+    }
+    if failed > 0 {
+        tracing::warn!(completed = results.len(), failed, "items failed, continuing with the rest");
+    }
+    tracing::info!(mode = mode.as_str(), workload = workload.name(), processed = results.len(), threads_used, duration_ms, "processing complete");
+    metrics::emit(&[("Mode", mode.as_str()), ("Workload", workload.name())], &[
+        Metric::count("ProcessedItems", results.len() as f64),
+        Metric::milliseconds("DurationMs", phases["processing"]),
+        Metric::count("ThreadsUsed", threads_used as f64),
+        Metric::count("FailedItems", failed as f64),
+        Metric::count("ItemRetries", retry.as_ref().map_or(0, |retry| retry.retries.load(Ordering::Relaxed)) as f64),
+    ]);
+    let processed = results.len();
+    let ms_per_item = phases["processing"] / processed as f64;
+    // A complete sequential run becomes the baseline; any other mode is compared against it
+    let key = BaselineKey { count, payload_bytes, workload: workload.name(), tuned: tuned.clone() };
+    let speedup = if mode == Mode::Sequential {
+        if skipped == 0 && failed == 0 { sequential_baseline().lock().unwrap().insert(key, ms_per_item); }
+        None
+    } else {
+        sequential_baseline().lock().unwrap().get(&key).map(|baseline| baseline / ms_per_item)
+    };
+    let thread_stats = thread_stats(&results);
+    let latency = latency_stats(&results, &thread_stats);
+    let verification = (workload.name() == "bcrypt_verify").then(|| {
+        let matches: Vec<bool> = results.iter().map(|outcome| outcome.output == "true").collect();
+        let matched = matches.iter().filter(|matched| **matched).count();
+        VerificationSummary { verified: matches.len(), matched, mismatched: matches.len() - matched, matches }
+    });
+    let concurrency = (mode == Mode::AsyncLimited).then(|| ConcurrencySummary {
+        permits,
+        peak_in_flight: peak_in_flight.load(Ordering::Relaxed),
+        mean_in_flight: results.iter().map(|outcome| outcome.duration_us as f64 / 1000.0).sum::<f64>() / phases["processing"].max(f64::EPSILON),
+    });
+    let nesting = tuned.salts.map(crate::nested::summary);
+    #[cfg(feature = "compress")]
+    let compression = tuned.compress.map(|settings| {
+        crate::compress::summarize(settings, results.iter().map(|outcome| outcome.output.as_str()), phases["processing"])
+    });
+    #[cfg(not(feature = "compress"))]
+    let compression: Option<CompressionSummary> = None;
+    let continuation = continued.then(|| {
+        // Failed items are done too: a retry of the next slice must not run them again
+        let done: HashSet<usize> = results.iter().map(|outcome| outcome.index)
+            .chain(failures.iter().flatten().map(|failure| failure.index)).collect();
+        let contiguous = (offset..offset + count).take_while(|index| done.contains(index)).count();
+        let next_offset = offset + contiguous;
+        ContinuationState { offset, next_offset, total_items: total, done: next_offset >= total }
+    });
+    let memory = MemoryStats::capture();
+    let cpu_time_ms = cpu_start.zip(process_cpu_time_ms()).map(|(start, end)| end - start);
+    let wall_ms = invocation_start.elapsed().as_secs_f64() * 1000.0;
+
+    // A checkpointed job's digest covers every item it completed, in earlier invocations too
+    let checksum = (returns == Returns::Checksum).then(|| {
+        let mut outcomes: Vec<&ItemOutcome> = results.iter().collect();
+        outcomes.sort_unstable_by_key(|outcome| outcome.index);
+        let outputs: Vec<&str> = match &job_outputs {
+            Some(outputs) => outputs.iter().map(String::as_str).collect(),
+            None => outcomes.iter().map(|outcome| outcome.output.as_str()).collect(),
+        };
+        let mut hasher = Sha256::new();
+        for output in &outputs {
+            hasher.update(output.as_bytes());
+            hasher.update(b"\n");
+        }
+        ResultChecksum { sha256: format!("{:x}", hasher.finalize()), items: outputs.len() }
+    });
+    let spill = match spill.filter(|spill| spill.spilled()) {
+        Some(spill) => {
+            let results = results.iter().map(|outcome| (outcome.index - offset, ItemResult::from(outcome)));
+            run_phase_async(&mut phases, "spill", spill.finish(results, request.spill_s3)).await.map_err(HandlerError::Io)?
+        }
+        None => None,
+    };
+    let results = (returns == Returns::Items && inline_results && spill.is_none()).then(|| {
+        results.iter().map(ItemResult::from).collect()
+    });
+
+    let response = ProcessResponse {
+        processed,
         duration_ms,
-        mode: mode.to_string(),
-        workers: if mode == "parallel" { workers } else { 1 },
-        detected_cpus: num_cpus::get(),
-        avg_ms_per_item: duration_ms as f64 / request.count as f64,
-        memory_used_kb: get_memory_usage_kb(),
+        mode,
+        workload: workload.name().to_string(),
+        workers: if mode == Mode::Sequential { 1 } else { workers },
+        worker_source,
+        detected_cpus: detected_cpus(),
+        effective_cpus: effective_cpus(),
+        platform: platform(),
+        recommended_workers: recommended_workers(),
+        avg_ms_per_item: duration_ms as f64 / processed.max(1) as f64,
+        payload_bytes_total: payload_bytes,
+        cpu_time_ms,
+        cpu_utilization: cpu_time_ms.map(|cpu_ms| cpu_ms / wall_ms.max(f64::EPSILON)),
+        context_switches,
+        speedup,
+        parallel_efficiency: speedup.map(|speedup| speedup / workers as f64),
+        latency,
+        memory_used_kb: memory.rss_kb,
+        memory,
+        allocator: memory::ALLOCATOR_NAME,
         threads_used,
-    })
+        completed: restored_completed + processed,
+        skipped,
+        chunk_size,
+        tuned,
+        generator: custom_generator,
+        pepper,
+        results,
+        checksum,
+        failures,
+        retry: retry.map(|retry| retry.summary()),
+        concurrency,
+        priority: priorities.map(|priorities| priorities.summary()),
+        budget,
+        aborted_reason,
+        persist,
+        output_firehose,
+        notification: None,
+        rate_limit,
+        verification,
+        nesting,
+        instruction_set: (workload.name() == "simd_hash").then(|| crate::simd::detect().as_str()),
+        compression,
+        source_s3,
+        output_s3,
+        spill,
+        profile,
+        pipeline,
+        auto,
+        continuation,
+        checkpoint,
+        thread_stats,
+        cache_hit: false,
+        idempotency: idempotency_key.clone().map(|(key, _)| IdempotencyState { key, replayed: false }),
+        lifecycle: lifecycle::current(),
+        phases,
+        encoding: request.compress_response.then_some(RESPONSE_ENCODING),
+    };
+    // A map-reduce slice stores its response for the reducer before anything remembers the run, so
+    // when the write fails the invocation fails and Lambda's retry of the slice writes it again
+    #[cfg(feature = "s3")]
+    if let Some(target) = &request.partial_s3 {
+        crate::mapreduce::write_part(target, &response).await.map_err(HandlerError::Io)?;
+    }
+    #[cfg(feature = "sns")]
+    let mut response = response;
+    #[cfg(feature = "sns")]
+    if let Some(topic_arn) = request.notify_topic_arn.clone() {
+        let message = response.completion(&context.request_id);
+        response.notification = Some(run_phase_async(&mut response.phases, "notify", crate::notify::publish(topic_arn, message)).await);
+    }
+    // Partial (deadline-cut) runs are not cached, so a warm retry can complete the batch, nor are
+    // spilled ones, whose file the next spill removes
+    if let Some(key) = cache_key.filter(|_| skipped == 0 && response.spill.is_none()) {
+        result_cache().lock().unwrap().put(key, response.clone());
+    }
+    // Likewise only complete runs are stored; a failed write only means a retry runs again
+    if let Some((key, fingerprint)) = idempotency_key.filter(|_| skipped == 0) {
+        #[cfg(feature = "dynamodb")]
+        if let Some(table) = crate::idempotency::table() {
+            if let Err(e) = crate::idempotency::save(&table, &key, &fingerprint, &response).await {
+                tracing::warn!(idempotency_key = %key, table, error = %e, "storing the response for idempotency failed");
+            }
+        }
+        idempotency_store().lock().unwrap().put(key, (fingerprint, response.clone()));
+    }
+    Ok(HandlerResponse::Process(Box::new(response)))
 }
@@ -0,0 +1,40 @@
+use lambda_http::{Body, Error, Request, RequestExt, Response};
+use crate::error::{invalid, HandlerError};
+use crate::handler::function_handler;
+use crate::schema;
+use crate::lifecycle;
+
+fn json_response(status: u16, body: serde_json::Value) -> Result<Response<Body>, Error> {
+    Ok(Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?)
+}
+
+/// Function URL / API Gateway entrypoint: the JSON body is a ProcessRequest (or a v1 request)
+/// 400 for malformed bodies and validation errors, 504 when the deadline left no time for any item,
+/// 503 for a failed health check, 500 for processing failures; error bodies are HandlerError::to_json()
+pub async fn http_handler(request: Request) -> Result<Response<Body>, Error> {
+    lifecycle::begin_invocation();
+    let parsed = serde_json::from_slice(request.body().as_ref()).and_then(schema::parse);
+    let (version, process_request) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return json_response(400, invalid("body", format!("invalid request body: {}", e)).to_json()),
+    };
+
+    let context = request.lambda_context();
+    #[cfg(feature = "telemetry")]
+    let start = std::time::Instant::now();
+    let response = match function_handler(process_request, &context).await {
+        Ok(response) => json_response(200, schema::respond(version, response)?),
+        Err(e) => json_response(match e {
+            HandlerError::Validation(_) => 400,
+            HandlerError::Timeout { .. } => 504,
+            HandlerError::Unhealthy(_) => 503,
+            HandlerError::Hashing(_) | HandlerError::Io(_) => 500,
+        }, e.to_json()),
+    };
+    #[cfg(feature = "telemetry")]
+    crate::telemetry::record_handler(&context.request_id, start.elapsed().as_secs_f64() * 1000.0);
+    response
+}
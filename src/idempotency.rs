@@ -0,0 +1,56 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::persist::client;
+
+// How long a stored response is replayed; the table's TTL attribute removes it afterwards
+const RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Table shared by every container of the function, from IDEMPOTENCY_TABLE; unset keeps idempotency
+// in-container only
+pub fn table() -> Option<String> {
+    env::var("IDEMPOTENCY_TABLE").ok().filter(|table| !table.is_empty())
+}
+
+// The response stored for `key`, as JSON, with the fingerprint of its request (none for items
+// written before fingerprints were), unless it has expired (TTL deletion can lag by hours)
+pub async fn load(table: &str, key: &str) -> Result<Option<(Option<String>, serde_json::Value)>, Error> {
+    let output = client().await.get_item().table_name(table)
+        .key("id", AttributeValue::S(key.to_string()))
+        .consistent_read(true)
+        .send().await?;
+    let Some(item) = output.item else { return Ok(None) };
+    let expires_at = item.get("expires_at").and_then(|value| value.as_n().ok()).and_then(|n| n.parse::<u64>().ok());
+    if expires_at.is_some_and(|expires_at| expires_at <= now_secs()) { return Ok(None); }
+    let fingerprint = item.get("fingerprint").and_then(|value| value.as_s().ok()).cloned();
+    match item.get("response").and_then(|value| value.as_s().ok()) {
+        Some(response) => Ok(Some((fingerprint, serde_json::from_str(response)?))),
+        None => Ok(None),
+    }
+}
+
+// Store `response` under `key` unless another invocation stored one first: the first completed
+// run wins, so every retry replays the same result. An expired item that TTL has not deleted yet
+// is absent to `load`, so it is overwritten like a missing one
+pub async fn save(table: &str, key: &str, fingerprint: &str, response: &impl serde::Serialize) -> Result<(), Error> {
+    let now = now_secs();
+    let result = client().await.put_item().table_name(table)
+        .item("id", AttributeValue::S(key.to_string()))
+        .item("fingerprint", AttributeValue::S(fingerprint.to_string()))
+        .item("response", AttributeValue::S(serde_json::to_string(response)?))
+        .item("expires_at", AttributeValue::N((now + RETENTION.as_secs()).to_string()))
+        .condition_expression("attribute_not_exists(id) OR expires_at <= :now")
+        .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+        .send().await;
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_conditional_check_failed_exception()) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
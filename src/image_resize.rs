@@ -0,0 +1,80 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+use crate::registry::{register_workload, ObjectSource, Objects, WorkloadExecutor};
+use crate::workload::{Workload, WorkloadParams};
+
+// Built-in source image: a 1080p frame, the typical size of an uploaded photo
+const EMBEDDED_WIDTH: u32 = 1920;
+const EMBEDDED_HEIGHT: u32 = 1080;
+const JPEG_QUALITY: u8 = 85;
+
+// Encoded once per container, so items only pay for decode, resize and encode
+static EMBEDDED_JPEG: OnceLock<Vec<u8>> = OnceLock::new();
+
+// Decode a JPEG, scale it to fit max_dimension x max_dimension (Lanczos3, aspect ratio kept) and
+// re-encode it: the thumbnail pipeline behind most multithreaded image Lambdas
+// Items are S3 object keys when the request has `source_s3`; otherwise every item decodes the
+// embedded image. The output is "<width>x<height>:<hex SHA-256 of the thumbnail JPEG>"
+pub struct ImageResize {
+    pub objects: Option<Objects>,
+    pub max_dimension: u32,
+}
+
+impl Workload for ImageResize {
+    fn name(&self) -> &'static str { "image_resize" }
+    // Decoded RGB frame of the embedded image plus the thumbnail and the encoded output
+    fn working_set_bytes(&self) -> usize {
+        let thumbnail = self.max_dimension as usize * self.max_dimension as usize * 3;
+        EMBEDDED_WIDTH as usize * EMBEDDED_HEIGHT as usize * 3 + 2 * thumbnail
+    }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let jpeg = match &self.objects {
+            Some(objects) => objects.get(item).ok_or_else(|| format!("object '{}' was not downloaded", item))?,
+            None => embedded_jpeg(),
+        };
+        let decoded = image::load_from_memory_with_format(jpeg, ImageFormat::Jpeg)
+            .map_err(|e| format!("'{}' is not a decodable JPEG: {}", item, e))?;
+        let thumbnail = decoded.resize(self.max_dimension, self.max_dimension, FilterType::Lanczos3);
+        let encoded = encode(&thumbnail)?;
+        Ok(format!("{}x{}:{:x}", thumbnail.width(), thumbnail.height(), Sha256::digest(&encoded)))
+    }
+}
+
+struct ImageResizeExecutor;
+
+impl WorkloadExecutor for ImageResizeExecutor {
+    fn name(&self) -> &'static str { "image_resize" }
+    fn object_source(&self) -> ObjectSource { ObjectSource::Optional }
+
+    fn prepare(&self, params: &WorkloadParams, objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(ImageResize { objects, max_dimension: params.max_dimension }))
+    }
+}
+
+register_workload!(ImageResizeExecutor);
+
+fn embedded_jpeg() -> &'static [u8] {
+    EMBEDDED_JPEG.get_or_init(|| {
+        // Gradients under a ripple, so the encoder and the resampler both see real detail
+        let frame = RgbImage::from_fn(EMBEDDED_WIDTH, EMBEDDED_HEIGHT, |x, y| {
+            let ripple = ((x as f32 / 37.0).sin() * (y as f32 / 23.0).cos() + 1.0) * 127.5;
+            Rgb([(x * 255 / EMBEDDED_WIDTH) as u8, (y * 255 / EMBEDDED_HEIGHT) as u8, ripple as u8])
+        });
+        encode(&DynamicImage::ImageRgb8(frame)).expect("the embedded image encodes")
+    })
+}
+
+// JPEG only stores 8-bit RGB or grayscale; anything else is converted to RGB first
+fn encode(image: &DynamicImage) -> image::ImageResult<Vec<u8>> {
+    let mut out = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY);
+    match image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageLuma8(_) => image.write_with_encoder(encoder)?,
+        _ => DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?,
+    }
+    Ok(out)
+}
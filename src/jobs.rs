@@ -0,0 +1,152 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use lambda_runtime::Context;
+use crate::error::HandlerError;
+use crate::handler::{JobCounts, ProcessRequest};
+use crate::persist::client;
+
+// What RUNNING, COMPLETED and FAILED set, cleared when a retry receives the job again
+const ATTEMPT_FIELDS: &[&str] = &["started_at", "items", "workers", "finished_at", "processed", "completed", "skipped", "failed", "partial", "error_code", "error"];
+
+// How long a job's record stays pollable; the table's TTL attribute removes it afterwards
+const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// Job lifecycle in JOB_TABLE (string partition key `id`): RECEIVED when the invocation arrives,
+// RUNNING once the request passed validation, then COMPLETED or FAILED. Every transition is a
+// conditional update on the current status, so a late or duplicate invocation cannot move a job
+// backwards; tracking never fails the request, a write that does not go through is only logged
+pub struct TrackedJob {
+    table: String,
+    id: String,
+}
+
+// Table from JOB_TABLE; unset turns tracking off
+pub fn table() -> Option<String> {
+    env::var("JOB_TABLE").ok().filter(|table| !table.is_empty())
+}
+
+impl TrackedJob {
+    // The job ID is the request's idempotency_key when it has one (which the caller knows before
+    // invoking), otherwise the Lambda request ID, with `#<index>` for the jobs of a batch. A
+    // retry of a job that did not complete (Lambda's asynchronous retries keep the request ID)
+    // starts it again as RECEIVED and counts the attempt; None when tracking is off or the job
+    // already completed
+    pub async fn receive(request: &ProcessRequest, context: &Context, batch_index: Option<usize>) -> Option<TrackedJob> {
+        let table = table()?;
+        let id = match (&request.idempotency_key, batch_index) {
+            (Some(key), _) => key.clone(),
+            (None, Some(index)) => format!("{}#{}", context.request_id, index),
+            (None, None) => context.request_id.clone(),
+        };
+        let total_items = request.items.as_ref().map(Vec::len)
+            .or(request.items_base64.as_ref().map(Vec::len))
+            .or(request.pairs.as_ref().map(Vec::len))
+            .unwrap_or(request.count);
+        let job = TrackedJob { table, id };
+        job.transition("RECEIVED", &["COMPLETED"], false, vec![
+            ("received_at", number(now_ms())),
+            ("request_id", AttributeValue::S(context.request_id.clone())),
+            ("workload", AttributeValue::S(request.workload.clone())),
+            ("mode", AttributeValue::S(request.mode.as_str().to_string())),
+            ("total_items", number(total_items)),
+            ("expires_at", number(now_ms() / 1000 + RETENTION.as_secs())),
+        ]).await.then_some(job)
+    }
+
+    // `items` is this invocation's share of the job (a continuation slice's limit)
+    pub async fn running(&self, items: usize, workers: usize) {
+        self.transition("RUNNING", &["RECEIVED"], true, vec![
+            ("started_at", number(now_ms())),
+            ("items", number(items)),
+            ("workers", number(workers)),
+        ]).await;
+    }
+
+    pub async fn complete(&self, counts: JobCounts) {
+        self.transition("COMPLETED", &["RECEIVED", "RUNNING"], true, vec![
+            ("finished_at", number(now_ms())),
+            ("processed", number(counts.processed)),
+            ("completed", number(counts.completed)),
+            ("skipped", number(counts.skipped)),
+            ("failed", number(counts.failed)),
+            ("partial", AttributeValue::Bool(counts.skipped > 0)),
+        ]).await;
+    }
+
+    pub async fn fail(&self, error: &HandlerError) {
+        self.transition("FAILED", &["RECEIVED", "RUNNING"], true, vec![
+            ("finished_at", number(now_ms())),
+            ("error_code", AttributeValue::S(error.code().to_string())),
+            ("error", AttributeValue::S(error.to_string())),
+        ]).await;
+    }
+
+    // Set `status` and `fields`. With `from_one_of` the job must be in one of those statuses, or
+    // without it (`receive`) in none of them, a missing item included; durations follow from the
+    // timestamps, so a poller can tell queueing, validation and processing apart
+    async fn transition(&self, status: &str, statuses: &[&str], from_one_of: bool, fields: Vec<(&str, AttributeValue)>) -> bool {
+        let mut names = HashMap::from([("#status".to_string(), "status".to_string())]);
+        let mut values = HashMap::from([(":status".to_string(), AttributeValue::S(status.to_string()))]);
+        let mut set = vec!["#status = :status".to_string()];
+        for (name, value) in fields {
+            names.insert(format!("#{}", name), name.to_string());
+            values.insert(format!(":{}", name), value);
+            set.push(format!("#{} = :{}", name, name));
+        }
+        let allowed: Vec<String> = statuses.iter().enumerate().map(|(index, allowed)| {
+            values.insert(format!(":from{}", index), AttributeValue::S(allowed.to_string()));
+            format!(":from{}", index)
+        }).collect();
+        let mut update = format!("SET {}", set.join(", "));
+        let condition = if from_one_of {
+            format!("#status IN ({})", allowed.join(", "))
+        } else {
+            // the attempt counter only moves on RECEIVED, which starts from a clean attempt
+            values.insert(":one".to_string(), number(1));
+            values.insert(":zero".to_string(), number(0));
+            names.insert("#attempts".to_string(), "attempts".to_string());
+            update.push_str(", #attempts = if_not_exists(#attempts, :zero) + :one REMOVE ");
+            update.push_str(&ATTEMPT_FIELDS.iter().map(|name| {
+                names.insert(format!("#{}", name), name.to_string());
+                format!("#{}", name)
+            }).collect::<Vec<_>>().join(", "));
+            format!("attribute_not_exists(id) OR NOT (#status IN ({}))", allowed.join(", "))
+        };
+        let result = client().await.update_item().table_name(&self.table)
+            .key("id", AttributeValue::S(self.id.clone()))
+            .update_expression(update)
+            .condition_expression(condition)
+            .set_expression_attribute_names(Some(names))
+            .set_expression_attribute_values(Some(values))
+            .send().await;
+        match result {
+            Ok(_) => {
+                tracing::info!(job_id = %self.id, table = %self.table, status, "job status updated");
+                true
+            }
+            // A completed job seen again is a replay (its idempotency_key, or a duplicate delivery)
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_conditional_check_failed_exception()) => {
+                if from_one_of {
+                    tracing::warn!(job_id = %self.id, table = %self.table, status, "job is not in a status that allows this transition, not updated");
+                } else {
+                    tracing::info!(job_id = %self.id, table = %self.table, "job already completed, not tracked again");
+                }
+                false
+            }
+            Err(e) => {
+                tracing::warn!(job_id = %self.id, table = %self.table, status, error = %aws_sdk_dynamodb::error::DisplayErrorContext(e), "job status update failed");
+                false
+            }
+        }
+    }
+}
+
+fn number(n: impl ToString) -> AttributeValue {
+    AttributeValue::N(n.to_string())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
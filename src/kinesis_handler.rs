@@ -0,0 +1,76 @@
+use aws_lambda_events::event::kinesis::KinesisEvent;
+use aws_lambda_events::event::streams::KinesisEventResponse;
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Instant;
+use crate::handler::process_each;
+use crate::workload::{get_workload, WorkloadParams};
+
+/// Kinesis event source mappings put records in Records[] with eventSource "aws:kinesis"
+pub fn is_kinesis_event(payload: &serde_json::Value) -> bool {
+    payload["Records"][0]["eventSource"] == "aws:kinesis"
+}
+
+// Per-shard totals for the log; sequence numbers are those of the shard's first and last record
+#[derive(Default)]
+struct ShardStats<'a> {
+    records: usize, succeeded: usize, failed: usize, bytes: usize,
+    first_sequence: &'a str, last_sequence: &'a str, first_failure: Option<&'a str>,
+}
+
+/// Process every record's data (base64-decoded by the event type) in parallel across all shards,
+/// then log aggregates per shard. A batch holds each shard's records in sequence order, and so do
+/// the results, so checkpointing stays per shard: for every shard with a failed record only the
+/// first one is reported, and Lambda retries that shard from it (requires ReportBatchItemFailures
+/// on the event source mapping); later records of that shard are retried along with it
+pub async fn kinesis_handler(event: KinesisEvent) -> Result<KinesisEventResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let workload_name = env::var("KINESIS_WORKLOAD").unwrap_or_else(|_| "bcrypt".to_string());
+    let workload = get_workload(&workload_name, &WorkloadParams::default())
+        .ok_or_else(|| format!("unknown workload '{}' in KINESIS_WORKLOAD", workload_name))?;
+
+    // (shard, sequence number, bytes, runnable); eventID is "<shard id>:<sequence number>"
+    // Records that are empty or not UTF-8 cannot be items and fail without running
+    let mut records = Vec::with_capacity(event.records.len());
+    let mut items = Vec::new();
+    for record in &event.records {
+        let shard = record.event_id.as_deref().and_then(|id| id.split_once(':')).map_or("unknown", |(shard, _)| shard);
+        let data = std::str::from_utf8(&record.kinesis.data).ok().filter(|data| !data.is_empty());
+        records.push((shard, record.kinesis.sequence_number.as_str(), record.kinesis.data.len(), data.is_some()));
+        items.extend(data.map(str::to_string));
+    }
+
+    let start = Instant::now();
+    let (results, threads_used) = process_each(items, workload.as_ref());
+    let duration_ms = start.elapsed().as_millis();
+
+    let mut results = results.into_iter();
+    let mut shards: BTreeMap<&str, ShardStats> = BTreeMap::new();
+    for &(shard, sequence, bytes, runnable) in &records {
+        let result = if runnable { results.next().unwrap_or(Err("missing result".to_string())) } else { Err("empty or non-UTF-8 data".to_string()) };
+        let stats = shards.entry(shard).or_default();
+        if stats.records == 0 { stats.first_sequence = sequence; }
+        stats.records += 1;
+        stats.bytes += bytes;
+        stats.last_sequence = sequence;
+        match result {
+            Ok(_) => stats.succeeded += 1,
+            Err(e) => {
+                tracing::warn!(shard, sequence_number = sequence, error = %e, "record failed");
+                stats.failed += 1;
+                stats.first_failure.get_or_insert(sequence);
+            }
+        }
+    }
+    for (shard, stats) in &shards {
+        tracing::info!(
+            shard, records = stats.records, succeeded = stats.succeeded, failed = stats.failed, bytes = stats.bytes,
+            first_sequence = stats.first_sequence, last_sequence = stats.last_sequence, "kinesis shard complete"
+        );
+    }
+    let failed: usize = shards.values().map(|stats| stats.failed).sum();
+    tracing::info!(records = records.len(), shards = shards.len(), succeeded = records.len() - failed, failed, duration_ms, threads_used, "kinesis batch complete");
+
+    let mut response = KinesisEventResponse::default();
+    response.set_failures(shards.values().filter_map(|stats| stats.first_failure));
+    Ok(response)
+}
@@ -0,0 +1,95 @@
+//! The processing core: the handler, its workloads and execution modes, and the event-source
+//! adapters. The Lambda entrypoint (main.rs), the dev server and the local_bench runner are thin
+//! binaries over it, and other projects can depend on it the same way.
+//!
+//! - [`router::route`] answers any event the function accepts (a request, a batch envelope, SQS,
+//!   Kinesis, EventBridge, S3 notifications, schema v1) with the JSON the function would return
+//! - [`handler::function_handler`] runs one [`handler::ProcessRequest`]; its
+//!   [`handler::HandlerResponse`] depends on the mode, and [`handler::serialize_response`] turns it
+//!   into the response JSON
+//! - [`prewarm::initialize`] is the cold start (configuration, Rayon pool, warm-up); without it
+//!   the handler runs on Rayon's default global pool
+//! - [`workload`] and [`registry`] hold the workloads and the [`register_workload!`] registry a
+//!   dependent crate adds its own to; [`config`] the settings and [`metrics`] the EMF output
+//!
+//! Nothing needs the Lambda runtime: a default `Context` has no deadline, so every item runs.
+//!
+//! ```
+//! use lambda_runtime::Context;
+//! use rust_multithread_lambda::router::route;
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let request = serde_json::json!({ "mode": "parallel", "workload": "monte_carlo", "count": 4, "samples": 1000 });
+//! let response = route(request, &Context::default()).await.unwrap();
+//! assert_eq!(response["processed"], 4);
+//! # });
+//! ```
+mod adaptive;
+mod blobs;
+pub mod config;
+mod cpu_info;
+mod describe;
+pub mod error;
+mod generator;
+pub mod handler;
+pub mod lifecycle;
+pub mod schema;
+mod memory;
+pub mod metrics;
+mod pepper;
+mod pipeline;
+mod platform;
+mod pool;
+pub mod registry;
+pub mod prewarm;
+mod progress;
+mod validation;
+pub mod workload;
+mod xray;
+pub mod sqs_handler;
+pub mod kinesis_handler;
+pub mod schedule_handler;
+mod simd;
+mod nested;
+mod spill;
+mod checkpoint;
+mod rate_limit;
+pub mod router;
+#[cfg(feature = "s3")]
+mod s3_checksum;
+#[cfg(feature = "s3")]
+pub mod s3_handler;
+#[cfg(feature = "s3")]
+mod mapreduce;
+#[cfg(feature = "s3")]
+mod s3_output;
+#[cfg(feature = "http")]
+pub mod http_handler;
+#[cfg(feature = "streaming")]
+pub mod stream_handler;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "dynamodb")]
+mod persist;
+#[cfg(feature = "firehose")]
+mod firehose;
+#[cfg(feature = "fanout")]
+pub mod fanout;
+#[cfg(feature = "sns")]
+mod notify;
+#[cfg(feature = "dynamodb")]
+mod idempotency;
+#[cfg(feature = "dynamodb")]
+mod jobs;
+#[cfg(feature = "image")]
+mod image_resize;
+#[cfg(feature = "parquet")]
+mod csv_parquet;
+#[cfg(feature = "compress")]
+mod compress;
+#[cfg(feature = "profiling")]
+mod profiling;
@@ -0,0 +1,138 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+// Set once when the process starts; the first invocation measures the init phase against it
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+static INIT_DURATION_MS: OnceLock<f64> = OnceLock::new();
+static WARMUP_MS: OnceLock<f64> = OnceLock::new();
+static INIT_REPORT: OnceLock<InitReport> = OnceLock::new();
+static INVOCATIONS: AtomicU64 = AtomicU64::new(0);
+static JOBS: Mutex<JobTotals> = Mutex::new(JobTotals { jobs: 0, items: 0, min_duration_ms: f64::INFINITY, max_duration_ms: 0.0 });
+
+/// Where an invocation falls in the execution environment's lifecycle
+#[derive(Clone, Copy, Serialize)]
+pub struct Lifecycle {
+    pub cold_start: bool,        // first invocation handled by this execution environment
+    pub invocation_number: u64,  // 1-based count of invocations in this environment
+    pub init_duration_ms: f64,   // process start to first invocation (runtime and thread pool setup)
+    pub warmup_ms: f64,          // thread pool warm-up during init (part of init_duration_ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init: Option<&'static InitReport>,  // first invocation only
+}
+
+/// What the init phase did before the runtime loop (prewarm::initialize)
+#[derive(Serialize)]
+pub struct InitReport {
+    pub initialization_type: String,  // AWS_LAMBDA_INITIALIZATION_TYPE, "on-demand" outside Lambda
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConfigSummary>,  // CONFIG_S3_URI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssm: Option<ConfigSummary>,     // CONFIG_SSM_PREFIX, with uri "ssm:<prefix>"
+    pub pregenerated_items: usize,
+    pub pregeneration_ms: f64,
+}
+
+/// The settings fetched during init: a file from CONFIG_S3_URI or SSM parameters (config.rs)
+#[derive(Serialize)]
+pub struct ConfigSummary {
+    pub uri: String,
+    pub applied: Vec<String>,  // settings taken from the source
+    pub ignored: Vec<String>,  // S3: already set on the function, which wins; SSM: not a tunable
+    pub fetch_ms: f64,
+}
+
+/// Aggregates over the life of the execution environment, in every response as `container_stats`,
+/// so a series of responses shows how a warm container's performance drifts
+#[derive(Clone, Copy, Serialize)]
+pub struct ContainerStats {
+    pub invocations: u64,
+    pub jobs: u64,               // requests run (each job of a batch envelope counts)
+    pub items: u64,              // items processed by those jobs; cache hits and replays add none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_time_ms: Option<f64>,  // CPU time of the process since it started, init included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_duration_ms: Option<f64>,  // fastest and slowest job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<f64>,
+    pub lifetime_ms: f64,        // since the process started
+}
+
+struct JobTotals {
+    jobs: u64, items: u64, min_duration_ms: f64, max_duration_ms: f64,
+}
+
+/// Call first thing in main: init_duration_ms and lifetime_ms are measured from here
+pub fn mark_process_start() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+/// The pool warm-up of the init, reported in every response's lifecycle
+pub fn record_warmup(ms: f64) {
+    let _ = WARMUP_MS.set(ms);
+}
+
+/// Mark the end of init, just before the runtime loop starts polling for invocations
+/// With provisioned concurrency the first invocation can come minutes later, so init_duration_ms
+/// is measured here rather than when that invocation begins
+pub fn record_init(report: InitReport) {
+    INIT_DURATION_MS.get_or_init(|| PROCESS_START.get().map_or(0.0, |start| start.elapsed().as_secs_f64() * 1000.0));
+    let _ = INIT_REPORT.set(report);
+}
+
+/// Count a new invocation; call once at the start of every entrypoint
+/// Without record_init (local runs), init is measured up to the first invocation
+/// Lambda sends one invocation at a time per environment, so the counter identifies the current one
+pub fn begin_invocation() -> Lifecycle {
+    INIT_DURATION_MS.get_or_init(|| PROCESS_START.get().map_or(0.0, |start| start.elapsed().as_secs_f64() * 1000.0));
+    INVOCATIONS.fetch_add(1, Ordering::Relaxed);
+    current()
+}
+
+/// Add a finished job (successful or not) to the container's totals
+pub fn record_job(items: usize, duration_ms: f64) {
+    let mut totals = JOBS.lock().unwrap();
+    totals.jobs += 1;
+    totals.items += items as u64;
+    totals.min_duration_ms = totals.min_duration_ms.min(duration_ms);
+    totals.max_duration_ms = totals.max_duration_ms.max(duration_ms);
+}
+
+pub fn container_stats() -> ContainerStats {
+    let totals = JOBS.lock().unwrap();
+    let ran = totals.jobs > 0;
+    ContainerStats {
+        invocations: INVOCATIONS.load(Ordering::Relaxed),
+        jobs: totals.jobs,
+        items: totals.items,
+        cpu_time_ms: crate::cpu_info::process_cpu_time_ms(),
+        min_duration_ms: ran.then_some(totals.min_duration_ms),
+        max_duration_ms: ran.then_some(totals.max_duration_ms),
+        lifetime_ms: PROCESS_START.get().map_or(0.0, |start| start.elapsed().as_secs_f64() * 1000.0),
+    }
+}
+
+/// SIGTERM, as the execution environment shuts down (main's graceful shutdown hook): emit the
+/// container's lifetime totals, which would otherwise be lost with the sandbox, and send any
+/// X-Ray subsegments still pending, within the ~500 ms Lambda allows before SIGKILL
+pub fn shutdown() {
+    let invocations = INVOCATIONS.load(Ordering::Relaxed);
+    let lifetime_ms = PROCESS_START.get().map_or(0.0, |start| start.elapsed().as_secs_f64() * 1000.0);
+    crate::metrics::emit_totals(invocations, lifetime_ms);
+    crate::xray::flush();
+    tracing::info!(invocations, lifetime_ms, "shutting down");
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+}
+
+/// Lifecycle of the invocation in progress
+pub fn current() -> Lifecycle {
+    let invocation_number = INVOCATIONS.load(Ordering::Relaxed);
+    Lifecycle {
+        cold_start: invocation_number <= 1,
+        invocation_number,
+        init_duration_ms: INIT_DURATION_MS.get().copied().unwrap_or(0.0),
+        warmup_ms: WARMUP_MS.get().copied().unwrap_or(0.0),
+        init: INIT_REPORT.get().filter(|_| invocation_number <= 1),
+    }
+}
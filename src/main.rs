@@ -1,14 +1,92 @@
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
-mod handler;
-use handler::{function_handler, get_worker_count, init_thread_pool, ProcessRequest};
+use serde_json::Value;
+use tracing::Instrument;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::format::FmtSpan;
+use rust_multithread_lambda::router::route;
+use rust_multithread_lambda::{lifecycle, prewarm};
+#[cfg(feature = "http")]
+use rust_multithread_lambda::http_handler;
+#[cfg(feature = "streaming")]
+use rust_multithread_lambda::stream_handler;
+#[cfg(feature = "telemetry")]
+use rust_multithread_lambda::telemetry;
+#[cfg(feature = "dev-server")]
+mod dev_server;
+
+// JSON logs for CloudWatch; closing a span logs its duration (level from RUST_LOG, default info)
+fn init_tracing() {
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(EnvFilter::builder().with_default_directive(LevelFilter::INFO.into()).from_env_lossy())
+        .with_span_events(FmtSpan::CLOSE)
+        .with_span_list(true)
+        .with_target(false)
+        .without_time()
+        .init();
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize Rayon thread pool at cold start (once per container lifecycle)
-    init_thread_pool(get_worker_count());
+    lifecycle::mark_process_start();
+    init_tracing();
+
+    // Config fetch, Rayon thread pool and its warm-up, and item pre-generation, all inside the
+    // init phase (once per container lifecycle; ahead of any request with provisioned concurrency)
+    prewarm::initialize().await?;
+
+    // The Telemetry API extension also has to register before the runtime loop starts
+    #[cfg(feature = "telemetry")]
+    if std::env::var("HANDLER_MODE").as_deref() != Ok("dev") {
+        telemetry::start().await?;
+    }
+
+    // Lambda only sends SIGTERM to a runtime that has an extension registered, so this registers a
+    // no-op internal one (still in init, as extensions must be); not outside Lambda or for the dev server
+    if std::env::var("AWS_LAMBDA_RUNTIME_API").is_ok() && std::env::var("HANDLER_MODE").as_deref() != Ok("dev") {
+        lambda_runtime::spawn_graceful_shutdown_handler(|| async { lifecycle::shutdown() }).await;
+    }
+
+    if std::env::var("CONTENT_FORMAT").as_deref() == Ok("protobuf") && cfg!(not(feature = "protobuf")) {
+        return Err("CONTENT_FORMAT=protobuf requires building with the \"protobuf\" feature".into());
+    }
+    if std::env::var("CONTENT_FORMAT").as_deref() == Ok("msgpack") && cfg!(not(feature = "msgpack")) {
+        return Err("CONTENT_FORMAT=msgpack requires building with the \"msgpack\" feature".into());
+    }
+
+    // HANDLER_MODE=http serves Function URL / API Gateway requests instead of raw events
+    if std::env::var("HANDLER_MODE").as_deref() == Ok("http") {
+        #[cfg(feature = "http")]
+        return lambda_http::run(lambda_http::service_fn(http_handler::http_handler)).await;
+        #[cfg(not(feature = "http"))]
+        return Err("HANDLER_MODE=http requires building with the \"http\" feature".into());
+    }
+
+    // HANDLER_MODE=dev serves the same routing over local HTTP, without the Lambda runtime
+    if std::env::var("HANDLER_MODE").as_deref() == Ok("dev") {
+        #[cfg(feature = "dev-server")]
+        return dev_server::serve().await;
+        #[cfg(not(feature = "dev-server"))]
+        return Err("HANDLER_MODE=dev requires building with the \"dev-server\" feature".into());
+    }
+
+    // HANDLER_MODE=stream returns NDJSON item results incrementally (response streaming)
+    if std::env::var("HANDLER_MODE").as_deref() == Ok("stream") {
+        #[cfg(feature = "streaming")]
+        return run(service_fn(stream_handler::stream_handler)).await;
+        #[cfg(not(feature = "streaming"))]
+        return Err("HANDLER_MODE=stream requires building with the \"streaming\" feature".into());
+    }
 
-    run(service_fn(|event: LambdaEvent<ProcessRequest>| async move {
-        function_handler(event.payload).await
+    run(service_fn(|event: LambdaEvent<Value>| async move {
+        let lifecycle = lifecycle::begin_invocation();
+        let span = tracing::info_span!("invocation", request_id = %event.context.request_id, cold_start = lifecycle.cold_start);
+        #[cfg(feature = "telemetry")]
+        let start = std::time::Instant::now();
+        let response = route(event.payload, &event.context).instrument(span).await;
+        #[cfg(feature = "telemetry")]
+        telemetry::record_handler(&event.context.request_id, start.elapsed().as_secs_f64() * 1000.0);
+        response
     }))
     .await
 }
@@ -0,0 +1,233 @@
+use aws_sdk_s3::primitives::ByteStream;
+use lambda_runtime::Context;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::error::{invalid, HandlerError};
+use crate::handler::{Mode, ProcessResponse, ReduceConfig, S3OutputConfig};
+use crate::s3_handler::client;
+use crate::validation::ValidationError;
+
+// Map-reduce over S3: every slice of a fanout job with `results_s3` writes its response as one
+// part under the job prefix (the map side, write_part), and a mode "reduce" invocation waits for
+// the parts and folds them into one summary of the whole job, also written to the prefix
+
+// Parts of one job, as many as a fanout has slices
+const MAX_PARTS: usize = 1000;
+// Longest wait_seconds, how often the prefix is listed while parts are missing, and the time kept
+// back from the invocation deadline to read the parts and answer
+const MAX_WAIT_SECONDS: u64 = 900;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEADLINE_MARGIN_MS: u64 = 3000;
+// GetObject requests in flight at once while reading the parts
+const DOWNLOAD_CONCURRENCY: usize = 32;
+const PART_PREFIX: &str = "part-";
+const SUMMARY_KEY: &str = "summary.json";
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Key of part `index` under a job prefix, which fanout gives each slice; the index is
+// zero-padded so a listing is in slice order
+#[cfg(feature = "fanout")]
+pub fn part_key(prefix: &str, index: usize) -> String {
+    format!("{}{}{:05}.json", prefix, PART_PREFIX, index)
+}
+
+// Map side: store a slice's response as a part, with the time it finished, which the reducer's
+// makespan is measured to
+pub async fn write_part(target: &S3OutputConfig, response: &ProcessResponse) -> Result<(), Error> {
+    let body = serde_json::to_vec(&json!({ "finished_at_ms": now_ms(), "response": response }))?;
+    client().await.put_object()
+        .bucket(&target.bucket).key(&target.key)
+        .content_type("application/json")
+        .body(ByteStream::from(body))
+        .send().await
+        .map_err(|e| format!("s3://{}/{}: {}", target.bucket, target.key, aws_sdk_s3::error::DisplayErrorContext(e)))?;
+    tracing::info!(bucket = %target.bucket, key = %target.key, "map-reduce part written");
+    Ok(())
+}
+
+// Result of mode "reduce". The counts add up the parts found; `missing` lists the slices without a
+// part when the wait ended (never invoked, still running, or failed for good). `makespan_ms` runs
+// from the fanout's dispatch to the last part, so items_per_sec is the job's throughput across
+// every sub-invocation, to set against one in-function parallel run
+#[derive(Serialize)]
+pub struct ReduceResponse {
+    mode: Mode,
+    bucket: String,
+    prefix: String,
+    parts_expected: usize,
+    parts_found: usize,
+    complete: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing: Vec<usize>,
+    processed: u64, completed: u64, skipped: u64, failed: u64,
+    slice_ms_min: f64, slice_ms_mean: f64, slice_ms_max: f64,
+    cpu_time_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    makespan_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items_per_sec: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<PartsChecksum>,
+    polls: usize,
+    waited_ms: f64,
+    summary_uri: String,
+    slices: Vec<SliceSummary>,
+}
+
+// With return "checksum" on every slice: the SHA-256 of the slices' digests in slice order, each
+// followed by "\n", which is the same for the same job and slicing whichever order they finished in
+#[derive(Serialize)]
+struct PartsChecksum {
+    sha256: String, parts: usize,
+}
+
+#[derive(Serialize)]
+struct SliceSummary {
+    index: usize, offset: u64, completed: u64, skipped: u64, failed: u64, duration_ms: f64, finished_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aborted_reason: Option<String>,
+}
+
+pub async fn reduce(config: Option<ReduceConfig>, context: &Context) -> Result<ReduceResponse, HandlerError> {
+    let Some(mut config) = config else { return Err(invalid("reduce", "reduce mode requires reduce")) };
+    if !config.prefix.is_empty() && !config.prefix.ends_with('/') { config.prefix.push('/'); }
+    let mut errors = Vec::new();
+    if config.bucket.is_empty() { errors.push(ValidationError::new("reduce.bucket", "reduce.bucket must not be empty")); }
+    if !(1..=MAX_PARTS).contains(&config.parts) {
+        errors.push(ValidationError::new("reduce.parts", format!("reduce.parts must be between 1 and {}", MAX_PARTS)));
+    }
+    if config.wait_seconds > MAX_WAIT_SECONDS {
+        errors.push(ValidationError::new("reduce.wait_seconds", format!("reduce.wait_seconds must be at most {}", MAX_WAIT_SECONDS)));
+    }
+    if !errors.is_empty() { return Err(HandlerError::Validation(errors)); }
+
+    let start = Instant::now();
+    let wait = Duration::from_secs(config.wait_seconds);
+    let mut polls = 0;
+    let keys = loop {
+        let keys = list_parts(&config).await.map_err(HandlerError::Io)?;
+        polls += 1;
+        let out_of_time = context.deadline > 0 && now_ms() + POLL_INTERVAL.as_millis() as u64 + DEADLINE_MARGIN_MS >= context.deadline;
+        if keys.len() >= config.parts || start.elapsed() + POLL_INTERVAL > wait || out_of_time { break keys; }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+    let waited_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let parts = download(&config.bucket, keys).await.map_err(HandlerError::Io)?;
+
+    let mut response = fold(&config, parts);
+    response.polls = polls;
+    response.waited_ms = waited_ms;
+    let body = serde_json::to_vec(&response).map_err(|e| HandlerError::Io(e.into()))?;
+    client().await.put_object()
+        .bucket(&config.bucket).key(format!("{}{}", config.prefix, SUMMARY_KEY))
+        .content_type("application/json")
+        .body(ByteStream::from(body))
+        .send().await
+        .map_err(|e| HandlerError::Io(format!("{}: {}", response.summary_uri, aws_sdk_s3::error::DisplayErrorContext(e)).into()))?;
+    tracing::info!(prefix = %config.prefix, parts_found = response.parts_found, parts_expected = response.parts_expected, polls, "map-reduce job reduced");
+    Ok(response)
+}
+
+// Part indices found under the prefix, with their keys; any other object there is ignored
+async fn list_parts(config: &ReduceConfig) -> Result<BTreeMap<usize, String>, Error> {
+    let mut keys = BTreeMap::new();
+    let mut pages = client().await.list_objects_v2().bucket(&config.bucket).prefix(format!("{}{}", config.prefix, PART_PREFIX)).into_paginator().send();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|e| format!("s3://{}/{}: {}", config.bucket, config.prefix, aws_sdk_s3::error::DisplayErrorContext(e)))?;
+        for key in page.contents().iter().filter_map(|object| object.key()) {
+            let index = key.strip_prefix(config.prefix.as_str()).and_then(|name| name.strip_prefix(PART_PREFIX))
+                .and_then(|name| name.strip_suffix(".json")).and_then(|index| index.parse::<usize>().ok());
+            if let Some(index) = index.filter(|index| *index < config.parts) { keys.insert(index, key.to_string()); }
+        }
+    }
+    Ok(keys)
+}
+
+async fn download(bucket: &str, keys: BTreeMap<usize, String>) -> Result<BTreeMap<usize, Value>, Error> {
+    let client = client().await;
+    let permits = Arc::new(Semaphore::new(DOWNLOAD_CONCURRENCY));
+    let mut downloads = JoinSet::new();
+    for (index, key) in keys {
+        let (client, bucket, permits) = (client.clone(), bucket.to_string(), permits.clone());
+        downloads.spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            let body = client.get_object().bucket(&bucket).key(&key).send().await
+                .map_err(|e| format!("s3://{}/{}: {}", bucket, key, aws_sdk_s3::error::DisplayErrorContext(e)))?
+                .body.collect().await?.into_bytes();
+            let part: Value = serde_json::from_slice(&body).map_err(|e| format!("s3://{}/{} is not a part: {}", bucket, key, e))?;
+            Ok::<_, Error>((index, part))
+        });
+    }
+    let mut parts = BTreeMap::new();
+    while let Some(download) = downloads.join_next().await {
+        let (index, part) = download??;
+        parts.insert(index, part);
+    }
+    Ok(parts)
+}
+
+fn fold(config: &ReduceConfig, parts: BTreeMap<usize, Value>) -> ReduceResponse {
+    let number = |value: &Value| value.as_u64().unwrap_or(0);
+    let slices: Vec<SliceSummary> = parts.iter().map(|(index, part)| {
+        let response = &part["response"];
+        SliceSummary {
+            index: *index,
+            offset: number(&response["offset"]),  // the flattened continuation state
+            completed: number(&response["completed"]),
+            skipped: number(&response["skipped"]),
+            failed: response["failures"].as_array().map_or(0, |failures| failures.len() as u64),
+            duration_ms: response["duration_ms"].as_f64().unwrap_or(0.0),
+            finished_at_ms: number(&part["finished_at_ms"]),
+            aborted_reason: response["aborted_reason"].as_str().map(str::to_string),
+        }
+    }).collect();
+    let digests: Option<Vec<&str>> = parts.values().map(|part| part["response"]["checksum"]["sha256"].as_str()).collect();
+    let checksum = digests.filter(|digests| !digests.is_empty()).map(|digests| {
+        let mut hasher = Sha256::new();
+        for digest in &digests {
+            hasher.update(digest.as_bytes());
+            hasher.update(b"\n");
+        }
+        PartsChecksum { sha256: format!("{:x}", hasher.finalize()), parts: digests.len() }
+    });
+
+    let durations = slices.iter().map(|slice| slice.duration_ms);
+    let completed = slices.iter().map(|slice| slice.completed).sum::<u64>();
+    let makespan_ms = config.dispatched_at_ms.zip(slices.iter().map(|slice| slice.finished_at_ms).max())
+        .map(|(dispatched, finished)| finished.saturating_sub(dispatched) as f64);
+    ReduceResponse {
+        mode: Mode::Reduce,
+        bucket: config.bucket.clone(),
+        prefix: config.prefix.clone(),
+        parts_expected: config.parts,
+        parts_found: slices.len(),
+        complete: slices.len() == config.parts,
+        missing: (0..config.parts).filter(|index| !parts.contains_key(index)).collect(),
+        processed: parts.values().map(|part| number(&part["response"]["processed"])).sum(),
+        completed,
+        skipped: slices.iter().map(|slice| slice.skipped).sum(),
+        failed: slices.iter().map(|slice| slice.failed).sum(),
+        slice_ms_min: durations.clone().reduce(f64::min).unwrap_or(0.0),
+        slice_ms_mean: durations.clone().sum::<f64>() / slices.len().max(1) as f64,
+        slice_ms_max: durations.reduce(f64::max).unwrap_or(0.0),
+        cpu_time_ms: parts.values().filter_map(|part| part["response"]["cpu_time_ms"].as_f64()).sum(),
+        makespan_ms,
+        items_per_sec: makespan_ms.filter(|ms| *ms > 0.0).map(|ms| completed as f64 / (ms / 1000.0)),
+        checksum,
+        polls: 0,
+        waited_ms: 0.0,
+        summary_uri: format!("s3://{}/{}{}", config.bucket, config.prefix, SUMMARY_KEY),
+        slices,
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
@@ -0,0 +1,170 @@
+use serde::Serialize;
+use std::alloc::{GlobalAlloc, Layout};
+use std::env;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("features \"jemalloc\" and \"mimalloc\" are mutually exclusive");
+
+// Underlying allocator, selected with the "jemalloc" / "mimalloc" features (default: system malloc)
+#[cfg(feature = "jemalloc")]
+use tikv_jemallocator::Jemalloc as Backend;
+#[cfg(feature = "jemalloc")]
+pub const ALLOCATOR_NAME: &str = "jemalloc";
+#[cfg(feature = "mimalloc")]
+use mimalloc::MiMalloc as Backend;
+#[cfg(feature = "mimalloc")]
+pub const ALLOCATOR_NAME: &str = "mimalloc";
+#[cfg(not(any(feature = "jemalloc", feature = "mimalloc")))]
+use std::alloc::System as Backend;
+#[cfg(not(any(feature = "jemalloc", feature = "mimalloc")))]
+pub const ALLOCATOR_NAME: &str = "system";
+
+// How often MemoryGuard samples the resident set, and the share of the memory limit it lets it reach
+const GUARD_INTERVAL: Duration = Duration::from_millis(20);
+const DEFAULT_GUARD_PERCENT: u64 = 90;
+
+// Bytes currently allocated on the heap, and the high-water mark since the last reset_heap_peak()
+static HEAP_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static HEAP_PEAK: AtomicUsize = AtomicUsize::new(0);
+
+// Allocator wrapper that keeps live and peak heap byte counts (two relaxed atomics per call)
+struct CountingAllocator<A>(A);
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc(layout);
+        if !ptr.is_null() { record_alloc(layout.size()); }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc_zeroed(layout);
+        if !ptr.is_null() { record_alloc(layout.size()); }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.dealloc(ptr, layout);
+        HEAP_ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.0.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            HEAP_ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator<Backend> = CountingAllocator(Backend);
+
+fn record_alloc(size: usize) {
+    let allocated = HEAP_ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    HEAP_PEAK.fetch_max(allocated, Ordering::Relaxed);
+}
+
+// Start a new heap high-water mark (at the beginning of each invocation)
+pub fn reset_heap_peak() {
+    HEAP_PEAK.store(HEAP_ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+// Process memory at the end of an invocation
+// RSS figures come from /proc/self/status (0 where unavailable); the peak RSS covers the
+// container's whole lifetime, while heap_peak_kb covers only the current invocation
+#[derive(Clone, Copy, Serialize)]
+pub struct MemoryStats {
+    pub rss_kb: u64,             // VmRSS: resident set size now
+    pub peak_rss_kb: u64,        // VmHWM: highest resident set size since the process started
+    pub heap_allocated_kb: u64,  // live heap bytes according to the counting allocator
+    pub heap_peak_kb: u64,       // highest live heap bytes during this invocation
+}
+
+impl MemoryStats {
+    pub fn capture() -> Self {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+        let field = |name: &str| status.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        MemoryStats {
+            rss_kb: field("VmRSS"),
+            peak_rss_kb: field("VmHWM"),
+            heap_allocated_kb: (HEAP_ALLOCATED.load(Ordering::Relaxed) / 1024) as u64,
+            heap_peak_kb: (HEAP_PEAK.load(Ordering::Relaxed) / 1024) as u64,
+        }
+    }
+}
+
+// The RSS at which MemoryGuard stops a job: MEMORY_GUARD_PERCENT (default 90, 0 turns the guard
+// off) of the function's memory, AWS_LAMBDA_FUNCTION_MEMORY_SIZE or MEMORY_LIMIT_MB outside
+// Lambda; None without a limit
+pub fn guard_threshold_kb() -> Option<u64> {
+    let limit_mb = env::var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE").or_else(|_| env::var("MEMORY_LIMIT_MB")).ok()?.parse::<u64>().ok()?;
+    let percent = match crate::config::var("MEMORY_GUARD_PERCENT").map(|percent| percent.parse::<u64>()) {
+        Some(Ok(percent)) if percent <= 100 => percent,
+        Some(_) => {
+            tracing::warn!(default = DEFAULT_GUARD_PERCENT, "MEMORY_GUARD_PERCENT must be 0-100, using the default");
+            DEFAULT_GUARD_PERCENT
+        }
+        None => DEFAULT_GUARD_PERCENT,
+    };
+    (percent > 0).then(|| limit_mb * 1024 * percent / 100)
+}
+
+// Samples the resident set every GUARD_INTERVAL while a batch runs and raises a shared flag once it
+// reaches the threshold, so the workers start no more items and the invocation returns what it has
+// instead of growing until Lambda kills the sandbox (a kill loses every result and the warm
+// container). Items already running still finish, which is why the threshold leaves headroom.
+// Like the progress heartbeat it runs on a plain thread; stops when dropped
+pub struct MemoryGuard {
+    tripped: Arc<AtomicBool>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MemoryGuard {
+    pub fn start(threshold_kb: u64) -> Self {
+        let tripped = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let flag = tripped.clone();
+        let span = tracing::Span::current();
+        let thread = std::thread::spawn(move || {
+            let _span = span.enter();
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(GUARD_INTERVAL) {
+                let rss_kb = MemoryStats::capture().rss_kb;
+                if rss_kb >= threshold_kb {
+                    flag.store(true, Ordering::Relaxed);
+                    tracing::warn!(rss_kb, threshold_kb, "memory pressure, no more items are started");
+                    break;
+                }
+            }
+        });
+        MemoryGuard { tripped, stop: Some(stop), thread: Some(thread) }
+    }
+
+    // Shared with the workers, which check it before every item
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.tripped.clone()
+    }
+
+    pub fn tripped(&self) -> bool {
+        self.tripped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for MemoryGuard {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
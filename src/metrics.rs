@@ -0,0 +1,71 @@
+use serde_json::{json, Map, Value};
+use std::env;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_NAMESPACE: &str = "RustMultithreadLambda";
+
+// Count metrics also summed over the execution environment's life, for emit_totals at shutdown
+const TOTALED: [&str; 3] = ["ProcessedItems", "FailedItems", "ItemRetries"];
+static TOTALS: Mutex<[f64; 3]> = Mutex::new([0.0; 3]);
+
+/// A single CloudWatch metric value with its unit
+pub struct Metric {
+    name: &'static str, unit: &'static str, value: f64,
+}
+
+impl Metric {
+    pub fn count(name: &'static str, value: f64) -> Self { Metric { name, unit: "Count", value } }
+    pub fn milliseconds(name: &'static str, value: f64) -> Self { Metric { name, unit: "Milliseconds", value } }
+    pub fn none(name: &'static str, value: f64) -> Self { Metric { name, unit: "None", value } }
+}
+
+// Build an Embedded Metric Format document: CloudWatch extracts the metrics from the log line,
+// so no PutMetricData call (or extra latency) is needed
+fn emf_document(namespace: &str, dimensions: &[(&str, &str)], metrics: &[Metric]) -> Value {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let dimension_names: Vec<&str> = dimensions.iter().map(|(name, _)| *name).collect();
+    let definitions: Vec<Value> = metrics.iter().map(|m| json!({ "Name": m.name, "Unit": m.unit })).collect();
+
+    let mut document = Map::new();
+    document.insert("_aws".to_string(), json!({
+        "Timestamp": timestamp,
+        "CloudWatchMetrics": [{ "Namespace": namespace, "Dimensions": [dimension_names], "Metrics": definitions }],
+    }));
+    for (name, value) in dimensions {
+        document.insert(name.to_string(), json!(value));
+    }
+    for metric in metrics {
+        document.insert(metric.name.to_string(), json!(metric.value));
+    }
+    Value::Object(document)
+}
+
+/// Write one EMF line to stdout (namespace from METRICS_NAMESPACE)
+/// Printed directly rather than through tracing: CloudWatch only parses EMF from a bare JSON line
+pub fn emit(dimensions: &[(&str, &str)], metrics: &[Metric]) {
+    let mut totals = TOTALS.lock().unwrap();
+    for metric in metrics {
+        if let Some(i) = TOTALED.iter().position(|name| *name == metric.name) { totals[i] += metric.value; }
+    }
+    drop(totals);
+    write(dimensions, metrics);
+}
+
+fn write(dimensions: &[(&str, &str)], metrics: &[Metric]) {
+    let namespace = env::var("METRICS_NAMESPACE").unwrap_or_else(|_| DEFAULT_NAMESPACE.to_string());
+    println!("{}", emf_document(&namespace, dimensions, metrics));
+}
+
+/// One line of container-lifetime totals under the dimension Scope=container, which per-run
+/// metrics never carry: invocations handled, the TOTALED counts and how long the process lived
+pub fn emit_totals(invocations: u64, lifetime_ms: f64) {
+    let totals = *TOTALS.lock().unwrap();
+    write(&[("Scope", "container")], &[
+        Metric::count("Invocations", invocations as f64),
+        Metric::count(TOTALED[0], totals[0]),
+        Metric::count(TOTALED[1], totals[1]),
+        Metric::count(TOTALED[2], totals[2]),
+        Metric::milliseconds("LifetimeMs", lifetime_ms),
+    ]);
+}
@@ -0,0 +1,80 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+use std::time::Instant;
+use crate::error::{invalid, HandlerError};
+
+// MessagePack invocations: `{"msgpack": "<base64>"}` always, and with CONTENT_FORMAT=msgpack also
+// a bare JSON string payload (Lambda payloads are JSON, so the base64 text is the envelope). The
+// body decodes to the same JSON value a plain invocation carries, is routed as one (a request, a
+// batch envelope or an event), and the response goes back MessagePack-encoded in the same
+// envelope. Errors stay JSON
+
+/// How the request and the response were encoded, next to what JSON would have cost for the same
+/// values: sizes in bytes, and decode/encode times in ms. The response figures are measured on the
+/// response without this field, which is then added and the response encoded again
+#[derive(Serialize)]
+pub struct FormatStats {
+    pub format: &'static str,
+    pub request_bytes: usize, pub request_json_bytes: usize,
+    pub request_decode_ms: f64, pub request_json_decode_ms: f64,
+    pub response_bytes: usize, pub response_json_bytes: usize,
+    pub response_encode_ms: f64, pub response_json_encode_ms: f64,
+}
+
+/// A decoded request on its way through the handler
+pub struct Decoded {
+    wrapped: bool,  // `{"msgpack": ...}` rather than a bare string
+    stats: FormatStats,
+}
+
+fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var("CONTENT_FORMAT").as_deref() == Ok("msgpack"))
+}
+
+/// The base64 body of a MessagePack invocation, if the payload is one; a wrapper is only the
+/// `msgpack` field on its own, so a request field of that name could never be mistaken for it
+pub fn body(payload: &Value) -> Option<(&str, bool)> {
+    match payload {
+        Value::String(encoded) if enabled() => Some((encoded, false)),
+        Value::Object(map) if map.len() == 1 => map.get("msgpack").and_then(Value::as_str).map(|encoded| (encoded, true)),
+        _ => None,
+    }
+}
+
+pub fn decode(encoded: &str, wrapped: bool) -> Result<(Value, Decoded), HandlerError> {
+    let bytes = STANDARD.decode(encoded).map_err(|e| invalid("msgpack", format!("the payload is not base64: {}", e)))?;
+    let start = Instant::now();
+    let value: Value = rmp_serde::from_slice(&bytes).map_err(|e| invalid("msgpack", format!("invalid MessagePack payload: {}", e)))?;
+    let request_decode_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let json = serde_json::to_vec(&value).map_err(|e| HandlerError::Io(e.into()))?;
+    let start = Instant::now();
+    let _: Value = serde_json::from_slice(&json).map_err(|e| HandlerError::Io(e.into()))?;
+    let stats = FormatStats {
+        format: "msgpack",
+        request_bytes: bytes.len(), request_json_bytes: json.len(),
+        request_decode_ms, request_json_decode_ms: start.elapsed().as_secs_f64() * 1000.0,
+        response_bytes: 0, response_json_bytes: 0, response_encode_ms: 0.0, response_json_encode_ms: 0.0,
+    };
+    Ok((value, Decoded { wrapped, stats }))
+}
+
+/// Field names are kept (to_vec_named), so the response decodes to the same map as the JSON one
+pub fn encode(mut response: Value, decoded: Decoded) -> Result<Value, HandlerError> {
+    let io = |e: rmp_serde::encode::Error| HandlerError::Io(e.into());
+    let Decoded { wrapped, mut stats } = decoded;
+    let start = Instant::now();
+    stats.response_bytes = rmp_serde::to_vec_named(&response).map_err(io)?.len();
+    stats.response_encode_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let start = Instant::now();
+    stats.response_json_bytes = serde_json::to_vec(&response).map_err(|e| HandlerError::Io(e.into()))?.len();
+    stats.response_json_encode_ms = start.elapsed().as_secs_f64() * 1000.0;
+    if let Some(map) = response.as_object_mut() {
+        map.insert("content_format".to_string(), serde_json::to_value(stats).map_err(|e| HandlerError::Io(e.into()))?);
+    }
+    let encoded = Value::String(STANDARD.encode(rmp_serde::to_vec_named(&response).map_err(io)?));
+    Ok(if wrapped { serde_json::json!({ "msgpack": encoded }) } else { encoded })
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use crate::registry::{register_workload, Objects, WorkloadExecutor};
+use crate::workload::{Workload, WorkloadParams};
+
+// Salts each nested_hash item is hashed with, and the chained SHA-256 rounds per salt
+pub const SALTS_RANGE: std::ops::RangeInclusive<u32> = 1..=256;
+pub const DEFAULT_SALTS: u32 = 16;
+const ROUNDS_PER_SALT: usize = 10_000;
+
+// Where the salts of the current request ran: every salt, the ones run by a thread other than the
+// one that started their item, and a bit per pool thread index (mod 64) that ran any
+static TASKS: AtomicUsize = AtomicUsize::new(0);
+static STOLEN: AtomicUsize = AtomicUsize::new(0);
+static THREADS: AtomicU64 = AtomicU64::new(0);
+
+// How the salts of a nested_hash run were spread; stolen_share near 0 means the items alone kept
+// every worker busy, near 1 - 1/threads that idle workers picked up the inner tasks
+#[derive(Clone, Serialize)]
+pub struct NestingSummary {
+    pub salts: u32, pub tasks: usize, pub stolen: usize, pub stolen_share: f64, pub threads: usize,
+}
+
+// Parallelism inside every item: the item is hashed with `salts` different salts, split in halves
+// with rayon::join down to single salts, and the output is the SHA-256 of the per-salt digests in
+// salt order. join never adds threads: each half is a task on the pool the item already runs on,
+// which an idle worker may steal and the worker that pushed it otherwise runs itself, so nesting
+// only helps when there are fewer items than workers. Outside a Rayon worker (sequential,
+// std_threads, channels and the tokio modes) the salts run in a plain loop, as join would otherwise
+// hand them to the global pool. The output is the same either way
+pub struct NestedHash {
+    pub salts: u32,
+}
+
+impl Workload for NestedHash {
+    fn name(&self) -> &'static str { "nested_hash" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_bytes(item.as_bytes())
+    }
+
+    fn execute_bytes(&self, item: &[u8]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let digests = hash_salts(item, 0..self.salts, rayon::current_thread_index());
+        let mut hasher = Sha256::new();
+        for digest in &digests { hasher.update(digest); }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+struct NestedHashExecutor;
+
+impl WorkloadExecutor for NestedHashExecutor {
+    fn name(&self) -> &'static str { "nested_hash" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(NestedHash { salts: params.salts }))
+    }
+}
+
+register_workload!(NestedHashExecutor);
+
+fn hash_salts(item: &[u8], salts: Range<u32>, origin: Option<usize>) -> Vec<[u8; 32]> {
+    if salts.len() == 1 || origin.is_none() {
+        return salts.map(|salt| hash_salt(item, salt, origin)).collect();
+    }
+    let mid = salts.start + salts.len() as u32 / 2;
+    let (mut left, right) = rayon::join(|| hash_salts(item, salts.start..mid, origin), || hash_salts(item, mid..salts.end, origin));
+    left.extend(right);
+    left
+}
+
+fn hash_salt(item: &[u8], salt: u32, origin: Option<usize>) -> [u8; 32] {
+    let thread = rayon::current_thread_index();
+    TASKS.fetch_add(1, Ordering::Relaxed);
+    if thread != origin { STOLEN.fetch_add(1, Ordering::Relaxed); }
+    if let Some(index) = thread { THREADS.fetch_or(1 << (index % 64), Ordering::Relaxed); }
+    let mut digest = Sha256::new().chain_update(item).chain_update(salt.to_le_bytes()).finalize();
+    for _ in 1..ROUNDS_PER_SALT {
+        digest = Sha256::digest(digest);
+    }
+    digest.into()
+}
+
+// Clear the counters before a run's processing phase
+pub fn reset() {
+    TASKS.store(0, Ordering::Relaxed);
+    STOLEN.store(0, Ordering::Relaxed);
+    THREADS.store(0, Ordering::Relaxed);
+}
+
+// The counters since reset(); one invocation runs at a time, so they are the current request's
+pub fn summary(salts: u32) -> NestingSummary {
+    let (tasks, stolen) = (TASKS.load(Ordering::Relaxed), STOLEN.load(Ordering::Relaxed));
+    NestingSummary {
+        salts, tasks, stolen,
+        stolen_share: if tasks > 0 { stolen as f64 / tasks as f64 } else { 0.0 },
+        threads: THREADS.load(Ordering::Relaxed).count_ones() as usize,
+    }
+}
@@ -0,0 +1,45 @@
+use aws_sdk_sns::error::{DisplayErrorContext, ProvideErrorMetadata};
+use aws_sdk_sns::types::MessageAttributeValue;
+use aws_sdk_sns::Client;
+use serde_json::Value;
+use tokio::sync::OnceCell;
+use crate::handler::NotifySummary;
+
+// SNS client is created once per container and reused across invocations
+static SNS_CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+pub async fn client() -> &'static Client {
+    SNS_CLIENT.get_or_init(|| async {
+        Client::new(&aws_config::load_from_env().await)
+    }).await
+}
+
+// Publish a completion message as JSON, with its status and workload also as message attributes
+// so subscriptions can filter on them (e.g. only "partial" jobs)
+pub async fn publish(topic_arn: String, message: Value) -> NotifySummary {
+    let attribute = |value: &Value| MessageAttributeValue::builder().data_type("String").string_value(value.as_str().unwrap_or_default()).build().map_err(|e| e.to_string());
+    let subject = format!("{} job {}", message["workload"].as_str().unwrap_or_default(), message["status"].as_str().unwrap_or_default());
+    let result = async {
+        client().await.publish()
+            .topic_arn(&topic_arn)
+            .subject(subject)
+            .message(message.to_string())
+            .message_attributes("status", attribute(&message["status"])?)
+            .message_attributes("workload", attribute(&message["workload"])?)
+            .send().await
+            .map_err(|e| match (e.code(), e.message()) {
+                (Some(code), Some(message)) => format!("{}: {}", code, message),
+                _ => DisplayErrorContext(e).to_string(),
+            })
+    }.await;
+    match result {
+        Ok(output) => {
+            tracing::info!(topic_arn, message_id = output.message_id(), "completion notification published");
+            NotifySummary { topic_arn, message_id: output.message_id, error: None }
+        }
+        Err(error) => {
+            tracing::warn!(topic_arn, error, "completion notification failed");
+            NotifySummary { topic_arn, message_id: None, error: Some(error) }
+        }
+    }
+}
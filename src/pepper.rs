@@ -0,0 +1,96 @@
+use serde::Serialize;
+use std::env;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use crate::workload::Workload;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Workloads a request may pepper: the password hashes (and sha256, the cheap stand-in for one)
+pub const PEPPER_WORKLOADS: &[&str] = &["bcrypt", "argon2", "pbkdf2", "scrypt", "sha256"];
+
+static PEPPER: OnceLock<Pepper> = OnceLock::new();
+
+// The secret from PEPPER_SECRET_ID, read once at cold start and never written back out: Debug
+// redacts it, and responses and logs only name the secret and its version
+pub struct Pepper {
+    secret: Arc<str>,
+    pub summary: PepperSummary,
+}
+
+impl std::fmt::Debug for Pepper {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.debug_struct("Pepper").field("secret", &"<redacted>").field("summary", &self.summary).finish()
+    }
+}
+
+// Which pepper a response's hashes were made with
+#[derive(Clone, Debug, Serialize)]
+pub struct PepperSummary {
+    pub secret_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+}
+
+pub fn get() -> Option<&'static Pepper> {
+    PEPPER.get()
+}
+
+// Cold start: fetch the secret when PEPPER_SECRET_ID is set; a secret that cannot be read fails the init
+pub async fn load() -> Result<(), Error> {
+    let Ok(secret_id) = env::var("PEPPER_SECRET_ID") else { return Ok(()) };
+    let start = Instant::now();
+    let (secret, version_id) = fetch(&secret_id).await?;
+    if secret.is_empty() { return Err(format!("secret {} is empty", secret_id).into()); }
+    tracing::info!(secret_id, version_id, fetch_ms = start.elapsed().as_secs_f64() * 1000.0, "pepper loaded");
+    let _ = PEPPER.set(Pepper { secret: secret.into(), summary: PepperSummary { secret_id, version_id } });
+    Ok(())
+}
+
+#[cfg(feature = "secrets")]
+async fn fetch(secret_id: &str) -> Result<(String, Option<String>), Error> {
+    let config = aws_config::load_from_env().await;
+    let output = aws_sdk_secretsmanager::Client::new(&config).get_secret_value().secret_id(secret_id).send().await
+        .map_err(|e| format!("secret {}: {}", secret_id, aws_sdk_secretsmanager::error::DisplayErrorContext(e)))?;
+    let secret = output.secret_string().ok_or_else(|| format!("secret {} has no SecretString", secret_id))?;
+    Ok((secret.to_string(), output.version_id().map(str::to_string)))
+}
+
+#[cfg(not(feature = "secrets"))]
+async fn fetch(_secret_id: &str) -> Result<(String, Option<String>), Error> {
+    Err("PEPPER_SECRET_ID requires building with the \"secrets\" feature".into())
+}
+
+// Runs `inner` on every item with the pepper appended. Every Rayon worker holds the same Arc<str>:
+// the secret is immutable after init, so sharing it needs no lock and no per-thread copy
+pub struct Peppered {
+    pub pepper: Arc<str>,
+    pub inner: Arc<dyn Workload>,
+}
+
+impl Peppered {
+    pub fn new(pepper: &Pepper, inner: Arc<dyn Workload>) -> Self {
+        Peppered { pepper: pepper.secret.clone(), inner }
+    }
+
+    fn peppered(&self, item: &str) -> String {
+        let mut peppered = String::with_capacity(item.len() + self.pepper.len());
+        peppered.push_str(item);
+        peppered.push_str(&self.pepper);
+        peppered
+    }
+}
+
+impl Workload for Peppered {
+    fn name(&self) -> &'static str { self.inner.name() }
+    fn working_set_bytes(&self) -> usize { self.inner.working_set_bytes() }
+    fn payload_len(&self, item: &str) -> usize { self.inner.payload_len(item) + self.pepper.len() }
+
+    fn execute_item(&self, item: &str) -> Result<String, Error> {
+        self.inner.execute_item(&self.peppered(item))
+    }
+
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Error> {
+        self.inner.verify(&self.peppered(item), output)
+    }
+}
@@ -0,0 +1,121 @@
+use aws_sdk_dynamodb::types::{AttributeValue, PutRequest, WriteRequest};
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, OnceCell};
+use tokio::task::{JoinHandle, JoinSet};
+use crate::handler::{ItemResult, ItemSink, PersistSummary};
+use crate::rate_limit::RateLimiter;
+
+// BatchWriteItem accepts at most 25 put requests per call
+const BATCH_SIZE: usize = 25;
+// Attempts per batch while DynamoDB keeps returning unprocessed items (throttling)
+const MAX_ATTEMPTS: u32 = 5;
+
+// DynamoDB client is created once per container and reused across invocations
+static DYNAMODB_CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub async fn client() -> &'static Client {
+    DYNAMODB_CLIENT.get_or_init(|| async {
+        Client::new(&aws_config::load_from_env().await)
+    }).await
+}
+
+// Writes item results to DynamoDB while the workers are still hashing: results arrive through
+// the sink as they complete, and every full batch is written by its own tokio task
+pub struct Persister {
+    table: String,
+    sink: ItemSink,
+    task: JoinHandle<Result<(usize, usize, usize), Error>>,
+}
+
+impl Persister {
+    // Items are keyed by `id` = "<request id>#<index>"; `forward` receives every item afterwards
+    // (the streaming response, when there is one), and every BatchWriteItem call waits for `limiter`
+    pub fn start(table: String, request_id: String, limiter: RateLimiter, forward: Option<ItemSink>) -> Self {
+        let (sink, mut items) = mpsc::unbounded_channel::<ItemResult>();
+        let task_table = table.clone();
+        let task = tokio::spawn(async move {
+            let client = client().await;
+            let mut writes = JoinSet::new();
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let mut batches = 0;
+            while let Some(item) = items.recv().await {
+                batch.push(write_request(&request_id, &item)?);
+                if let Some(forward) = &forward {
+                    let _ = forward.send(item);
+                }
+                if batch.len() == BATCH_SIZE {
+                    writes.spawn(write_batch(client, limiter.clone(), task_table.clone(), std::mem::take(&mut batch)));
+                    batches += 1;
+                }
+            }
+            if !batch.is_empty() {
+                writes.spawn(write_batch(client, limiter.clone(), task_table.clone(), batch));
+                batches += 1;
+            }
+
+            let (mut written, mut unprocessed) = (0, 0);
+            while let Some(result) = writes.join_next().await {
+                let (batch_written, batch_unprocessed) = result??;
+                written += batch_written;
+                unprocessed += batch_unprocessed;
+            }
+            Ok((written, batches, unprocessed))
+        });
+        Persister { table, sink, task }
+    }
+
+    pub fn sink(&self) -> ItemSink {
+        self.sink.clone()
+    }
+
+    // Wait for the outstanding writes; call once processing is done and every sink clone is dropped
+    pub async fn finish(self) -> Result<PersistSummary, Error> {
+        drop(self.sink);
+        let start = Instant::now();
+        let (items_written, batches, unprocessed) = self.task.await??;
+        if unprocessed > 0 {
+            tracing::warn!(table = %self.table, unprocessed, "items left unprocessed after retries");
+        }
+        Ok(PersistSummary {
+            table: self.table,
+            items_written,
+            batches,
+            unprocessed,
+            wait_ms: start.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+fn write_request(request_id: &str, item: &ItemResult) -> Result<WriteRequest, Error> {
+    let attributes = HashMap::from([
+        ("id".to_string(), AttributeValue::S(format!("{}#{}", request_id, item.index))),
+        ("request_id".to_string(), AttributeValue::S(request_id.to_string())),
+        ("index".to_string(), AttributeValue::N(item.index.to_string())),
+        ("output".to_string(), AttributeValue::S(item.output.clone())),
+        ("duration_us".to_string(), AttributeValue::N(item.duration_us.to_string())),
+        ("thread_id".to_string(), AttributeValue::S(item.thread_id.clone())),
+    ]);
+    let put = PutRequest::builder().set_item(Some(attributes)).build()?;
+    Ok(WriteRequest::builder().put_request(put).build())
+}
+
+// Write one batch, resubmitting unprocessed items with exponential backoff
+// Returns (written, still unprocessed)
+async fn write_batch(client: &'static Client, limiter: RateLimiter, table: String, requests: Vec<WriteRequest>) -> Result<(usize, usize), Error> {
+    let total = requests.len();
+    let mut pending = requests;
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(50 << attempt)).await;
+        }
+        limiter.acquire().await;
+        let output = client.batch_write_item().request_items(&table, pending).send().await?;
+        pending = output.unprocessed_items.and_then(|mut unprocessed| unprocessed.remove(&table)).unwrap_or_default();
+        if pending.is_empty() { break; }
+    }
+    Ok((total - pending.len(), pending.len()))
+}
@@ -0,0 +1,78 @@
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use crate::handler::{ItemResult, ItemSink, PipelineSummary};
+use crate::workload::Workload;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Second pipeline stage: Rayon threads send each result through an unbounded channel (a plain
+// synchronous send) to an async task, which verifies it on tokio's blocking pool while hashing
+// continues; at most `concurrency` verifications run at once
+pub struct Verifier {
+    sink: ItemSink,
+    task: JoinHandle<Result<(usize, usize, Duration, Duration), Error>>,
+    hashing_done: Arc<OnceLock<Instant>>,
+    start: Instant,
+}
+
+impl Verifier {
+    // `items[0]` is the item with global index `first_index`; `forward` receives every result
+    pub fn start(items: Arc<[String]>, first_index: usize, workload: Arc<dyn Workload>, concurrency: usize, forward: Option<ItemSink>) -> Self {
+        let (sink, mut results) = mpsc::unbounded_channel::<ItemResult>();
+        let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+        let hashing_done = Arc::new(OnceLock::new());
+        let done = hashing_done.clone();
+        let task = tokio::spawn(async move {
+            let mut checks = JoinSet::new();
+            while let Some(result) = results.recv().await {
+                let permit = permits.clone().acquire_owned().await?;
+                let (items, workload, done) = (items.clone(), workload.clone(), done.clone());
+                let (index, output) = (result.index - first_index, result.output.clone());
+                if let Some(forward) = &forward {
+                    let _ = forward.send(result);
+                }
+                checks.spawn_blocking(move || {
+                    let _permit = permit;
+                    let start = Instant::now();
+                    let valid = workload.verify(&items[index], &output)?;
+                    let took = start.elapsed();
+                    // The part of this check that ran before hashing finished
+                    let overlapped = done.get().map_or(took, |done: &Instant| done.saturating_duration_since(start).min(took));
+                    Ok::<_, Error>((valid, took, overlapped))
+                });
+            }
+
+            let (mut verified, mut failed, mut busy, mut overlapped) = (0, 0, Duration::ZERO, Duration::ZERO);
+            while let Some(check) = checks.join_next().await {
+                let (valid, took, check_overlapped) = check??;
+                if valid { verified += 1 } else { failed += 1 }
+                busy += took;
+                overlapped += check_overlapped;
+            }
+            Ok((verified, failed, busy, overlapped))
+        });
+        Verifier { sink, task, hashing_done, start: Instant::now() }
+    }
+
+    pub fn sink(&self) -> ItemSink {
+        self.sink.clone()
+    }
+
+    // Wait for the remaining verifications; call once hashing is done and every sink clone is dropped
+    pub async fn finish(self) -> Result<PipelineSummary, Error> {
+        drop(self.sink);
+        let tail_start = Instant::now();
+        let _ = self.hashing_done.set(tail_start);
+        let (verified, failed, busy, overlapped) = self.task.await??;
+        Ok(PipelineSummary {
+            verified,
+            failed,
+            verify_busy_ms: busy.as_secs_f64() * 1000.0,
+            tail_ms: tail_start.elapsed().as_secs_f64() * 1000.0,
+            end_to_end_ms: self.start.elapsed().as_secs_f64() * 1000.0,
+            overlap: if busy.is_zero() { 0.0 } else { overlapped.as_secs_f64() / busy.as_secs_f64() },
+        })
+    }
+}
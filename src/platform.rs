@@ -0,0 +1,52 @@
+use serde::Serialize;
+use std::fs;
+use std::sync::OnceLock;
+
+// The host cannot change during the execution environment's lifetime, so /proc/cpuinfo is read once
+static PLATFORM: OnceLock<Platform> = OnceLock::new();
+
+// The host's processor, so benchmark results can be attributed to a CPU generation
+// cpu_model is "model name" on x86_64; arm64 kernels only report the implementer and part
+// numbers, which are mapped to the Neoverse core (and the Graviton generation built on it)
+#[derive(Serialize)]
+pub struct Platform {
+    pub arch: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_model: Option<String>,
+    pub cores: usize,  // processor entries in /proc/cpuinfo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bogomips: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_mhz: Option<f64>,  // x86_64 only, the current clock of the first processor
+}
+
+pub fn platform() -> &'static Platform {
+    PLATFORM.get_or_init(|| parse(&fs::read_to_string("/proc/cpuinfo").unwrap_or_default()))
+}
+
+// "key\t: value" lines, one block per processor; the first block describes the model
+fn parse(cpuinfo: &str) -> Platform {
+    let field = |name: &str| cpuinfo.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim());
+    let cpu_model = field("model name").map(str::to_string)
+        .or_else(|| Some(arm_core(field("CPU implementer")?, field("CPU part")?)));
+    Platform {
+        arch: std::env::consts::ARCH,
+        cpu_model,
+        cores: cpuinfo.lines().filter(|line| line.starts_with("processor")).count(),
+        bogomips: field("bogomips").and_then(|value| value.parse().ok()),
+        cpu_mhz: field("cpu MHz").and_then(|value| value.parse().ok()),
+    }
+}
+
+// MIDR implementer and part numbers of the cores in Lambda's arm64 fleet (ARM Ltd, 0x41)
+fn arm_core(implementer: &str, part: &str) -> String {
+    match (implementer, part) {
+        ("0x41", "0xd0c") => "Neoverse-N1 (Graviton2)".to_string(),
+        ("0x41", "0xd40") => "Neoverse-V1 (Graviton3)".to_string(),
+        ("0x41", "0xd4f") => "Neoverse-V2 (Graviton4)".to_string(),
+        _ => format!("implementer {} part {}", implementer, part),
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+use std::any::Any;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Stack of every pool thread: Rust's default 2 MiB unless POOL_STACK_KIB sets it, within these bounds
+const DEFAULT_STACK_KIB: usize = 2048;
+const STACK_KIB_RANGE: std::ops::RangeInclusive<usize> = 64..=65_536;
+const DEFAULT_THREAD_PREFIX: &str = "rayon-worker";
+
+// Across every pool of the process: threads started and exited, and panics caught in items or pool tasks
+static STARTED: AtomicUsize = AtomicUsize::new(0);
+static EXITED: AtomicUsize = AtomicUsize::new(0);
+static PANICS: AtomicUsize = AtomicUsize::new(0);
+
+// How the function builds its Rayon pools: the global one at cold start, and the ones for a
+// request's `workers` and each sweep point, which take the same settings with their own size
+#[derive(Clone, Debug, Serialize)]
+pub struct PoolConfig {
+    pub threads: usize,
+    pub stack_kib: usize,            // POOL_STACK_KIB
+    pub thread_name_prefix: String,  // POOL_THREAD_PREFIX: threads are named "<prefix>-<index>"
+    pub log_threads: bool,           // POOL_LOG_THREADS: log every thread start and exit
+}
+
+// The active configuration of the global pool and the process-wide counters, for mode "describe"
+#[derive(Serialize)]
+pub struct PoolReport {
+    #[serde(flatten)]
+    pub config: PoolConfig,
+    pub threads_started: usize,
+    pub threads_exited: usize,
+    pub panics: usize,
+}
+
+impl PoolConfig {
+    // A value that does not parse, or a stack outside STACK_KIB_RANGE, falls back to the default
+    pub fn from_env(threads: usize) -> Self {
+        let stack_kib = match env::var("POOL_STACK_KIB").map(|kib| kib.parse::<usize>()) {
+            Ok(Ok(kib)) if STACK_KIB_RANGE.contains(&kib) => kib,
+            Ok(_) => {
+                tracing::warn!(default = DEFAULT_STACK_KIB, "POOL_STACK_KIB must be {}-{}, using the default", STACK_KIB_RANGE.start(), STACK_KIB_RANGE.end());
+                DEFAULT_STACK_KIB
+            }
+            Err(_) => DEFAULT_STACK_KIB,
+        };
+        PoolConfig {
+            threads,
+            stack_kib,
+            thread_name_prefix: env::var("POOL_THREAD_PREFIX").ok().filter(|prefix| !prefix.is_empty()).unwrap_or_else(|| DEFAULT_THREAD_PREFIX.to_string()),
+            log_threads: env::var("POOL_LOG_THREADS").is_ok_and(|log| log == "true" || log == "1"),
+        }
+    }
+
+    // The same settings for a pool of another size
+    pub fn with_threads(&self, threads: usize) -> Self {
+        PoolConfig { threads, ..self.clone() }
+    }
+
+    // A builder with every setting applied. The panic handler sees panics in tasks nobody waits
+    // for (rayon::spawn); a panic inside an item never gets that far, as Job catches it and records
+    // it as a failure of the item
+    pub fn builder(&self) -> rayon::ThreadPoolBuilder {
+        let (prefix, log) = (self.thread_name_prefix.clone(), self.log_threads);
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .stack_size(self.stack_kib * 1024)
+            .thread_name(move |index| format!("{}-{}", prefix, index))
+            .start_handler(move |index| {
+                STARTED.fetch_add(1, Ordering::Relaxed);
+                if log { tracing::info!(thread = index, "pool thread started"); }
+            })
+            .exit_handler(move |index| {
+                EXITED.fetch_add(1, Ordering::Relaxed);
+                if log { tracing::info!(thread = index, "pool thread exited"); }
+            })
+            .panic_handler(|payload| {
+                PANICS.fetch_add(1, Ordering::Relaxed);
+                tracing::error!(error = panic_message(payload.as_ref()), "task panicked on a pool thread");
+            })
+    }
+}
+
+// Count a panic caught inside an item, which Job reported as a failure of the item
+pub fn record_panic() {
+    PANICS.fetch_add(1, Ordering::Relaxed);
+}
+
+// The message of a panic payload: panic!("...") carries a &str or a String
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic without a message".to_string())
+}
+
+pub fn report(config: PoolConfig) -> PoolReport {
+    PoolReport {
+        config,
+        threads_started: STARTED.load(Ordering::Relaxed),
+        threads_exited: EXITED.load(Ordering::Relaxed),
+        panics: PANICS.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,90 @@
+use std::env;
+use std::sync::OnceLock;
+use std::time::Instant;
+use crate::generator::ItemGenerator;
+use crate::handler::{self, get_worker_count, init_thread_pool};
+use crate::lifecycle::{self, ConfigSummary, InitReport};
+use crate::pool::PoolConfig;
+use crate::validation::max_items;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Items of the default generator built during init, for every request asking for a prefix of them
+static PREGENERATED: OnceLock<Vec<String>> = OnceLock::new();
+
+const PROVISIONED: &str = "provisioned-concurrency";
+
+/// Everything the cold start does before the runtime loop, in order: the settings file from
+/// CONFIG_S3_URI and the SSM parameters (either may set WORKER_COUNT, so they come before the pool), the Rayon pool and its
+/// warm-up, then the default items. PREGENERATE_ITEMS sets how many; the default is MAX_ITEMS with
+/// provisioned concurrency, whose init runs ahead of any request and is not billed as latency, and
+/// none on demand, where init time is added to the first response
+pub async fn initialize() -> Result<(), Error> {
+    let initialization_type = env::var("AWS_LAMBDA_INITIALIZATION_TYPE").unwrap_or_else(|_| "on-demand".to_string());
+    let config = match env::var("CONFIG_S3_URI") {
+        Ok(uri) => Some(fetch_config(&uri).await?),
+        Err(_) => None,
+    };
+    let ssm = crate::config::load().await?;
+    crate::pepper::load().await?;
+
+    init_thread_pool(PoolConfig::from_env(get_worker_count()));
+    lifecycle::record_warmup(handler::warmup());
+
+    let count = env::var("PREGENERATE_ITEMS").ok()
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(if initialization_type == PROVISIONED { max_items() } else { 0 });
+    let start = Instant::now();
+    if count > 0 {
+        let generator = ItemGenerator::default();
+        let _ = PREGENERATED.set((0..count).map(|i| generator.item(i)).collect());
+    }
+    let pregeneration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    tracing::info!(initialization_type, pregenerated_items = count, pregeneration_ms, "init complete");
+    lifecycle::record_init(InitReport { initialization_type, config, ssm, pregenerated_items: count, pregeneration_ms });
+    Ok(())
+}
+
+/// `count` generated items from `offset`, copied from the pregenerated ones when they cover the range
+pub fn generate(generator: &ItemGenerator, offset: usize, count: usize) -> Vec<String> {
+    match PREGENERATED.get() {
+        Some(items) if offset + count <= items.len() && *generator == ItemGenerator::default() => items[offset..offset + count].to_vec(),
+        _ => (offset..offset + count).map(|i| generator.item(i)).collect(),
+    }
+}
+
+// A JSON object of environment variables ({"WORKER_COUNT": 4, "MAX_ITEMS": "5000"}), so one
+// file can tune a fleet of functions; a variable the function configuration already sets is kept
+#[cfg(feature = "s3")]
+async fn fetch_config(uri: &str) -> Result<ConfigSummary, Error> {
+    let start = Instant::now();
+    let (bucket, key) = uri.strip_prefix("s3://").and_then(|path| path.split_once('/'))
+        .ok_or_else(|| format!("CONFIG_S3_URI '{}' is not s3://bucket/key", uri))?;
+    let body = crate::s3_handler::client().await.get_object().bucket(bucket).key(key).send().await
+        .map_err(|e| format!("{}: {}", uri, aws_sdk_s3::error::DisplayErrorContext(e)))?
+        .body.collect().await?.into_bytes();
+    let settings: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&body)
+        .map_err(|e| format!("{} is not a JSON object of settings: {}", uri, e))?;
+    let (mut applied, mut ignored) = (Vec::new(), Vec::new());
+    for (name, value) in settings {
+        let value = match value {
+            serde_json::Value::String(value) => value,
+            serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.to_string(),
+            _ => return Err(format!("{}: setting {} must be a string, number or boolean", uri, name).into()),
+        };
+        if env::var_os(&name).is_some() {
+            ignored.push(name);
+        } else {
+            // Still init: nothing else reads the environment while it changes
+            env::set_var(&name, value);
+            applied.push(name);
+        }
+    }
+    tracing::info!(uri, applied = ?applied, ignored = ?ignored, "config applied");
+    Ok(ConfigSummary { uri: uri.to_string(), applied, ignored, fetch_ms: start.elapsed().as_secs_f64() * 1000.0 })
+}
+
+#[cfg(not(feature = "s3"))]
+async fn fetch_config(_uri: &str) -> Result<ConfigSummary, Error> {
+    Err("CONFIG_S3_URI requires building with the \"s3\" feature".into())
+}
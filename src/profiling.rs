@@ -0,0 +1,69 @@
+use pprof::protos::Message;
+use crate::handler::{ProfileFormat, ProfileSummary, S3OutputConfig};
+
+// Samples per second of CPU time, per thread; pprof's usual 99 Hz avoids lockstep with 100 Hz timers
+const FREQUENCY: i32 = 99;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Samples the stacks of every thread in the process (SIGPROF on CPU time) until finished, so the
+// Rayon workers are profiled along with the handler; runtime and libc frames are left out
+pub struct Profiler {
+    guard: pprof::ProfilerGuard<'static>,
+}
+
+impl Profiler {
+    pub fn start() -> Result<Self, Error> {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()?;
+        Ok(Profiler { guard })
+    }
+
+    // Stop sampling and write the report to /tmp/profile-<request id>.svg (or .pb), then upload
+    // it to `target` when set. /tmp lasts as long as the execution environment, so a later
+    // invocation can still read the file; S3 is the way to get it out of Lambda
+    pub async fn finish(self, format: ProfileFormat, request_id: &str, target: Option<S3OutputConfig>) -> Result<ProfileSummary, Error> {
+        let report = self.guard.report().build()?;
+        drop(self.guard);
+        let samples = report.data.values().sum::<isize>() as usize;
+        let mut bytes = Vec::new();
+        let extension = match format {
+            ProfileFormat::Flamegraph => { report.flamegraph(&mut bytes)?; "svg" }
+            ProfileFormat::Protobuf => { report.pprof()?.encode(&mut bytes)?; "pb" }
+        };
+        let id = if request_id.is_empty() { "local" } else { request_id };
+        let path = format!("/tmp/profile-{}.{}", id, extension);
+        std::fs::write(&path, &bytes)?;
+        tracing::info!(path, samples, bytes = bytes.len(), "profile written");
+
+        let size = bytes.len();
+        let s3_uri = match target {
+            Some(target) => Some(upload(target, format, bytes).await?),
+            None => None,
+        };
+        Ok(ProfileSummary { format, path, bytes: size, samples, s3_uri })
+    }
+}
+
+#[cfg(feature = "s3")]
+async fn upload(target: S3OutputConfig, format: ProfileFormat, bytes: Vec<u8>) -> Result<String, Error> {
+    let content_type = match format {
+        ProfileFormat::Flamegraph => "image/svg+xml",
+        ProfileFormat::Protobuf => "application/octet-stream",
+    };
+    crate::s3_handler::client().await.put_object()
+        .bucket(&target.bucket).key(&target.key)
+        .content_type(content_type)
+        .body(aws_sdk_s3::primitives::ByteStream::from(bytes))
+        .send().await
+        .map_err(|e| format!("s3://{}/{}: {}", target.bucket, target.key, aws_sdk_s3::error::DisplayErrorContext(e)))?;
+    Ok(format!("s3://{}/{}", target.bucket, target.key))
+}
+
+// Validation rejects profile_s3 without the "s3" feature
+#[cfg(not(feature = "s3"))]
+async fn upload(_target: S3OutputConfig, _format: ProfileFormat, _bytes: Vec<u8>) -> Result<String, Error> {
+    Err("profile_s3 requires building with the \"s3\" feature".into())
+}
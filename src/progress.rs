@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const DEFAULT_PROGRESS_INTERVAL_SECS: u64 = 10;
+
+// Seconds between progress lines from PROGRESS_INTERVAL_SECS (default 10); None when set to 0
+pub fn progress_interval() -> Option<Duration> {
+    let secs = crate::config::var("PROGRESS_INTERVAL_SECS")
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_PROGRESS_INTERVAL_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+// Logs a "progress" line every interval while a batch runs, counting items through a shared
+// counter the workers bump as they finish. Runs on a plain thread, so it keeps reporting while
+// the processing blocks every runtime worker (1-vCPU configurations); stops when dropped
+pub struct Heartbeat {
+    done: Arc<AtomicUsize>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    pub fn start(total: usize, interval: Duration) -> Self {
+        let done = Arc::new(AtomicUsize::new(0));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let counter = done.clone();
+        let span = tracing::Span::current();  // keep the invocation's request_id on every line
+        let thread = std::thread::spawn(move || {
+            let _span = span.enter();
+            let start = Instant::now();
+            let (mut last_done, mut last_beat) = (0, start);
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let done = counter.load(Ordering::Relaxed);
+                let now = Instant::now();
+                // Throughput over the last interval; the ETA uses the average rate since the start
+                let items_per_sec = (done - last_done) as f64 / now.duration_since(last_beat).as_secs_f64();
+                let average = done as f64 / now.duration_since(start).as_secs_f64();
+                let eta_secs = (average > 0.0).then(|| (total.saturating_sub(done) as f64 / average).round());
+                tracing::info!(done, total, percent = (100.0 * done as f64 / total.max(1) as f64).round(), items_per_sec, eta_secs, "progress");
+                (last_done, last_beat) = (done, now);
+            }
+        });
+        Heartbeat { done, stop: Some(stop), thread: Some(thread) }
+    }
+
+    // Shared with the workers, which add one per finished item
+    pub fn counter(&self) -> Arc<AtomicUsize> {
+        self.done.clone()
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        drop(self.stop.take());  // wakes the thread with Disconnected
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
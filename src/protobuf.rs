@@ -0,0 +1,194 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use lambda_runtime::Context;
+use prost::Message;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use crate::error::{invalid, HandlerError};
+use crate::handler::{function_handler, serialize_response, Mode, ProcessRequest};
+
+// CONTENT_FORMAT=protobuf: a direct invocation whose payload is a JSON string is a base64-encoded
+// Request, and is answered with a JSON string holding a base64-encoded Response. Lambda payloads
+// are always JSON, so the string is the envelope; events (SQS, S3, ...) and JSON requests still
+// route as before. The messages are written out with prost's derive rather than generated from a
+// .proto file, so building needs no protoc; proto/process.proto describes the same messages for
+// clients in other languages
+
+/// The fields most requests set, with the tags of proto/process.proto; `extra` carries any other
+/// ProcessRequest field as a JSON object, so nothing is out of reach of a protobuf client
+#[derive(Clone, PartialEq, Message)]
+pub struct Request {
+    #[prost(string, tag = "1")]
+    pub mode: String,
+    #[prost(string, optional, tag = "2")]
+    pub workload: Option<String>,
+    #[prost(uint64, tag = "3")]
+    pub count: u64,
+    #[prost(string, repeated, tag = "4")]
+    pub items: Vec<String>,
+    #[prost(uint32, optional, tag = "5")]
+    pub workers: Option<u32>,
+    #[prost(uint64, optional, tag = "6")]
+    pub chunk_size: Option<u64>,
+    #[prost(string, optional, tag = "7")]
+    pub returns: Option<String>,  // the JSON request's `return`
+    #[prost(uint32, optional, tag = "8")]
+    pub cost: Option<u32>,
+    #[prost(string, optional, tag = "9")]
+    pub item_template: Option<String>,
+    #[prost(uint64, optional, tag = "10")]
+    pub item_length: Option<u64>,
+    #[prost(uint64, optional, tag = "11")]
+    pub seed: Option<u64>,
+    #[prost(string, optional, tag = "12")]
+    pub on_error: Option<String>,
+    #[prost(string, optional, tag = "13")]
+    pub idempotency_key: Option<String>,
+    #[prost(bool, tag = "14")]
+    pub cache: bool,
+    #[prost(uint32, optional, tag = "15")]
+    pub time_budget_ms: Option<u32>,
+    #[prost(bytes = "vec", optional, tag = "16")]
+    pub priorities: Option<Vec<u8>>,  // one byte per item
+    #[prost(string, optional, tag = "17")]
+    pub extra: Option<String>,
+}
+
+/// The summary fields of a ProcessResponse, read from its serialized JSON like a v1 response
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(default)]
+pub struct Response {
+    #[prost(uint64, tag = "1")]
+    pub processed: u64,
+    #[prost(uint64, tag = "2")]
+    pub duration_ms: u64,
+    #[prost(string, tag = "3")]
+    pub mode: String,
+    #[prost(string, tag = "4")]
+    pub workload: String,
+    #[prost(uint32, tag = "5")]
+    pub workers: u32,
+    #[prost(uint32, tag = "6")]
+    pub detected_cpus: u32,
+    #[prost(double, tag = "7")]
+    pub avg_ms_per_item: f64,
+    #[prost(uint32, tag = "8")]
+    pub threads_used: u32,
+    #[prost(uint64, tag = "9")]
+    pub completed: u64,
+    #[prost(uint64, tag = "10")]
+    pub skipped: u64,
+    #[prost(uint64, tag = "11")]
+    pub memory_used_kb: u64,
+    #[prost(double, optional, tag = "12")]
+    pub cpu_time_ms: Option<f64>,
+    #[prost(double, optional, tag = "13")]
+    pub speedup: Option<f64>,
+    #[prost(message, repeated, tag = "14")]
+    pub results: Vec<ItemResult>,
+    #[prost(message, repeated, tag = "15")]
+    pub failures: Vec<ItemFailure>,
+    #[prost(message, optional, tag = "16")]
+    pub checksum: Option<Checksum>,
+    #[prost(bool, tag = "17")]
+    pub cache_hit: bool,
+    #[prost(string, optional, tag = "18")]
+    pub aborted_reason: Option<String>,
+    #[prost(btree_map = "string, double", tag = "19")]
+    pub phases: BTreeMap<String, f64>,
+    #[prost(uint32, tag = "20")]
+    pub schema_version: u32,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(default)]
+pub struct ItemResult {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(string, tag = "2")]
+    pub output: String,
+    #[prost(uint64, tag = "3")]
+    pub duration_us: u64,
+    #[prost(string, tag = "4")]
+    pub thread_id: String,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(default)]
+pub struct ItemFailure {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(string, tag = "2")]
+    pub error: String,
+    #[prost(string, optional, tag = "3")]
+    pub panic_message: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(default)]
+pub struct Checksum {
+    #[prost(string, tag = "1")]
+    pub sha256: String,
+    #[prost(uint64, tag = "2")]
+    pub items: u64,
+}
+
+impl Request {
+    // Through serde, so every field left out takes the same default as in a JSON request, and
+    // the same validation follows; the fields set here override the same ones in `extra`
+    fn upgrade(self) -> Result<ProcessRequest, HandlerError> {
+        let mut fields = match self.extra.as_deref().map(serde_json::from_str::<Map<String, Value>>) {
+            Some(Ok(fields)) => fields,
+            Some(Err(e)) => return Err(invalid("extra", format!("extra must be a JSON object: {}", e))),
+            None => Map::new(),
+        };
+        fields.insert("mode".to_string(), self.mode.into());
+        fields.insert("count".to_string(), self.count.into());
+        let optional = [
+            ("workload", self.workload.map(Value::from)),
+            ("items", (!self.items.is_empty()).then(|| self.items.into())),
+            ("workers", self.workers.map(Value::from)),
+            ("chunk_size", self.chunk_size.map(Value::from)),
+            ("return", self.returns.map(Value::from)),
+            ("cost", self.cost.map(Value::from)),
+            ("item_template", self.item_template.map(Value::from)),
+            ("item_length", self.item_length.map(Value::from)),
+            ("seed", self.seed.map(Value::from)),
+            ("on_error", self.on_error.map(Value::from)),
+            ("idempotency_key", self.idempotency_key.map(Value::from)),
+            ("cache", self.cache.then_some(Value::Bool(true))),
+            ("time_budget_ms", self.time_budget_ms.map(Value::from)),
+            ("priorities", self.priorities.map(Value::from)),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value { fields.insert(name.to_string(), value); }
+        }
+        serde_json::from_value(Value::Object(fields)).map_err(|e| invalid("request", format!("invalid request: {}", e)))
+    }
+}
+
+pub fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var("CONTENT_FORMAT").as_deref() == Ok("protobuf"))
+}
+
+/// Errors stay structured JSON errors, so Step Functions and callers handle them the same way
+pub async fn handle(encoded: &str, context: &Context) -> Result<Value, HandlerError> {
+    let bytes = STANDARD.decode(encoded).map_err(|e| invalid("request", format!("the payload is not base64: {}", e)))?;
+    let request = Request::decode(bytes.as_slice()).map_err(|e| invalid("request", format!("invalid protobuf request: {}", e)))?.upgrade()?;
+    // Only a ProcessResponse has a message; the per-item fields would be out of reach in `compressed`
+    if matches!(request.mode, Mode::Benchmark | Mode::Sweep | Mode::Stress | Mode::Fanout | Mode::Reduce | Mode::Describe | Mode::Health) {
+        return Err(invalid("mode", format!("mode {} has no protobuf response, send it as JSON", request.mode.as_str())));
+    }
+    if request.compress_response {
+        return Err(invalid("compress_response", "compress_response cannot be combined with CONTENT_FORMAT=protobuf"));
+    }
+    let response = function_handler(request, context).await.inspect_err(|e| {
+        tracing::warn!(code = e.code(), error = %e, "invocation failed");
+    })?;
+    let value = serialize_response(response).map_err(|e| HandlerError::Io(e.into()))?;
+    let response: Response = serde_json::from_value(value).map_err(|e| HandlerError::Io(e.into()))?;
+    Ok(Value::String(STANDARD.encode(response.encode_to_vec())))
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use crate::handler::{RateLimitConfig, RateLimitSummary};
+
+// Highest rate and largest bucket a request (or DOWNSTREAM_RPS / DOWNSTREAM_BURST) may set
+pub const MAX_REQUESTS_PER_SECOND: f64 = 100_000.0;
+pub const MAX_BURST: u32 = 10_000;
+
+// Token bucket the downstream calls of one request share: the DynamoDB persister's batch writes,
+// the Firehose writer's record batches, the S3 writer's multipart requests and the source_s3
+// downloads all take a token before every call (retries included), so the results of every worker
+// reach the downstream services at no more than the configured rate however many threads produce
+// them. The default is no limit, which costs nothing
+#[derive(Clone, Default)]
+pub struct RateLimiter(Option<Arc<Bucket>>);
+
+struct Bucket {
+    rate: f64,
+    burst: u32,
+    // Tokens left, negative while callers wait for tokens they reserved, and when it was last refilled
+    state: Mutex<(f64, Instant)>,
+    requests: AtomicUsize,
+    throttled: AtomicUsize,
+    wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+}
+
+impl RateLimiter {
+    // The request's rate_limit, or DOWNSTREAM_RPS (and DOWNSTREAM_BURST) for requests without one;
+    // validation has checked the request's, an invalid variable is ignored with a warning
+    pub fn new(config: Option<&RateLimitConfig>) -> Self {
+        let (rate, burst) = match config {
+            Some(config) => (config.requests_per_second, config.burst),
+            None => match crate::config::var("DOWNSTREAM_RPS").map(|rps| rps.parse::<f64>()) {
+                Some(Ok(rps)) if valid_rate(rps) => {
+                    let burst = crate::config::var("DOWNSTREAM_BURST").and_then(|burst| burst.parse::<u32>().ok()).filter(|burst| valid_burst(*burst));
+                    (rps, burst)
+                }
+                Some(_) => {
+                    tracing::warn!("DOWNSTREAM_RPS must be a number greater than 0 and at most {}, not rate limiting", MAX_REQUESTS_PER_SECOND);
+                    return RateLimiter::default();
+                }
+                None => return RateLimiter::default(),
+            },
+        };
+        // One second's worth of calls by default
+        let burst = burst.unwrap_or_else(|| (rate.ceil() as u32).clamp(1, MAX_BURST));
+        RateLimiter(Some(Arc::new(Bucket {
+            rate, burst,
+            state: Mutex::new((burst as f64, Instant::now())),
+            requests: AtomicUsize::new(0),
+            throttled: AtomicUsize::new(0),
+            wait_us: AtomicU64::new(0),
+            max_wait_us: AtomicU64::new(0),
+        })))
+    }
+
+    // Take a token, waiting when the bucket is empty. A caller that finds it empty reserves the
+    // next token to come and sleeps until then, so concurrent callers line up behind each other
+    // instead of all polling for the same token. Only the S3, DynamoDB and Firehose sinks call this
+    #[cfg_attr(not(any(feature = "s3", feature = "dynamodb", feature = "firehose")), allow(dead_code))]
+    pub async fn acquire(&self) {
+        let Some(bucket) = &self.0 else { return };
+        bucket.requests.fetch_add(1, Ordering::Relaxed);
+        let wait = {
+            let mut state = bucket.state.lock().unwrap();
+            let (tokens, refilled) = &mut *state;
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * bucket.rate).min(bucket.burst as f64) - 1.0;
+            *refilled = now;
+            (*tokens < 0.0).then(|| std::time::Duration::from_secs_f64(-*tokens / bucket.rate))
+        };
+        if let Some(wait) = wait {
+            let wait_us = wait.as_micros() as u64;
+            bucket.throttled.fetch_add(1, Ordering::Relaxed);
+            bucket.wait_us.fetch_add(wait_us, Ordering::Relaxed);
+            bucket.max_wait_us.fetch_max(wait_us, Ordering::Relaxed);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // None without a limit; read once the sinks have finished
+    pub fn summary(&self) -> Option<RateLimitSummary> {
+        let bucket = self.0.as_ref()?;
+        Some(RateLimitSummary {
+            requests_per_second: bucket.rate,
+            burst: bucket.burst,
+            requests: bucket.requests.load(Ordering::Relaxed),
+            throttled: bucket.throttled.load(Ordering::Relaxed),
+            wait_ms: bucket.wait_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            max_wait_ms: bucket.max_wait_us.load(Ordering::Relaxed) as f64 / 1000.0,
+        })
+    }
+}
+
+pub fn valid_rate(rate: f64) -> bool {
+    rate.is_finite() && rate > 0.0 && rate <= MAX_REQUESTS_PER_SECOND
+}
+
+pub fn valid_burst(burst: u32) -> bool {
+    (1..=MAX_BURST).contains(&burst)
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::handler::ProcessRequest;
+use crate::validation::ValidationError;
+use crate::workload::{Workload, WorkloadParams};
+
+/// Object bodies by key, downloaded from the source_s3 bucket before processing
+pub type Objects = HashMap<String, Vec<u8>>;
+
+/// Whether a workload's items are object keys in the source_s3 bucket: never, when the request
+/// sets source_s3, or always
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ObjectSource {
+    Unused,
+    #[cfg_attr(not(any(feature = "image", feature = "parquet")), allow(dead_code))]
+    Optional,
+    Required,
+}
+
+// Workloads of Cargo features this build may lack, by feature; without it they do not register,
+// and requests for them are told which feature to build with instead of getting "unknown workload"
+const FEATURE_WORKLOADS: &[(&str, &str)] = &[
+    ("s3_checksum", "s3"), ("image_resize", "image"), ("csv_to_parquet", "parquet"), ("compress", "compress"),
+];
+
+/// What the handler knows of a workload: its request name, the checks a request for it needs on
+/// top of the generic ones (`validate`, which sees the parameters resolved with their defaults),
+/// and how the instance that runs a request's items is built (`prepare`, from those parameters and
+/// with source_s3 the downloaded objects; None when they do not make one, which validation
+/// reports). The instance's Workload::execute_item then runs on every item, on whichever thread
+/// the mode puts it
+pub trait WorkloadExecutor: Sync {
+    fn name(&self) -> &'static str;
+    fn object_source(&self) -> ObjectSource { ObjectSource::Unused }
+    fn validate(&self, _request: &ProcessRequest, _params: &WorkloadParams, _errors: &mut Vec<ValidationError>) {}
+    fn prepare(&self, params: &WorkloadParams, objects: Option<Objects>) -> Option<Arc<dyn Workload>>;
+}
+
+/// An entry of the registry, made by register_workload!
+pub struct Registration(pub &'static dyn WorkloadExecutor);
+
+inventory::collect!(Registration);
+
+// Used by register_workload!, so a dependent crate does not need inventory itself
+#[doc(hidden)]
+pub use inventory;
+
+/// Register an executor from the module that defines it, so a new workload is one module and this
+/// line: `register_workload!(MyExecutor);` makes it known to validation, the dispatcher and describe.
+/// A crate depending on this one registers its own workloads the same way
+#[macro_export]
+macro_rules! register_workload {
+    ($executor:expr) => {
+        $crate::registry::inventory::submit! { $crate::registry::Registration(&$executor) }
+    };
+}
+pub use register_workload;
+
+/// The executor registered under a request's workload name
+pub fn find(name: &str) -> Option<&'static dyn WorkloadExecutor> {
+    inventory::iter::<Registration>.into_iter().map(|registration| registration.0).find(|executor| executor.name() == name)
+}
+
+/// Every registered workload, sorted, as validation errors and describe list them
+pub fn names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = inventory::iter::<Registration>.into_iter().map(|registration| registration.0.name()).collect();
+    names.sort_unstable();
+    names
+}
+
+/// The Cargo feature a workload needs when this build was made without it
+pub fn missing_feature(name: &str) -> Option<&'static str> {
+    if find(name).is_some() { return None; }
+    FEATURE_WORKLOADS.iter().find(|(workload, _)| *workload == name).map(|(_, feature)| *feature)
+}
+
+/// Workloads of the features this build lacks, with the feature each needs
+pub fn unavailable() -> Vec<(&'static str, &'static str)> {
+    FEATURE_WORKLOADS.iter().copied().filter(|(workload, _)| find(workload).is_none()).collect()
+}
@@ -0,0 +1,95 @@
+use lambda_runtime::Context;
+use serde_json::Value;
+use crate::error::{invalid, HandlerError};
+use crate::handler::{batch_handler, function_handler, BatchRequest, Invocation, ProcessRequest};
+use crate::schema::{self, SchemaVersion};
+use crate::{kinesis_handler, schedule_handler, sqs_handler};
+#[cfg(feature = "s3")]
+use crate::s3_handler;
+#[cfg(feature = "protobuf")]
+use crate::protobuf;
+#[cfg(feature = "msgpack")]
+use crate::msgpack;
+#[cfg(feature = "fanout")]
+use crate::fanout;
+
+/// Route an invocation by event shape: SQS batches, EventBridge schedules, Kinesis batches, S3 notifications (when enabled), or a direct ProcessRequest or BatchRequest.
+/// Failures become structured Lambda errors (see HandlerError)
+pub async fn route(payload: Value, context: &Context) -> Result<Value, HandlerError> {
+    // A MessagePack body is routed as the JSON value it decodes to, and the response re-encoded
+    #[cfg(feature = "msgpack")]
+    if let Some((encoded, wrapped)) = msgpack::body(&payload) {
+        let (payload, decoded) = msgpack::decode(encoded, wrapped)?;
+        let response = Box::pin(route(payload, context)).await?;
+        return msgpack::encode(response, decoded);
+    }
+
+    // CONTENT_FORMAT=protobuf: a JSON string payload is a base64 protobuf request
+    #[cfg(feature = "protobuf")]
+    if let Some(encoded) = payload.as_str().filter(|_| protobuf::enabled()) {
+        return protobuf::handle(encoded, context).await;
+    }
+
+    if sqs_handler::is_sqs_event(&payload) {
+        let event = serde_json::from_value(payload).map_err(|e| invalid("event", format!("invalid SQS event: {}", e)))?;
+        let response = sqs_handler::sqs_handler(event).await.map_err(HandlerError::Io)?;
+        return serde_json::to_value(response).map_err(|e| HandlerError::Io(e.into()));
+    }
+
+    if schedule_handler::is_scheduled_event(&payload) {
+        return schedule_handler::schedule_handler(context).await;
+    }
+
+    if kinesis_handler::is_kinesis_event(&payload) {
+        let event = serde_json::from_value(payload).map_err(|e| invalid("event", format!("invalid Kinesis event: {}", e)))?;
+        let response = kinesis_handler::kinesis_handler(event).await.map_err(HandlerError::Io)?;
+        return serde_json::to_value(response).map_err(|e| HandlerError::Io(e.into()));
+    }
+
+    #[cfg(feature = "s3")]
+    if s3_handler::is_s3_event(&payload) {
+        let event = serde_json::from_value(payload).map_err(|e| invalid("event", format!("invalid S3 event: {}", e)))?;
+        let response = s3_handler::s3_handler(event).await.map_err(HandlerError::Io)?;
+        return serde_json::to_value(response).map_err(|e| HandlerError::Io(e.into()));
+    }
+
+    // A v1 request is up-converted and answered in v1 (schema/); there are no v1 batches
+    let version = SchemaVersion::of(&payload);
+    if version == SchemaVersion::V1 {
+        let (_, request) = schema::parse(payload).map_err(|e| invalid("request", format!("invalid schema_version 1 request: {}", e)))?;
+        let response = function_handler(request, context).await.inspect_err(|e| {
+            tracing::warn!(code = e.code(), error = %e, "invocation failed");
+        })?;
+        return schema::respond(version, response).map_err(|e| HandlerError::Io(e.into()));
+    }
+
+    // Mode "fanout" re-sends the request itself, one slice per invocation
+    #[cfg(feature = "fanout")]
+    if payload["mode"] == "fanout" {
+        let response = fanout::handle(payload, context).await.inspect_err(|e| {
+            tracing::warn!(code = e.code(), error = %e, "invocation failed");
+        })?;
+        return schema::respond(version, response).map_err(|e| HandlerError::Io(e.into()));
+    }
+
+    // An untagged enum only reports that no variant matched, so a mismatch is parsed again as the
+    // intended variant for a useful message
+    let request = match serde_json::from_value::<Invocation>(payload.clone()) {
+        Ok(Invocation::Batch(batch)) => return batch_handler(batch, context).await,
+        Ok(Invocation::Single(request)) => *request,
+        Err(_) if payload.get("jobs").is_some() => {
+            // Name the first job that does not parse, which serde's error for the whole array does not
+            let job = payload["jobs"].as_array().into_iter().flatten().enumerate()
+                .find_map(|(i, job)| serde_json::from_value::<ProcessRequest>(job.clone()).err().map(|e| (i, e)));
+            return Err(match job {
+                Some((i, e)) => invalid(&format!("jobs[{}]", i), format!("invalid request in jobs[{}]: {}", i, e)),
+                None => invalid("jobs", format!("invalid batch request: {}", serde_json::from_value::<BatchRequest>(payload).err().map_or_else(String::new, |e| e.to_string()))),
+            });
+        }
+        Err(_) => serde_json::from_value::<ProcessRequest>(payload).map_err(|e| invalid("request", format!("invalid request: {}", e)))?,
+    };
+    let response = function_handler(request, context).await.inspect_err(|e| {
+        tracing::warn!(code = e.code(), error = %e, "invocation failed");
+    })?;
+    schema::respond(version, response).map_err(|e| HandlerError::Io(e.into()))
+}
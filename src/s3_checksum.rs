@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::handler::S3SourceSummary;
+use crate::rate_limit::RateLimiter;
+use crate::s3_handler::client;
+
+// GetObject requests in flight at once; downloads are I/O bound, so this is independent of the worker count
+const DOWNLOAD_CONCURRENCY: usize = 32;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Download every distinct key in `keys` from `bucket` concurrently, before the workers start
+// The bodies are held in memory for the s3_checksum workload, which digests them on Rayon; every
+// GetObject waits for `limiter` as well as for a permit
+pub async fn download(bucket: &str, keys: &[String], limiter: &RateLimiter) -> Result<(HashMap<String, Vec<u8>>, S3SourceSummary), Error> {
+    let client = client().await;
+    let start = Instant::now();
+    let permits = Arc::new(Semaphore::new(DOWNLOAD_CONCURRENCY));
+    let mut downloads = JoinSet::new();
+    let mut objects = HashMap::with_capacity(keys.len());
+    for key in keys {
+        if objects.insert(key.clone(), Vec::new()).is_some() { continue; }
+        let (client, bucket, key, permits, limiter) = (client.clone(), bucket.to_string(), key.clone(), permits.clone(), limiter.clone());
+        downloads.spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            limiter.acquire().await;
+            let body = client.get_object().bucket(&bucket).key(&key).send().await
+                .map_err(|e| format!("s3://{}/{}: {}", bucket, key, aws_sdk_s3::error::DisplayErrorContext(e)))?
+                .body.collect().await?.into_bytes();
+            Ok::<_, Error>((key, body.to_vec()))
+        });
+    }
+
+    let mut bytes = 0;
+    while let Some(download) = downloads.join_next().await {
+        let (key, body) = download??;
+        bytes += body.len();
+        objects.insert(key, body);
+    }
+    let download_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let summary = S3SourceSummary {
+        bucket: bucket.to_string(),
+        objects: objects.len(),
+        bytes,
+        download_ms,
+        mib_per_sec: bytes as f64 / 1048576.0 / (download_ms / 1000.0).max(f64::EPSILON),
+        concurrency: DOWNLOAD_CONCURRENCY.min(objects.len()),
+    };
+    Ok((objects, summary))
+}
@@ -0,0 +1,96 @@
+use aws_lambda_events::event::s3::S3Event;
+use aws_sdk_s3::primitives::ByteStream;
+use serde::Serialize;
+use std::env;
+use std::time::Instant;
+use tokio::sync::OnceCell;
+use crate::handler::process_batch;
+use crate::validation::max_items;
+use crate::workload::{get_workload, WorkloadParams};
+
+// S3 client is created once per container and reused across invocations
+static S3_CLIENT: OnceCell<aws_sdk_s3::Client> = OnceCell::const_new();
+
+#[derive(Serialize)]
+pub struct S3BatchResponse {
+    objects: Vec<S3ObjectResult>,
+}
+
+#[derive(Serialize)]
+pub struct S3ObjectResult {
+    bucket: String, key: String, output_bucket: String, output_key: String,
+    processed: usize, duration_ms: u128, threads_used: usize,
+}
+
+pub async fn client() -> &'static aws_sdk_s3::Client {
+    S3_CLIENT.get_or_init(|| async {
+        aws_sdk_s3::Client::new(&aws_config::load_from_env().await)
+    }).await
+}
+
+/// S3 notifications put events in Records[] with eventSource "aws:s3"
+pub fn is_s3_event(payload: &serde_json::Value) -> bool {
+    payload["Records"][0]["eventSource"] == "aws:s3"
+}
+
+// Object keys in S3 events are URL-encoded with '+' for spaces
+fn decode_key(key: &str) -> String {
+    let bytes = key.as_bytes();
+    let hex = |b: u8| (b as char).to_digit(16);
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => { out.push((hi * 16 + lo) as u8); i += 2; }
+                _ => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Handle ObjectCreated events: read each object as newline-delimited items, hash them
+/// in parallel, and write one hash per line to OUTPUT_BUCKET/OUTPUT_PREFIX
+pub async fn s3_handler(event: S3Event) -> Result<S3BatchResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let client = client().await;
+    let output_prefix = env::var("OUTPUT_PREFIX").unwrap_or_else(|_| "hashed/".to_string());
+    let workload_name = env::var("S3_WORKLOAD").unwrap_or_else(|_| "bcrypt".to_string());
+    let workload = get_workload(&workload_name, &WorkloadParams::default())
+        .ok_or_else(|| format!("unknown workload '{}' in S3_WORKLOAD", workload_name))?;
+
+    let mut objects = Vec::new();
+    for record in event.records {
+        if !record.event_name.as_deref().unwrap_or_default().starts_with("ObjectCreated") { continue; }
+        let (Some(bucket), Some(raw_key)) = (record.s3.bucket.name, record.s3.object.key) else { continue };
+        let key = decode_key(&raw_key);
+
+        let body = client.get_object().bucket(&bucket).key(&key).send().await?
+            .body.collect().await?.into_bytes();
+        let items: Vec<String> = String::from_utf8_lossy(&body)
+            .lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect();
+        if items.len() > max_items() {
+            return Err(format!("s3://{}/{} has {} items, exceeds maximum of {}", bucket, key, items.len(), max_items()).into());
+        }
+
+        let start = Instant::now();
+        let processed = items.len();
+        let (hashes, threads_used) = process_batch(items, workload.clone())?;
+        let duration_ms = start.elapsed().as_millis();
+
+        let output_bucket = env::var("OUTPUT_BUCKET").unwrap_or_else(|_| bucket.clone());
+        let output_key = format!("{}{}", output_prefix, key);
+        client.put_object()
+            .bucket(&output_bucket).key(&output_key)
+            .content_type("text/plain")
+            .body(ByteStream::from(hashes.join("\n").into_bytes()))
+            .send().await?;
+
+        objects.push(S3ObjectResult { bucket, key, output_bucket, output_key, processed, duration_ms, threads_used });
+    }
+
+    Ok(S3BatchResponse { objects })
+}
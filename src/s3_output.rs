@@ -0,0 +1,120 @@
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use crate::handler::{ItemResult, ItemSink, S3OutputSummary};
+use crate::rate_limit::RateLimiter;
+use crate::s3_handler::client;
+
+// S3 multipart parts must be at least 5 MiB, except the last one
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Streams item results to s3://bucket/key as gzip-compressed NDJSON while the workers are still
+// hashing: results arrive through the sink, and every 5 MiB of compressed output goes up as one part
+pub struct S3Writer {
+    uri: String,
+    sink: ItemSink,
+    task: JoinHandle<Result<(usize, usize, usize), Error>>,
+}
+
+impl S3Writer {
+    // `forward` receives every item after it has been written (streaming response or other sinks);
+    // every request of the upload waits for `limiter`
+    pub fn start(bucket: String, key: String, limiter: RateLimiter, forward: Option<ItemSink>) -> Self {
+        let uri = format!("s3://{}/{}", bucket, key);
+        let (sink, items) = mpsc::unbounded_channel::<ItemResult>();
+        let task = tokio::spawn(async move {
+            let client = client().await;
+            limiter.acquire().await;
+            let upload = client.create_multipart_upload()
+                .bucket(&bucket).key(&key)
+                .content_type("application/x-ndjson")
+                .content_encoding("gzip")
+                .send().await?;
+            let upload_id = upload.upload_id().ok_or("S3 returned no multipart upload id")?.to_string();
+
+            let result = upload_parts(client, &limiter, &bucket, &key, &upload_id, items, forward).await;
+            if result.is_err() {
+                // Drop the uploaded parts so they are not billed as an incomplete upload
+                let _ = client.abort_multipart_upload().bucket(&bucket).key(&key).upload_id(&upload_id).send().await;
+            }
+            result
+        });
+        S3Writer { uri, sink, task }
+    }
+
+    pub fn sink(&self) -> ItemSink {
+        self.sink.clone()
+    }
+
+    // Upload the final part and complete the object; call once every sink clone is dropped
+    pub async fn finish(self) -> Result<S3OutputSummary, Error> {
+        drop(self.sink);
+        let start = Instant::now();
+        let (items, parts, compressed_bytes) = self.task.await??;
+        Ok(S3OutputSummary {
+            uri: self.uri,
+            items,
+            parts,
+            compressed_bytes,
+            wait_ms: start.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+// Returns (items written, parts uploaded, compressed bytes)
+async fn upload_parts(
+    client: &Client, limiter: &RateLimiter, bucket: &str, key: &str, upload_id: &str,
+    mut items: mpsc::UnboundedReceiver<ItemResult>, forward: Option<ItemSink>,
+) -> Result<(usize, usize, usize), Error> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(PART_SIZE), Compression::default());
+    let mut parts = Vec::new();
+    let mut count = 0;
+    let mut compressed_bytes = 0;
+
+    while let Some(item) = items.recv().await {
+        serde_json::to_writer(&mut encoder, &item)?;
+        encoder.write_all(b"\n")?;
+        count += 1;
+        if let Some(forward) = &forward {
+            let _ = forward.send(item);
+        }
+        if encoder.get_ref().len() >= PART_SIZE {
+            let part = std::mem::replace(encoder.get_mut(), Vec::with_capacity(PART_SIZE));
+            compressed_bytes += part.len();
+            limiter.acquire().await;
+            parts.push(upload_part(client, bucket, key, upload_id, parts.len() as i32 + 1, part).await?);
+        }
+    }
+    let last = encoder.finish()?;
+    compressed_bytes += last.len();
+    limiter.acquire().await;
+    parts.push(upload_part(client, bucket, key, upload_id, parts.len() as i32 + 1, last).await?);
+
+    let uploaded = parts.len();
+    limiter.acquire().await;
+    client.complete_multipart_upload()
+        .bucket(bucket).key(key).upload_id(upload_id)
+        .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+        .send().await?;
+    Ok((count, uploaded, compressed_bytes))
+}
+
+async fn upload_part(client: &Client, bucket: &str, key: &str, upload_id: &str, part_number: i32, body: Vec<u8>) -> Result<CompletedPart, Error> {
+    let output = client.upload_part()
+        .bucket(bucket).key(key).upload_id(upload_id)
+        .part_number(part_number)
+        .body(ByteStream::from(body))
+        .send().await?;
+    Ok(CompletedPart::builder()
+        .set_e_tag(output.e_tag().map(String::from))
+        .part_number(part_number)
+        .build())
+}
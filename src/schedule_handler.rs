@@ -0,0 +1,72 @@
+use lambda_runtime::Context;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::env;
+use std::time::{Instant, SystemTime};
+use crate::error::{invalid, HandlerError};
+use crate::handler::{function_handler, ProcessRequest};
+use crate::metrics::{self, Metric};
+use crate::platform::platform;
+
+const DEFAULT_SWEEP_COUNTS: &str = "10,50,100";
+
+/// EventBridge Scheduler and scheduled rules deliver {"source": "aws.events", "detail-type": "Scheduled Event", ...}
+pub fn is_scheduled_event(payload: &Value) -> bool {
+    payload["source"] == "aws.events" && payload["detail-type"] == "Scheduled Event"
+}
+
+// One count of the sweep; the fields of the benchmark response the metrics are built from
+#[derive(Serialize)]
+struct SweepPoint {
+    count: usize, sequential_ms: f64, parallel_ms: f64, speedup: f64, efficiency: f64,
+}
+
+/// Self-benchmark: run benchmark mode (sequential then parallel on the same items) once per count
+/// in SWEEP_COUNTS with SWEEP_WORKLOAD, and publish each point as EMF metrics dimensioned by
+/// workload, count and architecture, so a dashboard tracks the scaling curve across platform changes
+/// Counts run in ascending order; one that would not finish before the deadline, extrapolated
+/// linearly from the previous one, is skipped with the rest
+pub async fn schedule_handler(context: &Context) -> Result<Value, HandlerError> {
+    let workload = env::var("SWEEP_WORKLOAD").unwrap_or_else(|_| "bcrypt".to_string());
+    let mut counts = env::var("SWEEP_COUNTS").unwrap_or_else(|_| DEFAULT_SWEEP_COUNTS.to_string())
+        .split(',')
+        .map(|count| count.trim().parse::<usize>().map_err(|_| invalid("SWEEP_COUNTS", format!("SWEEP_COUNTS entry '{}' is not a count", count.trim()))))
+        .collect::<Result<Vec<_>, _>>()?;
+    counts.sort_unstable();
+    counts.dedup();
+
+    let mut points = Vec::with_capacity(counts.len());
+    let mut last: Option<(usize, f64)> = None;  // previous count and its wall-clock ms
+    for count in counts {
+        let remaining_ms = (context.deadline != 0).then(|| {
+            context.deadline().duration_since(SystemTime::now()).unwrap_or_default().as_secs_f64() * 1000.0
+        });
+        if let (Some(remaining_ms), Some((last_count, last_ms))) = (remaining_ms, last) {
+            if last_ms * count as f64 / last_count as f64 >= remaining_ms {
+                tracing::warn!(count, remaining_ms, "skipping the rest of the sweep: the deadline is too close");
+                break;
+            }
+        }
+
+        let request: ProcessRequest = serde_json::from_value(json!({ "mode": "benchmark", "workload": workload, "count": count }))
+            .map_err(|e| HandlerError::Io(e.into()))?;
+        let start = Instant::now();
+        let response = serde_json::to_value(function_handler(request, context).await?).map_err(|e| HandlerError::Io(e.into()))?;
+        last = Some((count, start.elapsed().as_secs_f64() * 1000.0));
+        let field = |name: &str| response[name].as_f64().unwrap_or_default();
+        let point = SweepPoint {
+            count, sequential_ms: field("sequential_ms"), parallel_ms: field("parallel_ms"),
+            speedup: field("speedup"), efficiency: field("efficiency"),
+        };
+        let count_dimension = count.to_string();
+        metrics::emit(&[("Sweep", "scheduled"), ("Workload", &workload), ("Count", &count_dimension), ("Architecture", env::consts::ARCH)], &[
+            Metric::milliseconds("SequentialDurationMs", point.sequential_ms),
+            Metric::milliseconds("DurationMs", point.parallel_ms),
+            Metric::none("Speedup", point.speedup),
+            Metric::none("Efficiency", point.efficiency),
+        ]);
+        points.push(point);
+    }
+    tracing::info!(workload, points = points.len(), "scheduled benchmark sweep complete");
+    Ok(json!({ "workload": workload, "architecture": env::consts::ARCH, "platform": platform(), "sweep": points }))
+}
@@ -0,0 +1,45 @@
+use serde_json::Value;
+use crate::handler::{serialize_response, HandlerResponse, ProcessRequest};
+
+pub mod v1;
+pub mod v2;
+
+/// The schema this build speaks natively, assumed for requests without `schema_version`
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Request/response contract a single request was sent under. v1 requests are up-converted to the
+/// current ProcessRequest before validation and get a v1 response back; everything else is v2
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SchemaVersion {
+    V1,
+    V2,
+}
+
+impl SchemaVersion {
+    /// Unsupported versions parse as v2, whose validation rejects them by name
+    pub fn of(payload: &Value) -> Self {
+        match payload.get("schema_version").and_then(Value::as_u64) {
+            Some(1) => SchemaVersion::V1,
+            _ => SchemaVersion::V2,
+        }
+    }
+}
+
+/// One request (not a batch envelope) at the version it declares
+pub fn parse(payload: Value) -> Result<(SchemaVersion, ProcessRequest), serde_json::Error> {
+    let version = SchemaVersion::of(&payload);
+    let request = match version {
+        SchemaVersion::V1 => serde_json::from_value::<v1::Request>(payload)?.upgrade()?,
+        SchemaVersion::V2 => serde_json::from_value(payload)?,
+    };
+    Ok((version, request))
+}
+
+/// Serialize `response` in the schema its request used (serialize_response for v2)
+pub fn respond(version: SchemaVersion, response: HandlerResponse) -> Result<Value, serde_json::Error> {
+    let value = serialize_response(response)?;
+    match version {
+        SchemaVersion::V1 => serde_json::to_value(serde_json::from_value::<v1::Response>(value)?),
+        SchemaVersion::V2 => Ok(value),
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::handler::ProcessRequest;
+
+// The original contract: bcrypt at cost 10 over `count` generated items, and `mode` "sequential",
+// "parallel" or anything else for "parallel when there is more than one worker"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Request {
+    #[serde(rename = "schema_version")]
+    _schema_version: u32,  // always 1: SchemaVersion::of routed the request here
+    count: usize,
+    mode: String,
+}
+
+impl Request {
+    // Through serde, so every field added since v1 takes the same default as in a v2 request that
+    // leaves it out. v1's catch-all mode is closest to "auto", which also picks by worker count
+    pub fn upgrade(self) -> Result<ProcessRequest, serde_json::Error> {
+        let mode = match self.mode.as_str() {
+            "sequential" | "parallel" => self.mode.as_str(),
+            _ => "auto",
+        };
+        serde_json::from_value(json!({ "count": self.count, "mode": mode, "workload": "bcrypt", "cost": 10 }))
+    }
+}
+
+// The eight fields a v1 response had, all still part of the v2 response they are read from
+#[derive(Deserialize, Serialize)]
+pub struct Response {
+    processed: usize, duration_ms: u128, mode: String, workers: usize,
+    detected_cpus: usize, avg_ms_per_item: f64, memory_used_kb: u64,
+    threads_used: usize,
+}
@@ -0,0 +1,7 @@
+// The current contract: ProcessRequest in, HandlerResponse out (a ProcessResponse for the
+// processing modes, or the benchmark, sweep and stress results), with `schema_version: 2` in the
+// response. v2 only grows by optional fields with serde defaults, so a v2 client written against
+// any earlier build keeps working, and a response field is never renamed or removed. The one
+// exception is opt-in: with compress_response the per-item fields travel inside `compressed`,
+// and `encoding` names them and the codec
+pub use crate::handler::{HandlerResponse as Response, ProcessRequest as Request};
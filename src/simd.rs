@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use crate::registry::{register_workload, Objects, WorkloadExecutor};
+use crate::workload::{log_lines, Workload, WorkloadParams};
+
+// Bytes every item hashes, generated once per container, and how many times it runs over them
+const BUFFER_BYTES: usize = 1024 * 1024;
+const PASSES: usize = 16;
+// 32 x 32-bit lanes: four AVX2 registers, or eight NEON registers. Each lane is a chain of
+// dependent multiplies, so several registers in flight hide the multiply latency
+const LANES: usize = 32;
+const STRIPE_BYTES: usize = LANES * 4;
+// xxHash32's primes for the lane rounds, xxHash64's for the final mix
+const PRIME32_1: u32 = 0x9E37_79B1;
+const PRIME32_2: u32 = 0x85EB_CA77;
+const PRIME32_3: u32 = 0xC2B2_AE3D;
+const PRIME64_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME64_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+
+static BUFFER: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+
+// Vector instructions the stripe loop runs on, detected at runtime: AVX2 on x86_64 (most Lambda
+// x86 hosts), NEON on aarch64 (Graviton, where it is always present); scalar code otherwise
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InstructionSet {
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+    Scalar,
+}
+
+impl InstructionSet {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            InstructionSet::Avx2 => "avx2",
+            #[cfg(target_arch = "aarch64")]
+            InstructionSet::Neon => "neon",
+            InstructionSet::Scalar => "scalar",
+        }
+    }
+}
+
+// The detection macros cache their result, so this is cheap to call per item
+pub fn detect() -> InstructionSet {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") { return InstructionSet::Avx2; }
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("neon") { return InstructionSet::Neon; }
+    InstructionSet::Scalar
+}
+
+// A multi-lane hash in the style of xxHash32 over the built-in buffer, seeded by the item: each
+// 128-byte stripe feeds one 32-bit word to each of 32 independent lanes (add, multiply, rotate), so
+// the lanes map directly onto vector registers. The output is the 64-bit hash in hex, identical
+// whichever instruction set computed it
+pub struct SimdHash;
+
+impl Workload for SimdHash {
+    fn name(&self) -> &'static str { "simd_hash" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_bytes(item.as_bytes())
+    }
+
+    fn execute_bytes(&self, item: &[u8]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(format!("{:016x}", hash(item, detect())))
+    }
+}
+
+struct SimdHashExecutor;
+
+impl WorkloadExecutor for SimdHashExecutor {
+    fn name(&self) -> &'static str { "simd_hash" }
+
+    fn prepare(&self, _params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(SimdHash))
+    }
+}
+
+register_workload!(SimdHashExecutor);
+
+fn hash(item: &[u8], instruction_set: InstructionSet) -> u64 {
+    let buffer = BUFFER.get_or_init(|| log_lines(BUFFER_BYTES));
+    let seed = item.iter().fold(0, |seed, byte| round(seed, *byte as u32));
+    let mut lanes: [u32; LANES] = std::array::from_fn(|lane| seed.wrapping_add(PRIME32_3.wrapping_mul(lane as u32 + 1)));
+    for _ in 0..PASSES {
+        match instruction_set {
+            // SAFETY: detect() only returns these when the CPU supports the instructions
+            #[cfg(target_arch = "x86_64")]
+            InstructionSet::Avx2 => unsafe { stripes_avx2(&mut lanes, buffer) },
+            #[cfg(target_arch = "aarch64")]
+            InstructionSet::Neon => unsafe { stripes_neon(&mut lanes, buffer) },
+            InstructionSet::Scalar => stripes_scalar(&mut lanes, buffer),
+        }
+    }
+
+    let tail = &buffer[buffer.len() / STRIPE_BYTES * STRIPE_BYTES..];
+    let mut hash = ((buffer.len() * PASSES) as u64).wrapping_mul(PRIME64_1);
+    for lane in lanes.into_iter().chain(tail.iter().map(|byte| *byte as u32)) {
+        hash = (hash ^ lane as u64).wrapping_mul(PRIME64_1).rotate_left(31);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME64_2);
+    hash ^ (hash >> 29)
+}
+
+fn round(lane: u32, word: u32) -> u32 {
+    lane.wrapping_add(word.wrapping_mul(PRIME32_2)).rotate_left(13).wrapping_mul(PRIME32_1)
+}
+
+fn stripes_scalar(lanes: &mut [u32; LANES], data: &[u8]) {
+    for stripe in data.chunks_exact(STRIPE_BYTES) {
+        for (lane, word) in lanes.iter_mut().zip(stripe.chunks_exact(4)) {
+            *lane = round(*lane, u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+        }
+    }
+}
+
+// Eight lanes per 256-bit register; x86 loads are little-endian like the scalar words
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn stripes_avx2(lanes: &mut [u32; LANES], data: &[u8]) {
+    use std::arch::x86_64::*;
+    let (prime1, prime2) = (_mm256_set1_epi32(PRIME32_1 as i32), _mm256_set1_epi32(PRIME32_2 as i32));
+    let mut acc: [__m256i; LANES / 8] = std::array::from_fn(|i| _mm256_loadu_si256(lanes.as_ptr().add(i * 8).cast()));
+    for stripe in data.chunks_exact(STRIPE_BYTES) {
+        for (i, acc) in acc.iter_mut().enumerate() {
+            let words = _mm256_loadu_si256(stripe.as_ptr().add(i * 32).cast());
+            let sum = _mm256_add_epi32(*acc, _mm256_mullo_epi32(words, prime2));
+            let rotated = _mm256_or_si256(_mm256_slli_epi32::<13>(sum), _mm256_srli_epi32::<19>(sum));
+            *acc = _mm256_mullo_epi32(rotated, prime1);
+        }
+    }
+    for (i, acc) in acc.iter().enumerate() {
+        _mm256_storeu_si256(lanes.as_mut_ptr().add(i * 8).cast(), *acc);
+    }
+}
+
+// Four lanes per 128-bit register; the rotate is a shift left plus a shift-right-insert
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn stripes_neon(lanes: &mut [u32; LANES], data: &[u8]) {
+    use std::arch::aarch64::*;
+    let (prime1, prime2) = (vdupq_n_u32(PRIME32_1), vdupq_n_u32(PRIME32_2));
+    let mut acc: [uint32x4_t; LANES / 4] = std::array::from_fn(|i| vld1q_u32(lanes.as_ptr().add(i * 4)));
+    for stripe in data.chunks_exact(STRIPE_BYTES) {
+        for (i, acc) in acc.iter_mut().enumerate() {
+            let words = vreinterpretq_u32_u8(vld1q_u8(stripe.as_ptr().add(i * 16)));
+            let sum = vmlaq_u32(*acc, words, prime2);
+            *acc = vmulq_u32(vsriq_n_u32::<19>(vshlq_n_u32::<13>(sum), sum), prime1);
+        }
+    }
+    for (i, acc) in acc.iter().enumerate() {
+        vst1q_u32(lanes.as_mut_ptr().add(i * 4), *acc);
+    }
+}
@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use crate::handler::{ItemResult, S3OutputConfig};
+
+// Projected size of the inline results above which they spill to /tmp (SPILL_THRESHOLD_MB), and
+// the size of /tmp the function was given (EPHEMERAL_STORAGE_MB, Lambda's default 512 MB)
+const DEFAULT_THRESHOLD_MB: u64 = 64;
+const DEFAULT_EPHEMERAL_STORAGE_MB: u64 = 512;
+// The spill file may fill this share of whichever is smaller, /tmp's size or its free space
+const STORAGE_SHARE_PERCENT: u64 = 90;
+// Bytes an ItemResult line adds to its output (index, duration, thread id and JSON punctuation)
+const LINE_OVERHEAD: u64 = 80;
+const DIR: &str = "/tmp";
+const PREFIX: &str = "spill-";
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Where a spilled batch's results went: `bytes` of NDJSON (one ItemResult per line, in completion
+// order) in `path`, also uploaded to `s3_uri` when the request set spill_s3
+#[derive(Clone, Serialize)]
+pub struct SpillSummary {
+    pub path: String, pub items: usize, pub bytes: u64, pub projected_bytes: u64, pub threshold_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_uri: Option<String>,
+}
+
+// The results of a return "items" batch on their way to the response. Every item adds its size to
+// a projection for the whole batch; while that stays under the threshold nothing happens and the
+// results go inline as usual, and once it crosses it the spill file is opened and every later
+// result is written there as it completes, with its output dropped from memory unless a summary
+// of the response reads it. The items finished before that are appended by finish(). The file
+// counts against /tmp: once it reaches the storage limit the job stops starting items
+pub struct Spill {
+    count: usize,
+    threshold_bytes: u64,
+    capacity_bytes: u64,
+    keep_outputs: bool,
+    path: String,
+    seen_bytes: AtomicU64,
+    seen: AtomicUsize,
+    full: AtomicBool,
+    file: Mutex<Option<SpillFile>>,  // None until the projection crosses the threshold
+}
+
+struct SpillFile {
+    writer: BufWriter<File>,
+    written: Vec<bool>,  // by index within the job
+    items: usize,
+    bytes: u64,
+}
+
+impl Spill {
+    // None when SPILL_THRESHOLD_MB is 0
+    pub fn new(count: usize, request_id: &str, keep_outputs: bool) -> Option<Self> {
+        let threshold_mb = env_mb("SPILL_THRESHOLD_MB", DEFAULT_THRESHOLD_MB);
+        if threshold_mb == 0 { return None; }
+        let storage = env_mb("EPHEMERAL_STORAGE_MB", DEFAULT_EPHEMERAL_STORAGE_MB) * 1024 * 1024;
+        let id = if request_id.is_empty() { "local" } else { request_id };
+        Some(Spill {
+            count,
+            threshold_bytes: threshold_mb * 1024 * 1024,
+            capacity_bytes: storage.min(free_bytes().unwrap_or(storage)) / 100 * STORAGE_SHARE_PERCENT,
+            keep_outputs,
+            path: format!("{}/{}{}.ndjson", DIR, PREFIX, id),
+            seen_bytes: AtomicU64::new(0),
+            seen: AtomicUsize::new(0),
+            full: AtomicBool::new(false),
+            file: Mutex::new(None),
+        })
+    }
+
+    // Whether the projection crossed the threshold, so finish() has a file to complete
+    pub fn spilled(&self) -> bool {
+        self.file.lock().unwrap().is_some()
+    }
+
+    // Set once the file reached the storage limit
+    pub fn full(&self) -> bool {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    // Offer the result of item `index` (within the job); true when the item was written to the
+    // file and the caller may drop its output
+    pub fn offer(&self, index: usize, result: &ItemResult) -> Result<bool, Error> {
+        let size = result.output.len() as u64 + LINE_OVERHEAD;
+        let seen_bytes = self.seen_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        let seen = self.seen.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut file = self.file.lock().unwrap();
+        if file.is_none() {
+            if seen_bytes / seen as u64 * self.count as u64 <= self.threshold_bytes { return Ok(false); }
+            *file = Some(self.open()?);
+            tracing::info!(path = self.path, projected_bytes = self.projected_bytes(), threshold_bytes = self.threshold_bytes, "results spill to /tmp");
+        }
+        self.write(file.as_mut().unwrap(), index, result)?;
+        Ok(!self.keep_outputs)
+    }
+
+    // Append the results the file does not have yet, then upload it to `target` when set;
+    // None when the batch never spilled
+    pub async fn finish(&self, results: impl Iterator<Item = (usize, ItemResult)>, target: Option<S3OutputConfig>) -> Result<Option<SpillSummary>, Error> {
+        let projected_bytes = self.projected_bytes();
+        let Some(mut file) = self.file.lock().unwrap().take() else { return Ok(None) };
+        for (index, result) in results {
+            if !file.written[index] { self.write(&mut file, index, &result)?; }
+        }
+        file.writer.flush()?;
+        tracing::info!(path = self.path, items = file.items, bytes = file.bytes, "spill file written");
+        let s3_uri = match target {
+            Some(target) => Some(upload(&self.path, target).await?),
+            None => None,
+        };
+        Ok(Some(SpillSummary { path: self.path.clone(), items: file.items, bytes: file.bytes, projected_bytes, threshold_bytes: self.threshold_bytes, s3_uri }))
+    }
+
+    fn projected_bytes(&self) -> u64 {
+        let seen = self.seen.load(Ordering::Relaxed).max(1) as u64;
+        self.seen_bytes.load(Ordering::Relaxed) / seen * self.count as u64
+    }
+
+    // /tmp outlives the invocation, so the file of an earlier spill in this container is removed
+    // first; asking for the results of that one again means running it again (or using spill_s3)
+    fn open(&self) -> Result<SpillFile, Error> {
+        for entry in std::fs::read_dir(DIR)?.flatten() {
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with(PREFIX) { let _ = std::fs::remove_file(entry.path()); }
+        }
+        let writer = BufWriter::new(File::create(&self.path)?);
+        Ok(SpillFile { writer, written: vec![false; self.count], items: 0, bytes: 0 })
+    }
+
+    // The items still running when the limit is reached are written too, into the headroom the
+    // storage share leaves
+    fn write(&self, file: &mut SpillFile, index: usize, result: &ItemResult) -> Result<(), Error> {
+        let line = line(result)?;
+        file.writer.write_all(&line)?;
+        file.bytes += line.len() as u64;
+        file.items += 1;
+        file.written[index] = true;
+        if file.bytes >= self.capacity_bytes && !self.full.swap(true, Ordering::Relaxed) {
+            tracing::warn!(path = self.path, bytes = file.bytes, capacity_bytes = self.capacity_bytes, "spill file reached the storage limit, no more items are started");
+        }
+        Ok(())
+    }
+}
+
+fn line(result: &ItemResult) -> Result<Vec<u8>, Error> {
+    let mut line = serde_json::to_vec(result)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+// A value that does not parse falls back to the default
+fn env_mb(name: &str, default: u64) -> u64 {
+    crate::config::var(name).map_or(default, |mb| mb.parse().unwrap_or_else(|_| {
+        tracing::warn!(default, "{} must be a number of MB, using the default", name);
+        default
+    }))
+}
+
+// Free bytes of the /tmp file system
+fn free_bytes() -> Option<u64> {
+    let path = std::ffi::CString::new(DIR).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 { return None; }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(feature = "s3")]
+async fn upload(path: &str, target: S3OutputConfig) -> Result<String, Error> {
+    let body = aws_sdk_s3::primitives::ByteStream::from_path(path).await?;
+    crate::s3_handler::client().await.put_object()
+        .bucket(&target.bucket).key(&target.key)
+        .content_type("application/x-ndjson")
+        .body(body)
+        .send().await
+        .map_err(|e| format!("s3://{}/{}: {}", target.bucket, target.key, aws_sdk_s3::error::DisplayErrorContext(e)))?;
+    Ok(format!("s3://{}/{}", target.bucket, target.key))
+}
+
+// Validation rejects spill_s3 without the "s3" feature
+#[cfg(not(feature = "s3"))]
+async fn upload(_path: &str, _target: S3OutputConfig) -> Result<String, Error> {
+    Err("spill_s3 requires building with the \"s3\" feature".into())
+}
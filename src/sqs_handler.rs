@@ -0,0 +1,50 @@
+use aws_lambda_events::event::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent};
+use std::env;
+use std::time::Instant;
+use crate::handler::process_each;
+use crate::workload::{get_workload, WorkloadParams};
+
+/// SQS event source mappings put messages in Records[] with eventSource "aws:sqs"
+pub fn is_sqs_event(payload: &serde_json::Value) -> bool {
+    payload["Records"][0]["eventSource"] == "aws:sqs"
+}
+
+/// Hash every message body in parallel and report failed messages individually, so SQS
+/// only retries those (requires ReportBatchItemFailures on the event source mapping)
+pub async fn sqs_handler(event: SqsEvent) -> Result<SqsBatchResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let workload_name = env::var("SQS_WORKLOAD").unwrap_or_else(|_| "bcrypt".to_string());
+    let workload = get_workload(&workload_name, &WorkloadParams::default())
+        .ok_or_else(|| format!("unknown workload '{}' in SQS_WORKLOAD", workload_name))?;
+
+    let mut failed_ids = Vec::new();
+    let mut ids = Vec::new();
+    let mut items = Vec::new();
+    for record in event.records {
+        let id = record.message_id.unwrap_or_default();
+        match record.body {
+            Some(body) if !body.is_empty() => { ids.push(id); items.push(body); }
+            _ => failed_ids.push(id),
+        }
+    }
+
+    let start = Instant::now();
+    let (results, threads_used) = process_each(items, workload.as_ref());
+    let duration_ms = start.elapsed().as_millis();
+
+    let mut succeeded = 0;
+    for (id, result) in ids.into_iter().zip(results) {
+        match result {
+            Ok(_) => succeeded += 1,
+            Err(e) => { tracing::warn!(message_id = %id, error = %e, "message failed"); failed_ids.push(id); }
+        }
+    }
+    tracing::info!(succeeded, failed = failed_ids.len(), duration_ms, threads_used, "sqs batch complete");
+
+    let mut response = SqsBatchResponse::default();
+    response.batch_item_failures = failed_ids.into_iter().map(|id| {
+        let mut failure = BatchItemFailure::default();
+        failure.item_identifier = id;
+        failure
+    }).collect();
+    Ok(response)
+}
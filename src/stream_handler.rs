@@ -0,0 +1,40 @@
+use lambda_runtime::streaming::{channel, Body, Response};
+use lambda_runtime::{Error, LambdaEvent};
+use serde_json::json;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use crate::handler::{function_handler_streaming, serialize_response, ProcessRequest};
+use crate::lifecycle;
+
+/// Response streaming entrypoint: each item result is written as an NDJSON line as soon as it
+/// completes, followed by one summary line (the usual response without `results`) or an error line
+pub async fn stream_handler(event: LambdaEvent<ProcessRequest>) -> Result<Response<Body>, Error> {
+    lifecycle::begin_invocation();
+    let (mut tx, body) = channel();
+    let (sink, mut items) = mpsc::unbounded_channel();
+    let LambdaEvent { payload: request, context } = event;
+
+    // Processing blocks its thread, so it runs off the runtime workers; otherwise the forwarding
+    // loop below could be starved until the end on 1-vCPU configurations
+    let handle = Handle::current();
+    let processing = tokio::task::spawn_blocking(move || {
+        handle.block_on(function_handler_streaming(request, &context, sink))
+    });
+
+    tokio::spawn(async move {
+        while let Some(item) = items.recv().await {
+            let line = json!(item).to_string() + "\n";
+            if tx.send_data(line.into()).await.is_err() { return; }  // client went away
+        }
+
+        // The sink closes when the handler returns, so the summary always comes last
+        let summary = match processing.await {
+            Ok(Ok(response)) => serialize_response(response).unwrap_or_else(|e| json!({ "error": e.to_string() })),
+            Ok(Err(e)) => e.to_json(),
+            Err(e) => json!({ "error": format!("processing task failed: {}", e) }),
+        };
+        let _ = tx.send_data((summary.to_string() + "\n").into()).await;
+    });
+
+    Ok(Response::from(body))
+}
@@ -0,0 +1,115 @@
+use lambda_extension::{service_fn, Extension, LambdaTelemetry, LambdaTelemetryRecord, LogBuffering, SharedService};
+use serde::Serialize;
+use std::sync::Mutex;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+// Name of the internal extension, unique within the function
+const EXTENSION_NAME: &str = "rust-multithread-telemetry";
+// Shortest batching the Telemetry API allows, so a report usually arrives before the next invocation
+const BUFFERING: LogBuffering = LogBuffering { timeout_ms: 25, max_bytes: 262_144, max_items: 1_000 };
+// Invocations waiting for their platform records; older ones are dropped (their report never came)
+const MAX_PENDING: usize = 16;
+
+/// What the platform and the function each measured about one invocation
+#[derive(Clone, Default, Serialize)]
+pub struct InvocationTelemetry {
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handler_ms: Option<f64>,             // the function's own timing: routing to serialized response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_done_ms: Option<f64>,        // platform.runtimeDone: event received to response sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_overhead_ms: Option<f64>,    // runtime_done_ms - handler_ms: the runtime client's share
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,            // platform.report
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billed_duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billed_delta_ms: Option<f64>,        // billed_duration_ms - handler_ms: what was billed beyond the handler
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory_used_mb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip)]
+    reported: bool,
+}
+
+impl InvocationTelemetry {
+    fn derive(&mut self) {
+        if let (Some(handler), Some(done)) = (self.handler_ms, self.runtime_done_ms) { self.runtime_overhead_ms = Some(done - handler); }
+        if let (Some(handler), Some(billed)) = (self.handler_ms, self.billed_duration_ms) { self.billed_delta_ms = Some(billed as f64 - handler); }
+    }
+}
+
+// Invocations by request ID until their platform.report arrives, then the latest complete one
+// until a response takes it
+static PENDING: Mutex<Vec<InvocationTelemetry>> = Mutex::new(Vec::new());
+static COMPLETE: Mutex<Option<InvocationTelemetry>> = Mutex::new(None);
+
+/// Register the extension and subscribe to platform telemetry (during init, before the runtime
+/// loop starts), then drive it as a second task beside the handler; outside Lambda there is no
+/// Telemetry API, so nothing is registered
+pub async fn start() -> Result<(), Error> {
+    if std::env::var("AWS_LAMBDA_RUNTIME_API").is_err() { return Ok(()) }
+    let extension = Extension::new()
+        .with_extension_name(EXTENSION_NAME)
+        .with_events(&[])
+        .with_telemetry_types(&["platform"])
+        .with_telemetry_buffering(BUFFERING)
+        .with_telemetry_processor(SharedService::new(service_fn(receive)))
+        .register().await?;
+    tokio::spawn(async move {
+        if let Err(e) = extension.run().await { tracing::warn!(error = %e, "telemetry extension stopped"); }
+    });
+    Ok(())
+}
+
+/// The function's timing of an invocation, recorded as its response is returned
+pub fn record_handler(request_id: &str, handler_ms: f64) {
+    update(request_id, |entry| entry.handler_ms = Some(handler_ms));
+}
+
+/// The latest invocation with a platform report, for the next response to carry (once)
+pub fn take_previous() -> Option<InvocationTelemetry> {
+    COMPLETE.lock().unwrap().take()
+}
+
+async fn receive(events: Vec<LambdaTelemetry>) -> Result<(), Error> {
+    for event in events {
+        match event.record {
+            LambdaTelemetryRecord::PlatformRuntimeDone { request_id, metrics, .. } => {
+                update(&request_id, |entry| entry.runtime_done_ms = metrics.map(|metrics| metrics.duration_ms));
+            }
+            LambdaTelemetryRecord::PlatformReport { request_id, status, metrics, .. } => update(&request_id, |entry| {
+                entry.duration_ms = Some(metrics.duration_ms);
+                entry.billed_duration_ms = Some(metrics.billed_duration_ms);
+                entry.max_memory_used_mb = Some(metrics.max_memory_used_mb);
+                entry.status = serde_json::to_value(status).ok().and_then(|status| status.as_str().map(str::to_string));
+                entry.reported = true;
+            }),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// The records of one invocation arrive from two sides in either order; once its report is in,
+// it moves to COMPLETE
+fn update(request_id: &str, apply: impl FnOnce(&mut InvocationTelemetry)) {
+    let mut pending = PENDING.lock().unwrap();
+    let index = match pending.iter().position(|entry| entry.request_id == request_id) {
+        Some(index) => index,
+        None => {
+            if pending.len() >= MAX_PENDING { pending.remove(0); }
+            pending.push(InvocationTelemetry { request_id: request_id.to_string(), ..Default::default() });
+            pending.len() - 1
+        }
+    };
+    apply(&mut pending[index]);
+    if pending[index].reported && pending[index].handler_ms.is_some() {
+        let mut entry = pending.remove(index);
+        entry.derive();
+        *COMPLETE.lock().unwrap() = Some(entry);
+    }
+}
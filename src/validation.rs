@@ -0,0 +1,617 @@
+use serde::Serialize;
+use std::env;
+use std::sync::Arc;
+use crate::error::HandlerError;
+use crate::generator::{ItemGenerator, ITEM_LENGTH_RANGE, MAX_TEMPLATE_LEN};
+use crate::handler::{Mode, OnError, Operation, ProcessRequest, Returns};
+use crate::memory::MemoryStats;
+use crate::nested::SALTS_RANGE;
+use crate::pepper::PEPPER_WORKLOADS;
+use crate::schema::CURRENT_VERSION;
+use crate::registry::{self, ObjectSource};
+use crate::workload::{
+    Argon2Settings, BcryptVerify, CompressSettings, JsonMapping, Pbkdf2Settings,
+    ScryptSettings, Workload, WorkloadParams, ARGON2_ITERATIONS_RANGE, ARGON2_LANES_RANGE, ARGON2_MEMORY_KIB_RANGE,
+    BCRYPT_COST_RANGE, BUFFER_KIB_RANGE, FACTOR_BITS_RANGE, MATRIX_SIZE_RANGE, MAX_DIMENSION_RANGE, MAX_PATTERNS, PBKDF2_ROUNDS_RANGE, SAMPLES_RANGE, SCRYPT_LOG_N_RANGE,
+    SCRYPT_P_RANGE, SCRYPT_R_RANGE,
+};
+
+// Default upper bound on items per invocation (bcrypt at cost 10 is ~100ms per item per vCPU)
+const DEFAULT_MAX_ITEMS: usize = 1000;
+// Memory held per item for the whole batch: input, output and result bookkeeping (generous)
+const ESTIMATED_ITEM_BYTES: u64 = 512;
+// Share of the function's free memory a batch may plan to use
+const MEMORY_HEADROOM: f64 = 0.5;
+// Upper bound on the total job size when it is split across invocations with `continuation`
+const MAX_CONTINUATION_ITEMS: usize = 10_000_000;
+// Upper bound on the request's `workers` (Lambda has at most 6 vCPUs; beyond that only oversubscription)
+const MAX_REQUEST_WORKERS: usize = 64;
+// Worker counts one sweep may run
+const MAX_SWEEP_POINTS: usize = 16;
+// Upper bound on async_limited permits: tokio's blocking pool holds at most 512 threads
+const MAX_PERMITS: usize = 512;
+// Seconds a stress run may last: Lambda's 15-minute timeout
+const STRESS_DURATION_RANGE: std::ops::RangeInclusive<u32> = 1..=900;
+// Milliseconds a best_effort run may budget, up to the same timeout
+const TIME_BUDGET_MS_RANGE: std::ops::RangeInclusive<u32> = 1..=900_000;
+// Allowed values of the numeric workload parameters, by request field (in the order `values` lists them)
+const PARAM_RANGES: [(&str, std::ops::RangeInclusive<u32>); 14] = [
+    ("cost", BCRYPT_COST_RANGE), ("memory_kib", ARGON2_MEMORY_KIB_RANGE), ("iterations", ARGON2_ITERATIONS_RANGE),
+    ("lanes", ARGON2_LANES_RANGE), ("rounds", PBKDF2_ROUNDS_RANGE), ("log_n", SCRYPT_LOG_N_RANGE), ("r", SCRYPT_R_RANGE),
+    ("p", SCRYPT_P_RANGE), ("max_dimension", MAX_DIMENSION_RANGE), ("buffer_kib", BUFFER_KIB_RANGE),
+    ("samples", SAMPLES_RANGE), ("factor_bits", FACTOR_BITS_RANGE), ("matrix_size", MATRIX_SIZE_RANGE),
+    ("salts", SALTS_RANGE),
+];
+// Longest accepted idempotency_key (also well under DynamoDB's 2 KiB partition key limit)
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+// Bounds of a request's retry policy
+const RETRY_ATTEMPTS_RANGE: std::ops::RangeInclusive<u32> = 1..=10;
+const MAX_RETRY_BASE_DELAY_MS: u64 = 10_000;
+
+/// One rejected request field; `details` optionally carries the values behind the decision
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationError {
+    pub field: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ValidationError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        ValidationError { field: field.to_string(), message: message.into(), details: None }
+    }
+}
+
+// What run_request needs from a request that passed validation
+pub struct Validated {
+    pub workload: Arc<dyn Workload>,
+    pub params: WorkloadParams,
+    pub generator: ItemGenerator,  // used when the request has neither items nor pairs
+    pub total: usize,   // items in the whole job
+    pub offset: usize,  // first item of this invocation's slice
+    pub count: usize,   // items in this invocation's slice
+}
+
+// Check the whole request against every rule and report all violations at once
+// `workers` feeds the memory estimate; `streaming` is set for the response streaming entrypoint
+pub fn validate(request: &ProcessRequest, workers: usize, streaming: bool) -> Result<Validated, HandlerError> {
+    let mut errors = Vec::new();
+
+    match request.schema_version {
+        None | Some(CURRENT_VERSION) => {}
+        Some(1) => errors.push(ValidationError::new("schema_version", "schema_version 1 requests are not accepted as batch jobs or over response streaming")),
+        Some(version) => errors.push(ValidationError::new("schema_version", format!("unsupported schema_version {}, expected 1 or {}", version, CURRENT_VERSION))),
+    }
+    let verify = request.operation == Operation::Verify;
+    let total = match (&request.pairs, &request.items, &request.items_base64) {
+        (Some(pairs), _, _) => pairs.len(),
+        (None, Some(items), _) => items.len(),
+        (None, None, Some(blobs)) => blobs.len(),
+        // Stress runs for a duration rather than a batch, generating items as it goes
+        (None, None, None) if request.mode == Mode::Stress => 0,
+        (None, None, None) => request.count,
+    };
+    match (&request.pairs, &request.items, &request.items_base64, total) {
+        (Some(_), _, _, 0) => errors.push(ValidationError::new("pairs", "pairs must not be empty")),
+        (None, Some(_), _, 0) => errors.push(ValidationError::new("items", "items must not be empty")),
+        (None, None, Some(_), 0) => errors.push(ValidationError::new("items_base64", "items_base64 must not be empty")),
+        (None, None, None, 0) if !verify && request.mode != Mode::Stress => errors.push(ValidationError::new("count", "count must be greater than 0")),
+        _ => {}
+    }
+    if request.items_base64.is_some() && (request.items.is_some() || request.pairs.is_some()) {
+        errors.push(ValidationError::new("items_base64", "items_base64 cannot be combined with items or pairs"));
+    }
+    if verify {
+        if request.workload != "bcrypt" {
+            errors.push(ValidationError::new("operation", format!("operation \"verify\" is only supported by the bcrypt workload, not '{}'", request.workload)));
+        }
+        if request.pairs.is_none() { errors.push(ValidationError::new("pairs", "operation \"verify\" requires pairs")); }
+        if request.items.is_some() { errors.push(ValidationError::new("items", "items cannot be combined with pairs; put each password in its pair")); }
+        if request.cost.is_some() { errors.push(ValidationError::new("cost", "cost is taken from each hash when verifying")); }
+    } else if request.pairs.is_some() {
+        errors.push(ValidationError::new("pairs", "pairs is only used by operation \"verify\""));
+    }
+    for (i, pair) in request.pairs.iter().flatten().enumerate() {
+        // Characters outside bcrypt's alphabet would also break the "<hash>:<password>" item encoding
+        let field = format!("pairs[{}].hash", i);
+        let bcrypt_alphabet = pair.hash.bytes().all(|b| b.is_ascii_alphanumeric() || b"$./".contains(&b));
+        match pair.hash.parse::<bcrypt::HashParts>() {
+            Ok(parts) if bcrypt_alphabet && BCRYPT_COST_RANGE.contains(&parts.get_cost()) => {}
+            // A cost above the limit would make one pair run for minutes: each step doubles the time
+            Ok(parts) if bcrypt_alphabet => errors.push(ValidationError::new(&field, format!(
+                "{} has cost {}, outside the allowed range {}-{}", field, parts.get_cost(), BCRYPT_COST_RANGE.start(), BCRYPT_COST_RANGE.end(),
+            ))),
+            _ => errors.push(ValidationError::new(&field, format!("{} is not a bcrypt hash such as \"$2b$10$...\"", field))),
+        }
+    }
+    if request.items.is_some() || request.pairs.is_some() || request.items_base64.is_some() {
+        for (field, set) in [("item_template", request.item_template.is_some()), ("item_length", request.item_length.is_some()), ("seed", request.seed.is_some())] {
+            if set { errors.push(ValidationError::new(field, format!("{} only applies to generated items, not to items, items_base64 or pairs", field))); }
+        }
+    }
+    if let Some(template) = &request.item_template {
+        if template.is_empty() || template.len() > MAX_TEMPLATE_LEN {
+            errors.push(ValidationError::new("item_template", format!("item_template must be between 1 and {} bytes", MAX_TEMPLATE_LEN)));
+        }
+        // item_length may cut the template anywhere, which only lands on a character boundary in ASCII
+        if !template.is_ascii() { errors.push(ValidationError::new("item_template", "item_template must be ASCII")); }
+        if template.matches("{random}").count() > 1 {
+            errors.push(ValidationError::new("item_template", "item_template may contain {random} at most once"));
+        }
+    }
+    if let Some(length) = request.item_length.filter(|length| !ITEM_LENGTH_RANGE.contains(length)) {
+        errors.push(ValidationError::new("item_length", format!("item_length {} is outside the allowed range {}-{}", length, ITEM_LENGTH_RANGE.start(), ITEM_LENGTH_RANGE.end())));
+    }
+    let fallback = ItemGenerator::default();
+    let generator = ItemGenerator {
+        template: request.item_template.clone().unwrap_or(fallback.template),
+        length: request.item_length,
+        seed: request.seed.unwrap_or(fallback.seed),
+    };
+    if let Some(workers) = request.workers.filter(|workers| !(1..=MAX_REQUEST_WORKERS).contains(workers)) {
+        errors.push(ValidationError::new("workers", format!("workers {} is outside the allowed range 1-{}", workers, MAX_REQUEST_WORKERS)));
+    }
+    if request.return_items && request.returns == Returns::Checksum {
+        errors.push(ValidationError::new("return", "return \"checksum\" cannot be combined with return_items"));
+    }
+    if request.chunk_size == Some(0) { errors.push(ValidationError::new("chunk_size", "chunk_size must be greater than 0")); }
+    // One priority per item of the whole job (a continuation slice takes its share)
+    if let Some(priorities) = &request.priorities {
+        if !matches!(request.mode, Mode::Sequential | Mode::Parallel | Mode::Auto | Mode::BestEffort) {
+            errors.push(ValidationError::new("priorities", "priorities is only used by sequential, parallel, auto and best_effort modes"));
+        }
+        if request.chunk_size.is_some() { errors.push(ValidationError::new("priorities", "priorities cannot be combined with chunk_size")); }
+        if priorities.len() != total {
+            errors.push(ValidationError::new("priorities", format!("priorities has {} entries but the job has {} items", priorities.len(), total)));
+        }
+    }
+    let values = [
+        request.cost, request.memory_kib, request.iterations, request.lanes, request.rounds, request.log_n, request.r,
+        request.p, request.max_dimension, request.buffer_kib, request.samples, request.factor_bits, request.matrix_size,
+        request.salts,
+    ];
+    for ((field, range), value) in PARAM_RANGES.into_iter().zip(values) {
+        match value {
+            Some(value) if !range.contains(&value) => {
+                errors.push(ValidationError::new(field, format!("{} {} is outside the allowed range {}-{}", field, value, range.start(), range.end())));
+            }
+            _ => {}
+        }
+    }
+    let defaults = Argon2Settings::default();
+    let argon2 = Argon2Settings {
+        memory_kib: request.memory_kib.unwrap_or(defaults.memory_kib),
+        iterations: request.iterations.unwrap_or(defaults.iterations),
+        lanes: request.lanes.unwrap_or(defaults.lanes),
+    };
+    // saturating: lanes out of its range (already reported) must not overflow the product
+    if argon2.memory_kib < argon2.lanes.saturating_mul(8) {
+        errors.push(ValidationError::new("memory_kib", format!("memory_kib must be at least 8 x lanes ({})", argon2.lanes.saturating_mul(8))));
+    }
+    let pbkdf2 = Pbkdf2Settings { rounds: request.rounds.unwrap_or(Pbkdf2Settings::default().rounds) };
+    let defaults = ScryptSettings::default();
+    let scrypt = ScryptSettings {
+        log_n: request.log_n.map_or(defaults.log_n, |log_n| log_n.min(*SCRYPT_LOG_N_RANGE.end()) as u8),
+        r: request.r.unwrap_or(defaults.r),
+        p: request.p.unwrap_or(defaults.p),
+    };
+    let defaults = CompressSettings::default();
+    let codec = request.codec.unwrap_or(defaults.codec);
+    let compress = CompressSettings {
+        codec, level: request.level.unwrap_or(codec.default_level()), buffer_kib: request.buffer_kib.unwrap_or(defaults.buffer_kib),
+    };
+    if !codec.levels().contains(&compress.level) {
+        errors.push(ValidationError::new("level", format!("level {} is outside the {} range {}-{}", compress.level, codec.as_str(), codec.levels().start(), codec.levels().end())));
+    }
+    let fallback = WorkloadParams::default();
+    let params = WorkloadParams {
+        bcrypt_cost: request.cost.unwrap_or(fallback.bcrypt_cost), argon2, pbkdf2, scrypt, compress,
+        max_dimension: request.max_dimension.unwrap_or(fallback.max_dimension),
+        samples: request.samples.unwrap_or(fallback.samples),
+        factor_bits: request.factor_bits.unwrap_or(fallback.factor_bits),
+        matrix_size: request.matrix_size.unwrap_or(fallback.matrix_size),
+        salts: request.salts.unwrap_or(fallback.salts),
+        patterns: request.patterns.clone().unwrap_or(fallback.patterns),
+        mapping: request.mapping.as_ref().map_or(fallback.mapping, |mapping| JsonMapping(
+            mapping.iter().map(|(field, pointer)| (field.clone(), pointer.as_str().unwrap_or_default().to_string())).collect(),
+        )),
+    };
+    if let Some(patterns) = &request.patterns {
+        if request.workload != "grep" { errors.push(ValidationError::new("patterns", "patterns is only used by the grep workload")); }
+        if patterns.is_empty() || patterns.len() > MAX_PATTERNS {
+            errors.push(ValidationError::new("patterns", format!("patterns must have between 1 and {} entries", MAX_PATTERNS)));
+        }
+        for (i, pattern) in patterns.iter().enumerate() {
+            if let Err(e) = regex::Regex::new(pattern) {
+                // The parser's message spans lines (pattern, caret, reason); keep the reason
+                let reason = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                let field = format!("patterns[{}]", i);
+                errors.push(ValidationError::new(&field, format!("{} is not a valid regex: {}", field, reason)));
+            }
+        }
+    }
+    if let Some(mapping) = &request.mapping {
+        if request.workload != "json_transform" {
+            errors.push(ValidationError::new("mapping", "mapping is only used by the json_transform workload"));
+        }
+        if mapping.is_empty() { errors.push(ValidationError::new("mapping", "mapping must not be empty")); }
+        for (field, pointer) in mapping {
+            if !pointer.as_str().is_some_and(|pointer| pointer.is_empty() || pointer.starts_with('/')) {
+                let field = format!("mapping.{}", field);
+                errors.push(ValidationError::new(&field, format!("{} must be a JSON Pointer string such as \"/user/name\"", field)));
+            }
+        }
+    }
+    // The workload's own checks; the objects of source_s3 are downloaded after validation, so
+    // until then its instance has none
+    let executor = registry::find(&request.workload);
+    if let Some(executor) = executor { executor.validate(request, &params, &mut errors); }
+    let workload = match executor {
+        Some(_) if verify => Some(Arc::new(BcryptVerify) as Arc<dyn Workload>),
+        Some(executor) => executor.prepare(&params, None),
+        None => None,
+    };
+    if let Some(feature) = registry::missing_feature(&request.workload) {
+        errors.push(ValidationError::new("workload", format!("{} requires building with the \"{}\" feature", request.workload, feature)));
+    } else if executor.is_none() {
+        errors.push(ValidationError::new("workload", format!("unknown workload '{}', expected one of: {}", request.workload, registry::names().join(", "))));
+    }
+    // The pepper is appended to text items; verify pairs already hold their hashes
+    if request.pepper {
+        if crate::pepper::get().is_none() {
+            errors.push(ValidationError::new("pepper", "pepper requires PEPPER_SECRET_ID to name a secret, loaded at init"));
+        }
+        if !PEPPER_WORKLOADS.contains(&request.workload.as_str()) {
+            errors.push(ValidationError::new("pepper", format!("pepper is only used by the {} workloads", PEPPER_WORKLOADS.join(", "))));
+        }
+        if verify { errors.push(ValidationError::new("pepper", "pepper cannot be combined with operation \"verify\"")); }
+        if request.items_base64.is_some() { errors.push(ValidationError::new("pepper", "pepper cannot be combined with items_base64")); }
+    }
+
+    // Benchmark, sweep and stress compare timings of whole runs, which the per-run integrations would distort
+    let benchmark = matches!(request.mode, Mode::Benchmark | Mode::Sweep | Mode::Stress);
+    let mode = request.mode.as_str();
+    match (&request.worker_counts, request.mode) {
+        (Some(counts), Mode::Sweep) => {
+            if counts.is_empty() || counts.len() > MAX_SWEEP_POINTS {
+                errors.push(ValidationError::new("worker_counts", format!("worker_counts must have between 1 and {} entries", MAX_SWEEP_POINTS)));
+            }
+            if let Some(count) = counts.iter().find(|count| !(1..=MAX_REQUEST_WORKERS).contains(count)) {
+                errors.push(ValidationError::new("worker_counts", format!("worker count {} is outside the allowed range 1-{}", count, MAX_REQUEST_WORKERS)));
+            }
+        }
+        (None, Mode::Sweep) => errors.push(ValidationError::new("worker_counts", "sweep mode requires worker_counts")),
+        (Some(_), _) => errors.push(ValidationError::new("worker_counts", "worker_counts is only used by sweep mode")),
+        (None, _) => {}
+    }
+    // Fanout requests are split by the router (fanout.rs) and never get here in their own mode
+    if request.mode == Mode::Fanout {
+        let message = if cfg!(feature = "fanout") { "fanout mode is only accepted as a direct invocation" } else { "fanout mode requires building with the \"fanout\" feature" };
+        errors.push(ValidationError::new("mode", message));
+    } else if request.fanout.is_some() {
+        errors.push(ValidationError::new("fanout", "fanout is only used by fanout mode"));
+    }
+    if request.reduce.is_some() { errors.push(ValidationError::new("reduce", "reduce is only used by reduce mode")); }
+    if request.mode == Mode::Sweep && request.workers.is_some() {
+        errors.push(ValidationError::new("workers", "sweep mode takes its pool sizes from worker_counts, not workers"));
+    }
+    match (request.duration_seconds, request.mode) {
+        (Some(seconds), Mode::Stress) if !STRESS_DURATION_RANGE.contains(&seconds) => errors.push(ValidationError::new(
+            "duration_seconds", format!("duration_seconds {} is outside the allowed range {}-{}", seconds, STRESS_DURATION_RANGE.start(), STRESS_DURATION_RANGE.end()),
+        )),
+        (None, Mode::Stress) => errors.push(ValidationError::new("duration_seconds", "stress mode requires duration_seconds")),
+        (Some(_), mode) if mode != Mode::Stress => errors.push(ValidationError::new("duration_seconds", "duration_seconds is only used by stress mode")),
+        _ => {}
+    }
+    match (request.time_budget_ms, request.mode) {
+        (Some(ms), Mode::BestEffort) if !TIME_BUDGET_MS_RANGE.contains(&ms) => errors.push(ValidationError::new(
+            "time_budget_ms", format!("time_budget_ms {} is outside the allowed range {}-{}", ms, TIME_BUDGET_MS_RANGE.start(), TIME_BUDGET_MS_RANGE.end()),
+        )),
+        (None, Mode::BestEffort) => errors.push(ValidationError::new("time_budget_ms", "best_effort mode requires time_budget_ms")),
+        (Some(_), mode) if mode != Mode::BestEffort => errors.push(ValidationError::new("time_budget_ms", "time_budget_ms is only used by best_effort mode")),
+        _ => {}
+    }
+    if request.mode == Mode::Stress {
+        if request.items.is_some() { errors.push(ValidationError::new("items", "stress mode generates its own items")); }
+        if request.items_base64.is_some() { errors.push(ValidationError::new("items_base64", "stress mode generates its own items")); }
+        if request.pairs.is_some() { errors.push(ValidationError::new("pairs", "stress mode generates its own items")); }
+    }
+    match request.permits {
+        Some(_) if request.mode != Mode::AsyncLimited => errors.push(ValidationError::new("permits", "permits is only used by async_limited mode")),
+        Some(permits) if !(1..=MAX_PERMITS).contains(&permits) => {
+            errors.push(ValidationError::new("permits", format!("permits {} is outside the allowed range 1-{}", permits, MAX_PERMITS)));
+        }
+        _ => {}
+    }
+
+    // Without a known workload only MAX_ITEMS applies; a sweep holds its largest pool's working set,
+    // async_limited one per permit
+    let workers = request.worker_counts.iter().flatten().copied().filter(|count| *count <= MAX_REQUEST_WORKERS).max().unwrap_or(workers);
+    let workers = request.permits.filter(|permits| *permits <= MAX_PERMITS).unwrap_or(workers);
+    let item_bytes = request.item_length.filter(|length| ITEM_LENGTH_RANGE.contains(length)).unwrap_or(0) as u64;
+    let item_limit = ItemLimit::compute(workload.as_deref(), workers, item_bytes);
+    let (offset, count) = match &request.continuation {
+        None => {
+            errors.extend(item_limit.check("count", total));
+            (0, total)
+        }
+        Some(continuation) => {
+            if total > MAX_CONTINUATION_ITEMS {
+                errors.push(ValidationError::new("count", format!("count exceeds maximum of {} items for continuation jobs", MAX_CONTINUATION_ITEMS)));
+            }
+            let limit = continuation.limit.unwrap_or(item_limit.limit);
+            if limit == 0 {
+                errors.push(ValidationError::new("continuation.limit", "continuation.limit must be greater than 0"));
+            }
+            errors.extend(item_limit.check("continuation.limit", limit));
+            if total > 0 && continuation.offset >= total {
+                errors.push(ValidationError::new("continuation.offset", format!("continuation.offset {} is past the end of the job ({} items)", continuation.offset, total)));
+            }
+            if benchmark { errors.push(ValidationError::new("continuation", format!("{} mode does not support continuation", mode))); }
+            (continuation.offset, limit.min(total.saturating_sub(continuation.offset)))
+        }
+    };
+
+    if let Some(persist) = &request.persist {
+        if cfg!(not(feature = "dynamodb")) {
+            errors.push(ValidationError::new("persist", "persist requires building with the \"dynamodb\" feature"));
+        }
+        if persist.table.is_empty() { errors.push(ValidationError::new("persist.table", "persist.table must not be empty")); }
+        if benchmark { errors.push(ValidationError::new("persist", format!("{} mode does not support persist", mode))); }
+    }
+    if let Some(firehose) = &request.output_firehose {
+        if cfg!(not(feature = "firehose")) {
+            errors.push(ValidationError::new("output_firehose", "output_firehose requires building with the \"firehose\" feature"));
+        }
+        if firehose.delivery_stream.is_empty() {
+            errors.push(ValidationError::new("output_firehose.delivery_stream", "output_firehose.delivery_stream must not be empty"));
+        }
+        #[cfg(feature = "firehose")]
+        if let Err((field, e)) = crate::firehose::check(firehose) { errors.push(ValidationError::new(&format!("output_firehose.{}", field), e)); }
+        if benchmark { errors.push(ValidationError::new("output_firehose", format!("{} mode does not support output_firehose", mode))); }
+    }
+    if let Some(topic_arn) = &request.notify_topic_arn {
+        if cfg!(not(feature = "sns")) {
+            errors.push(ValidationError::new("notify_topic_arn", "notify_topic_arn requires building with the \"sns\" feature"));
+        }
+        // arn:<partition>:sns:<region>:<account>:<topic>
+        let parts: Vec<&str> = topic_arn.split(':').collect();
+        if parts.len() != 6 || parts[0] != "arn" || parts[2] != "sns" || parts[5].is_empty() {
+            errors.push(ValidationError::new("notify_topic_arn", "notify_topic_arn must be an SNS topic ARN (arn:aws:sns:<region>:<account>:<topic>)"));
+        }
+        if benchmark { errors.push(ValidationError::new("notify_topic_arn", format!("{} mode does not support notify_topic_arn", mode))); }
+    }
+    if let Some(limit) = &request.rate_limit {
+        if !crate::rate_limit::valid_rate(limit.requests_per_second) {
+            errors.push(ValidationError::new("rate_limit.requests_per_second", format!("rate_limit.requests_per_second must be greater than 0 and at most {}", crate::rate_limit::MAX_REQUESTS_PER_SECOND)));
+        }
+        if limit.burst.is_some_and(|burst| !crate::rate_limit::valid_burst(burst)) {
+            errors.push(ValidationError::new("rate_limit.burst", format!("rate_limit.burst must be between 1 and {}", crate::rate_limit::MAX_BURST)));
+        }
+        if benchmark { errors.push(ValidationError::new("rate_limit", format!("{} mode does not support rate_limit", mode))); }
+    }
+    if let Some(target) = &request.output_s3 {
+        if cfg!(not(feature = "s3")) {
+            errors.push(ValidationError::new("output_s3", "output_s3 requires building with the \"s3\" feature"));
+        }
+        if target.bucket.is_empty() { errors.push(ValidationError::new("output_s3.bucket", "output_s3.bucket must not be empty")); }
+        if target.key.is_empty() { errors.push(ValidationError::new("output_s3.key", "output_s3.key must not be empty")); }
+        if benchmark { errors.push(ValidationError::new("output_s3", format!("{} mode does not support output_s3", mode))); }
+    }
+    if let Some(target) = &request.partial_s3 {
+        if cfg!(not(feature = "s3")) {
+            errors.push(ValidationError::new("partial_s3", "partial_s3 requires building with the \"s3\" feature"));
+        }
+        if target.bucket.is_empty() { errors.push(ValidationError::new("partial_s3.bucket", "partial_s3.bucket must not be empty")); }
+        if target.key.is_empty() { errors.push(ValidationError::new("partial_s3.key", "partial_s3.key must not be empty")); }
+        if benchmark { errors.push(ValidationError::new("partial_s3", format!("{} mode does not support partial_s3", mode))); }
+    }
+    if let Some(checkpoint) = &request.checkpoint {
+        if !crate::checkpoint::valid_job_id(&checkpoint.job_id) {
+            errors.push(ValidationError::new("checkpoint.job_id", "checkpoint.job_id must be 1-128 characters of A-Z, a-z, 0-9, '.', '_' and '-'"));
+        }
+        if checkpoint.every.is_some_and(|every| every == 0) {
+            errors.push(ValidationError::new("checkpoint.every", "checkpoint.every must be greater than 0"));
+        }
+        if let Some(target) = &checkpoint.s3 {
+            if cfg!(not(feature = "s3")) {
+                errors.push(ValidationError::new("checkpoint.s3", "checkpoint.s3 requires building with the \"s3\" feature"));
+            }
+            if target.bucket.is_empty() { errors.push(ValidationError::new("checkpoint.s3.bucket", "checkpoint.s3.bucket must not be empty")); }
+            if target.key.is_empty() { errors.push(ValidationError::new("checkpoint.s3.key", "checkpoint.s3.key must not be empty")); }
+        }
+        // The checkpoint keeps neither the results of earlier invocations nor the S3 object they went to
+        if request.return_items || request.returns == Returns::Items {
+            errors.push(ValidationError::new("checkpoint", "checkpoint does not support return \"items\"; use return \"summary\" or \"checksum\", with persist or output_firehose for the results"));
+        }
+        if request.output_s3.is_some() { errors.push(ValidationError::new("checkpoint", "checkpoint cannot be combined with output_s3")); }
+        if request.continuation.is_some() { errors.push(ValidationError::new("checkpoint", "checkpoint cannot be combined with continuation")); }
+        if benchmark { errors.push(ValidationError::new("checkpoint", format!("{} mode does not support checkpoint", mode))); }
+    }
+    if let Some(target) = &request.spill_s3 {
+        if cfg!(not(feature = "s3")) {
+            errors.push(ValidationError::new("spill_s3", "spill_s3 requires building with the \"s3\" feature"));
+        }
+        if target.bucket.is_empty() { errors.push(ValidationError::new("spill_s3.bucket", "spill_s3.bucket must not be empty")); }
+        if target.key.is_empty() { errors.push(ValidationError::new("spill_s3.key", "spill_s3.key must not be empty")); }
+        if benchmark { errors.push(ValidationError::new("spill_s3", format!("{} mode does not support spill_s3", mode))); }
+        if !request.return_items && request.returns != Returns::Items {
+            errors.push(ValidationError::new("spill_s3", "spill_s3 requires return \"items\""));
+        }
+        if request.output_s3.is_some() { errors.push(ValidationError::new("spill_s3", "spill_s3 cannot be combined with output_s3")); }
+    }
+    if request.compress_response {
+        if cfg!(not(feature = "compress")) {
+            errors.push(ValidationError::new("compress_response", "compress_response requires building with the \"compress\" feature"));
+        }
+        if benchmark { errors.push(ValidationError::new("compress_response", format!("{} mode does not support compress_response", mode))); }
+    }
+    if request.profile {
+        if cfg!(not(feature = "profiling")) {
+            errors.push(ValidationError::new("profile", "profile requires building with the \"profiling\" feature"));
+        }
+        if benchmark { errors.push(ValidationError::new("profile", format!("{} mode does not support profile", mode))); }
+    } else {
+        if request.profile_format.is_some() { errors.push(ValidationError::new("profile_format", "profile_format requires profile: true")); }
+        if request.profile_s3.is_some() { errors.push(ValidationError::new("profile_s3", "profile_s3 requires profile: true")); }
+    }
+    if let Some(target) = &request.profile_s3 {
+        if cfg!(not(feature = "s3")) {
+            errors.push(ValidationError::new("profile_s3", "profile_s3 requires building with the \"s3\" feature"));
+        }
+        if target.bucket.is_empty() { errors.push(ValidationError::new("profile_s3.bucket", "profile_s3.bucket must not be empty")); }
+        if target.key.is_empty() { errors.push(ValidationError::new("profile_s3.key", "profile_s3.key must not be empty")); }
+    }
+    // s3_checksum always reads its items from S3; image_resize and csv_to_parquet do when source_s3 is
+    // set (their object_source)
+    match (&request.source_s3, executor.map_or(ObjectSource::Unused, |executor| executor.object_source())) {
+        (Some(source), ObjectSource::Optional | ObjectSource::Required) => {
+            if cfg!(not(feature = "s3")) {
+                errors.push(ValidationError::new("source_s3", "source_s3 requires building with the \"s3\" feature"));
+            }
+            if source.bucket.is_empty() { errors.push(ValidationError::new("source_s3.bucket", "source_s3.bucket must not be empty")); }
+            if request.items.is_none() { errors.push(ValidationError::new("items", "source_s3 requires the object keys in items")); }
+        }
+        (None, ObjectSource::Required) => errors.push(ValidationError::new("source_s3", format!("{} requires source_s3 with the bucket to read", request.workload))),
+        (Some(_), _) if executor.is_some() => errors.push(ValidationError::new("source_s3", format!("source_s3 is not used by the {} workload", request.workload))),
+        _ => {}
+    }
+    match request.idempotency_key.as_deref().map(str::len) {
+        Some(0) => errors.push(ValidationError::new("idempotency_key", "idempotency_key must not be empty")),
+        Some(len) if len > MAX_IDEMPOTENCY_KEY_LEN => errors.push(ValidationError::new(
+            "idempotency_key", format!("idempotency_key is {} bytes, at most {} are allowed", len, MAX_IDEMPOTENCY_KEY_LEN),
+        )),
+        _ => {}
+    }
+    if request.idempotency_key.is_some() && (benchmark || streaming) {
+        errors.push(ValidationError::new("idempotency_key", "idempotency_key is not supported with benchmark, sweep or stress mode or response streaming"));
+    }
+    if let Some(retry) = &request.retry {
+        if !RETRY_ATTEMPTS_RANGE.contains(&retry.max_attempts) {
+            errors.push(ValidationError::new("retry.max_attempts", format!(
+                "retry.max_attempts {} is outside the allowed range {}-{}", retry.max_attempts, RETRY_ATTEMPTS_RANGE.start(), RETRY_ATTEMPTS_RANGE.end(),
+            )));
+        }
+        if retry.base_delay_ms > MAX_RETRY_BASE_DELAY_MS {
+            errors.push(ValidationError::new("retry.base_delay_ms", format!("retry.base_delay_ms must be at most {}", MAX_RETRY_BASE_DELAY_MS)));
+        }
+        if benchmark { errors.push(ValidationError::new("retry", format!("{} mode does not support retry", mode))); }
+    }
+    if benchmark && request.on_error == OnError::Continue {
+        errors.push(ValidationError::new("on_error", format!("{} mode does not support on_error \"continue\"", mode)));
+    }
+    if benchmark && streaming { errors.push(ValidationError::new("mode", format!("{} mode does not support streaming", mode))); }
+
+    match workload {
+        Some(workload) if errors.is_empty() => Ok(Validated { workload, params, generator, total, offset, count }),
+        _ => Err(HandlerError::Validation(errors)),
+    }
+}
+
+// Largest batch one invocation accepts: MAX_ITEMS, further limited by the memory estimate
+#[derive(Serialize)]
+struct ItemLimit {
+    limit: usize,
+    max_items: usize,                   // MAX_ITEMS env var (default 1000)
+    memory_limit_items: Option<usize>,  // None outside Lambda (memory size unknown)
+    per_item_bytes: u64,
+    working_set_bytes: u64,             // workload memory per worker x workers
+    available_bytes: Option<u64>,       // function memory minus current RSS
+}
+
+impl ItemLimit {
+    // `item_bytes`: the length of each generated item, held on top of the bookkeeping estimate
+    fn compute(workload: Option<&dyn Workload>, workers: usize, item_bytes: u64) -> Self {
+        let per_item_bytes = ESTIMATED_ITEM_BYTES + item_bytes;
+        let max_items = max_items();
+        let working_set_bytes = (workload.map_or(0, |workload| workload.working_set_bytes()) * workers) as u64;
+        let available_bytes = env::var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE").ok()
+            .and_then(|mb| mb.parse::<u64>().ok())
+            .map(|mb| (mb * 1024 * 1024).saturating_sub(MemoryStats::capture().rss_kb * 1024));
+        let memory_limit_items = available_bytes.map(|available| {
+            let budget = (available as f64 * MEMORY_HEADROOM) as u64;
+            (budget.saturating_sub(working_set_bytes) / per_item_bytes) as usize
+        });
+        ItemLimit {
+            limit: memory_limit_items.map_or(max_items, |items| items.min(max_items)),
+            max_items, memory_limit_items, per_item_bytes, working_set_bytes, available_bytes,
+        }
+    }
+
+    // Reject `requested` items in `field` above the limit, explaining which bound applied
+    fn check(&self, field: &str, requested: usize) -> Option<ValidationError> {
+        if requested <= self.limit { return None; }
+        let reason = match (self.memory_limit_items, self.available_bytes) {
+            (Some(items), Some(available)) if items < self.max_items => format!(
+                "memory estimate: {} bytes per item plus a {:.1} MiB working set must fit in half of the {:.1} MiB available",
+                self.per_item_bytes, self.working_set_bytes as f64 / 1048576.0, available as f64 / 1048576.0,
+            ),
+            _ => format!("MAX_ITEMS={}", self.max_items),
+        };
+        Some(ValidationError {
+            details: serde_json::to_value(self).ok(),
+            ..ValidationError::new(field, format!("{} of {} exceeds the limit of {} items ({})", field, requested, self.limit, reason))
+        })
+    }
+}
+
+// The bounds validate enforces, as mode "describe" reports them; `items` is the cap for `workers`
+// before a workload's working set or item_length is counted, so a request may get a lower one
+#[derive(Serialize)]
+pub struct Limits {
+    items: ItemLimit,
+    max_continuation_items: usize,
+    max_request_workers: usize,
+    max_batch_jobs: usize,
+    max_sweep_points: usize,
+    max_permits: usize,
+    max_idempotency_key_len: usize,
+    max_template_len: usize,
+    max_patterns: usize,
+    stress_duration_seconds: Bounds,
+    time_budget_ms: Bounds,
+    item_length: Bounds,
+    retry_max_attempts: Bounds,
+    max_retry_base_delay_ms: u64,
+    params: serde_json::Map<String, serde_json::Value>,  // {"cost": {"min": 4, "max": 14}, ...}
+}
+
+#[derive(Serialize)]
+struct Bounds {
+    min: u64,
+    max: u64,
+}
+
+impl Bounds {
+    fn of<T: Copy + Into<u64>>(range: &std::ops::RangeInclusive<T>) -> Self {
+        Bounds { min: (*range.start()).into(), max: (*range.end()).into() }
+    }
+}
+
+pub fn limits(workers: usize) -> Limits {
+    Limits {
+        items: ItemLimit::compute(None, workers, 0),
+        max_continuation_items: MAX_CONTINUATION_ITEMS,
+        max_request_workers: MAX_REQUEST_WORKERS,
+        max_batch_jobs: crate::handler::MAX_BATCH_JOBS,
+        max_sweep_points: MAX_SWEEP_POINTS,
+        max_permits: MAX_PERMITS,
+        max_idempotency_key_len: MAX_IDEMPOTENCY_KEY_LEN,
+        max_template_len: MAX_TEMPLATE_LEN,
+        max_patterns: MAX_PATTERNS,
+        stress_duration_seconds: Bounds::of(&STRESS_DURATION_RANGE),
+        time_budget_ms: Bounds::of(&TIME_BUDGET_MS_RANGE),
+        item_length: Bounds { min: *ITEM_LENGTH_RANGE.start() as u64, max: *ITEM_LENGTH_RANGE.end() as u64 },
+        retry_max_attempts: Bounds::of(&RETRY_ATTEMPTS_RANGE),
+        max_retry_base_delay_ms: MAX_RETRY_BASE_DELAY_MS,
+        params: PARAM_RANGES.iter().map(|(field, range)| (field.to_string(), serde_json::to_value(Bounds::of(range)).unwrap_or_default())).collect(),
+    }
+}
+
+// Configured item cap from MAX_ITEMS (default 1000); the memory estimate may lower it further
+pub fn max_items() -> usize {
+    crate::config::var("MAX_ITEMS")
+        .and_then(|max| max.parse::<usize>().ok())
+        .map_or(DEFAULT_MAX_ITEMS, |max| max.max(1))
+}
@@ -0,0 +1,870 @@
+use argon2::password_hash::{self, SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::sync::Arc;
+use crate::handler::ProcessRequest;
+use crate::registry::{self, register_workload, ObjectSource, Objects, WorkloadExecutor};
+use crate::validation::ValidationError;
+
+/// bcrypt cost bounds accepted from requests (each step doubles the per-item CPU cost)
+pub const BCRYPT_COST_RANGE: std::ops::RangeInclusive<u32> = 4..=14;
+const DEFAULT_BCRYPT_COST: u32 = 10;
+/// Argon2id bounds accepted from requests; memory is per item, so peak usage is memory_kib x workers
+pub const ARGON2_MEMORY_KIB_RANGE: std::ops::RangeInclusive<u32> = 8..=262_144;
+pub const ARGON2_ITERATIONS_RANGE: std::ops::RangeInclusive<u32> = 1..=10;
+pub const ARGON2_LANES_RANGE: std::ops::RangeInclusive<u32> = 1..=8;
+/// PBKDF2-HMAC-SHA256 rounds accepted from requests (cost grows linearly)
+pub const PBKDF2_ROUNDS_RANGE: std::ops::RangeInclusive<u32> = 1_000..=10_000_000;
+/// scrypt bounds accepted from requests; memory per item is 128 x r x 2^log_n bytes
+pub const SCRYPT_LOG_N_RANGE: std::ops::RangeInclusive<u32> = 1..=20;
+pub const SCRYPT_R_RANGE: std::ops::RangeInclusive<u32> = 1..=32;
+pub const SCRYPT_P_RANGE: std::ops::RangeInclusive<u32> = 1..=16;
+/// Longest thumbnail edge in pixels accepted from requests (image_resize)
+pub const MAX_DIMENSION_RANGE: std::ops::RangeInclusive<u32> = 16..=4096;
+const DEFAULT_MAX_DIMENSION: u32 = 256;
+/// Size of the buffer each compress item compresses, in KiB
+pub const BUFFER_KIB_RANGE: std::ops::RangeInclusive<u32> = 1..=16_384;
+const DEFAULT_BUFFER_KIB: u32 = 1_024;
+/// Random points per monte_carlo item
+pub const SAMPLES_RANGE: std::ops::RangeInclusive<u32> = 1_000..=100_000_000;
+const DEFAULT_SAMPLES: u32 = 1_000_000;
+/// Largest bit length of the smaller prime in factorize semiprimes (trial division cost ~ 2^bits)
+pub const FACTOR_BITS_RANGE: std::ops::RangeInclusive<u32> = 8..=31;
+const DEFAULT_FACTOR_BITS: u32 = 28;
+// Rounds of chained SHA-256 per item
+const SHA256_ROUNDS: usize = 100_000;
+/// Square matrix dimension N accepted from requests (matmul / matrix_multiply); memory is 3 x N^2 f64 per item
+pub const MATRIX_SIZE_RANGE: std::ops::RangeInclusive<u32> = 16..=2048;
+const DEFAULT_MATRIX_SIZE: u32 = 256;
+// grep: the bundled corpus (512 KiB of application log lines), the shards an item selects one of,
+// and the patterns searched when the request names none
+const CORPUS: &str = include_str!("../data/grep_corpus.log");
+const CORPUS_SHARDS: usize = 8;
+pub const MAX_PATTERNS: usize = 16;
+const DEFAULT_PATTERNS: &[&str] = &[r"status=5\d\d", r"path=/api/(orders|cart)", r"WARN .* duration_ms=8\d\d"];
+// Number of records in each generated JSON document
+const JSON_RECORDS: usize = 2_000;
+// Records in json_transform's built-in document, and its default mapping (output field, source pointer)
+const TRANSFORM_RECORDS: usize = 100;
+const DEFAULT_MAPPING: &[(&str, &str)] = &[
+    ("id", "/id"), ("customer", "/user/name"), ("city", "/user/address/city"), ("total", "/order/total"),
+];
+
+/// Tunable workload parameters, resolved from the request with env/built-in fallbacks
+pub struct WorkloadParams {
+    pub bcrypt_cost: u32,
+    pub argon2: Argon2Settings,
+    pub pbkdf2: Pbkdf2Settings,
+    pub scrypt: ScryptSettings,
+    pub max_dimension: u32,
+    pub mapping: JsonMapping,
+    pub compress: CompressSettings,
+    pub samples: u32,
+    pub factor_bits: u32,
+    pub matrix_size: u32,
+    pub patterns: Vec<String>,
+    pub salts: u32,
+}
+
+impl Default for WorkloadParams {
+    fn default() -> Self {
+        WorkloadParams {
+            bcrypt_cost: default_bcrypt_cost(), argon2: Argon2Settings::default(),
+            pbkdf2: Pbkdf2Settings::default(), scrypt: ScryptSettings::default(),
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            mapping: JsonMapping::default(),
+            compress: CompressSettings::default(),
+            samples: DEFAULT_SAMPLES,
+            factor_bits: DEFAULT_FACTOR_BITS,
+            matrix_size: DEFAULT_MATRIX_SIZE,
+            patterns: DEFAULT_PATTERNS.iter().map(|pattern| pattern.to_string()).collect(),
+            salts: crate::nested::DEFAULT_SALTS,
+        }
+    }
+}
+
+impl WorkloadParams {
+    /// Only the parameters the named workload actually uses
+    pub fn tuned(&self, workload: &str) -> TunedParams {
+        TunedParams {
+            cost: (workload == "bcrypt").then_some(self.bcrypt_cost),
+            argon2: (workload == "argon2").then_some(self.argon2),
+            pbkdf2: (workload == "pbkdf2").then_some(self.pbkdf2),
+            scrypt: (workload == "scrypt").then_some(self.scrypt),
+            max_dimension: (workload == "image_resize").then_some(self.max_dimension),
+            mapping: (workload == "json_transform").then(|| self.mapping.clone()),
+            compress: (workload == "compress").then_some(self.compress),
+            samples: (workload == "monte_carlo").then_some(self.samples),
+            factor_bits: (workload == "factorize").then_some(self.factor_bits),
+            matrix_size: matches!(workload, "matmul" | "matrix_multiply").then_some(self.matrix_size),
+            patterns: (workload == "grep").then(|| self.patterns.clone()),
+            salts: (workload == "nested_hash").then_some(self.salts),
+        }
+    }
+}
+
+/// The selected workload's parameters: echoed in responses, and part of every key that compares
+/// runs (result cache, sequential baseline, auto mode's per-item cost)
+#[derive(Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct TunedParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub argon2: Option<Argon2Settings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pbkdf2: Option<Pbkdf2Settings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scrypt: Option<ScryptSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_dimension: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<JsonMapping>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress: Option<CompressSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samples: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub factor_bits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patterns: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salts: Option<u32>,
+}
+
+/// json_transform's projection: output field names, in order, each with the JSON Pointer
+/// (RFC 6901, e.g. "/user/name") of the source value; serialized as an object like the request's
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct JsonMapping(pub Vec<(String, String)>);
+
+impl Default for JsonMapping {
+    fn default() -> Self {
+        JsonMapping(DEFAULT_MAPPING.iter().map(|(field, pointer)| (field.to_string(), pointer.to_string())).collect())
+    }
+}
+
+impl Serialize for JsonMapping {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(field, pointer)| (field, pointer)))
+    }
+}
+
+/// Argon2id cost parameters (memory in KiB per hash, passes over memory, lanes)
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Argon2Settings {
+    pub memory_kib: u32, pub iterations: u32, pub lanes: u32,
+}
+
+// The argon2 crate's defaults (19 MiB, 2 passes, 1 lane), as recommended by OWASP
+impl Default for Argon2Settings {
+    fn default() -> Self {
+        Argon2Settings { memory_kib: Params::DEFAULT_M_COST, iterations: Params::DEFAULT_T_COST, lanes: Params::DEFAULT_P_COST }
+    }
+}
+
+/// PBKDF2-HMAC-SHA256 iteration count
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Pbkdf2Settings {
+    pub rounds: u32,
+}
+
+// 600,000 rounds, the pbkdf2 crate's default as recommended by OWASP
+impl Default for Pbkdf2Settings {
+    fn default() -> Self {
+        Pbkdf2Settings { rounds: pbkdf2::Params::RECOMMENDED_ROUNDS as u32 }
+    }
+}
+
+/// scrypt cost parameters (CPU/memory cost 2^log_n, block size r, parallelization p)
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct ScryptSettings {
+    pub log_n: u8, pub r: u32, pub p: u32,
+}
+
+impl ScryptSettings {
+    pub fn params(&self) -> Result<scrypt::Params, scrypt::errors::InvalidParams> {
+        scrypt::Params::new(self.log_n, self.r, self.p, scrypt::Params::RECOMMENDED_LEN)
+    }
+}
+
+// The scrypt crate's defaults (log_n 17, r 8, p 1: 128 MiB per hash), as recommended by OWASP
+impl Default for ScryptSettings {
+    fn default() -> Self {
+        use scrypt::Params;
+        ScryptSettings { log_n: Params::RECOMMENDED_LOG_N, r: Params::RECOMMENDED_R, p: Params::RECOMMENDED_P }
+    }
+}
+
+/// Codec of the compress workload; request names are lowercase
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+impl Codec {
+    pub fn as_str(self) -> &'static str {
+        match self { Codec::Gzip => "gzip", Codec::Zstd => "zstd", Codec::Brotli => "brotli" }
+    }
+
+    /// Levels each codec accepts, and the default: the library's own default, or a common web
+    /// setting for brotli, whose default (11) is meant for offline compression
+    pub fn levels(self) -> std::ops::RangeInclusive<u32> {
+        match self { Codec::Gzip => 0..=9, Codec::Zstd => 1..=22, Codec::Brotli => 0..=11 }
+    }
+
+    pub fn default_level(self) -> u32 {
+        match self { Codec::Gzip => 6, Codec::Zstd => 3, Codec::Brotli => 6 }
+    }
+}
+
+/// compress workload parameters (codec, its level, and the size of each item's buffer)
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct CompressSettings {
+    pub codec: Codec, pub level: u32, pub buffer_kib: u32,
+}
+
+impl Default for CompressSettings {
+    fn default() -> Self {
+        CompressSettings { codec: Codec::Zstd, level: Codec::Zstd.default_level(), buffer_kib: DEFAULT_BUFFER_KIB }
+    }
+}
+
+/// Default bcrypt cost from BCRYPT_COST env var, clamped to the accepted range
+pub fn default_bcrypt_cost() -> u32 {
+    crate::config::var("BCRYPT_COST")
+        .and_then(|cost| cost.parse::<u32>().ok())
+        .map(|cost| cost.clamp(*BCRYPT_COST_RANGE.start(), *BCRYPT_COST_RANGE.end()))
+        .unwrap_or(DEFAULT_BCRYPT_COST)
+}
+
+/// A CPU-bound unit of work applied independently to every item, built for a request by the
+/// workload's WorkloadExecutor (registry.rs)
+pub trait Workload: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
+
+    // execute_item() on a binary item (items_base64). Text workloads see the bytes as UTF-8, checked in
+    // place without a copy; the ones that only hash bytes override this to take any bytes
+    fn execute_bytes(&self, item: &[u8]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_item(std::str::from_utf8(item).map_err(|e| format!("{} needs UTF-8 items: {}", self.name(), e))?)
+    }
+
+    // Bytes of input behind `item`, for payload_bytes_total; differs when items are keys to data
+    fn payload_len(&self, item: &str) -> usize { item.len() }
+
+    // Memory one in-flight item needs while executing, for the pre-flight memory estimate
+    fn working_set_bytes(&self) -> usize { 0 }
+
+    // Check that `output` is what execute_item() produces for `item` (pipeline mode)
+    // Deterministic workloads recompute; salted hashes override this with a real verifier
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.execute_item(item)? == output)
+    }
+}
+
+/// Look up a workload by name, returning None for unknown names
+/// s3_checksum is left out: it needs its objects downloaded first (see ObjectSha256)
+/// Shared via Arc so it can be moved into 'static tasks (tokio spawn_blocking)
+pub fn get_workload(name: &str, params: &WorkloadParams) -> Option<Arc<dyn Workload>> {
+    registry::find(name).filter(|executor| executor.object_source() != ObjectSource::Required)?.prepare(params, None)
+}
+
+// CPU-intensive bcrypt hashing with a configurable cost factor
+struct Bcrypt {
+    cost: u32,
+}
+
+impl Workload for Bcrypt {
+    fn name(&self) -> &'static str { "bcrypt" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(bcrypt::hash(item, self.cost)?)
+    }
+
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(bcrypt::verify(item, output)?)
+    }
+}
+
+struct BcryptExecutor;
+
+impl WorkloadExecutor for BcryptExecutor {
+    fn name(&self) -> &'static str { "bcrypt" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Bcrypt { cost: params.bcrypt_cost }))
+    }
+}
+
+register_workload!(BcryptExecutor);
+
+/// Check (password, hash) pairs with bcrypt::verify, for `operation: "verify"`; the cost comes from
+/// each hash. Items are `"<hash>:<password>"` (bcrypt hashes never contain ':'), built by run_request
+/// from the request's pairs, and the output is "true" or "false"
+pub struct BcryptVerify;
+
+impl Workload for BcryptVerify {
+    fn name(&self) -> &'static str { "bcrypt_verify" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let (hash, password) = item.split_once(':').ok_or("verify item is not a <hash>:<password> pair")?;
+        Ok(bcrypt::verify(password, hash)?.to_string())
+    }
+}
+
+// Chained SHA-256 digests: pure compute, no allocation in the hot loop
+struct Sha256Chain;
+
+impl Workload for Sha256Chain {
+    fn name(&self) -> &'static str { "sha256" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_bytes(item.as_bytes())
+    }
+
+    fn execute_bytes(&self, item: &[u8]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut digest = Sha256::digest(item);
+        for _ in 1..SHA256_ROUNDS {
+            digest = Sha256::digest(digest);
+        }
+        Ok(format!("{:x}", digest))
+    }
+}
+
+struct Sha256Executor;
+
+impl WorkloadExecutor for Sha256Executor {
+    fn name(&self) -> &'static str { "sha256" }
+
+    fn prepare(&self, _params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Sha256Chain))
+    }
+}
+
+register_workload!(Sha256Executor);
+
+// Argon2id with tunable memory, iterations and lanes: CPU and memory-bandwidth bound
+// Lanes are computed sequentially within an item; parallelism comes from hashing items concurrently
+struct Argon2id {
+    settings: Argon2Settings,
+}
+
+impl Workload for Argon2id {
+    fn name(&self) -> &'static str { "argon2" }
+    fn working_set_bytes(&self) -> usize { self.settings.memory_kib as usize * 1024 }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let salt = random_salt()?;
+        let Argon2Settings { memory_kib, iterations, lanes } = self.settings;
+        let params = Params::new(memory_kib, iterations, lanes, None).map_err(|e| e.to_string())?;
+        let hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(item.as_bytes(), &salt)
+            .map_err(|e| e.to_string())?;
+        Ok(hash.to_string())
+    }
+
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        verify_phc(&Argon2::default(), item, output)
+    }
+}
+
+struct Argon2Executor;
+
+impl WorkloadExecutor for Argon2Executor {
+    fn name(&self) -> &'static str { "argon2" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Argon2id { settings: params.argon2 }))
+    }
+}
+
+register_workload!(Argon2Executor);
+
+// Estimate pi from `samples` random points in the unit square: pure compute with no allocation
+// and no shared state, the cleanest case for linear scaling. The generator is seeded from the
+// item, so every item is reproducible (pipeline mode recomputes it); the output is the estimate
+struct MonteCarloPi {
+    samples: u32,
+}
+
+impl Workload for MonteCarloPi {
+    fn name(&self) -> &'static str { "monte_carlo" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut state = seed(item);
+        let mut inside = 0u64;
+        for _ in 0..self.samples {
+            let (x, y) = (unit(splitmix64(&mut state)), unit(splitmix64(&mut state)));
+            inside += (x * x + y * y <= 1.0) as u64;
+        }
+        Ok(format!("{:.6}", 4.0 * inside as f64 / self.samples as f64))
+    }
+}
+
+struct MonteCarloExecutor;
+
+impl WorkloadExecutor for MonteCarloExecutor {
+    fn name(&self) -> &'static str { "monte_carlo" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(MonteCarloPi { samples: params.samples }))
+    }
+}
+
+register_workload!(MonteCarloExecutor);
+
+// Count the lines of one shard of the bundled corpus matching each pattern, all of them in one
+// pass (a RegexSet), outputting the shard and the count per pattern ("3:12,0,307"). The item
+// selects the shard (its FNV-1a hash modulo CORPUS_SHARDS), so different items scan different
+// lines. Within the item the shard's lines are split across the pool with par_lines; outside a
+// Rayon worker (sequential and the OS-thread modes) they are scanned in a plain loop instead of
+// being handed to the global pool. The compiled set is shared by all workers: regex matching is
+// thread-safe, with per-thread scratch space taken from an internal pool
+struct Grep {
+    patterns: regex::RegexSet,
+}
+
+// The corpus cut into CORPUS_SHARDS line-aligned pieces of about the same size
+static SHARDS: std::sync::OnceLock<Vec<&'static str>> = std::sync::OnceLock::new();
+
+fn corpus_shards() -> &'static [&'static str] {
+    SHARDS.get_or_init(|| {
+        let mut shards = Vec::with_capacity(CORPUS_SHARDS);
+        let mut rest = CORPUS;
+        for remaining in (1..=CORPUS_SHARDS).rev() {
+            let target = rest.len().div_ceil(remaining);
+            let end = rest[target..].find('\n').map_or(rest.len(), |newline| target + newline + 1);
+            let (shard, tail) = rest.split_at(end);
+            shards.push(shard);
+            rest = tail;
+        }
+        shards
+    })
+}
+
+impl Grep {
+    fn count(&self, counts: &mut [usize], line: &str) {
+        for pattern in self.patterns.matches(line).iter() { counts[pattern] += 1; }
+    }
+}
+
+impl Workload for Grep {
+    fn name(&self) -> &'static str { "grep" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let index = (seed(item) % CORPUS_SHARDS as u64) as usize;
+        let shard = corpus_shards()[index];
+        let patterns = self.patterns.len();
+        let counts = if rayon::current_thread_index().is_some() {
+            shard.par_lines()
+                .fold(|| vec![0usize; patterns], |mut counts, line| { self.count(&mut counts, line); counts })
+                .reduce(|| vec![0usize; patterns], |mut a, b| { a.iter_mut().zip(b).for_each(|(a, b)| *a += b); a })
+        } else {
+            let mut counts = vec![0usize; patterns];
+            for line in shard.lines() { self.count(&mut counts, line); }
+            counts
+        };
+        Ok(format!("{}:{}", index, counts.iter().map(usize::to_string).collect::<Vec<_>>().join(",")))
+    }
+}
+
+struct GrepExecutor;
+
+impl WorkloadExecutor for GrepExecutor {
+    fn name(&self) -> &'static str { "grep" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        // Patterns are checked by validation; one that fails to compile here makes the name unknown
+        regex::RegexSet::new(&params.patterns).ok().map(|patterns| Arc::new(Grep { patterns }) as Arc<dyn Workload>)
+    }
+}
+
+register_workload!(GrepExecutor);
+
+/// Synthetic application log lines (the compress and simd_hash buffers; data/grep_corpus.log was
+/// written with it): repetitive structure with varying fields, which compresses like real logs
+/// (ratios around 4x at default levels) instead of trivially (zeros) or not at all (random)
+pub fn log_lines(size: usize) -> Vec<u8> {
+    const LEVELS: [&str; 4] = ["INFO", "INFO", "WARN", "DEBUG"];
+    const PATHS: [&str; 5] = ["/api/orders", "/api/users", "/health", "/api/cart/items", "/login"];
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut buffer = Vec::with_capacity(size + 160);
+    while buffer.len() < size {
+        // xorshift64: deterministic, so every container generates the same bytes
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let _ = writeln!(
+            buffer, "2024-05-{:02}T{:02}:{:02}:{:02}.{:03}Z {} request_id={:016x} method=GET path={} status={} duration_ms={} bytes={}",
+            1 + state % 28, state % 24, (state >> 8) % 60, (state >> 16) % 60, (state >> 24) % 1000,
+            LEVELS[(state >> 32) as usize % LEVELS.len()], state, PATHS[(state >> 40) as usize % PATHS.len()],
+            [200, 200, 200, 304, 404, 500][(state >> 44) as usize % 6], (state >> 48) % 900, (state >> 20) % 65_536,
+        );
+    }
+    buffer.truncate(size);
+    buffer
+}
+
+// Factor a semiprime p x q derived from the item by trial division, where p has between 8 and
+// factor_bits bits (uniformly per item): an item's cost is proportional to p, so costs differ by
+// up to 2^(factor_bits - 8) between items. Skewed on purpose, to compare Rayon's work stealing
+// with static partitioning (std_threads). The output is "<n>=<p>*<q>"
+struct Factorize {
+    factor_bits: u32,
+}
+
+impl Workload for Factorize {
+    fn name(&self) -> &'static str { "factorize" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut state = seed(item);
+        let bits = 8 + (splitmix64(&mut state) % (self.factor_bits as u64 - 7)) as u32;
+        // q is larger than p, and n stays below 2^63
+        let p = random_prime(bits, &mut state);
+        let q = random_prime(63 - bits, &mut state);
+        let n = p * q;
+        let smallest = (3..).step_by(2).take_while(|d| d * d <= n).find(|d| n.is_multiple_of(*d)).unwrap_or(n);
+        Ok(format!("{}={}*{}", n, smallest, n / smallest))
+    }
+}
+
+struct FactorizeExecutor;
+
+impl WorkloadExecutor for FactorizeExecutor {
+    fn name(&self) -> &'static str { "factorize" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Factorize { factor_bits: params.factor_bits }))
+    }
+}
+
+register_workload!(FactorizeExecutor);
+
+// Smallest prime at or above a random odd number of exactly `bits` bits
+fn random_prime(bits: u32, state: &mut u64) -> u64 {
+    let mut candidate = (1 << (bits - 1)) | (splitmix64(state) & ((1 << (bits - 1)) - 1)) | 1;
+    while !is_prime(candidate) { candidate += 2; }
+    candidate
+}
+
+// Deterministic Miller-Rabin for 64-bit integers (these twelve bases cover every n < 2^64)
+fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 { return false; }
+    if let Some(&base) = BASES.iter().find(|&&base| n.is_multiple_of(base)) { return n == base; }
+    let mul = |a: u64, b: u64| (a as u128 * b as u128 % n as u128) as u64;
+    let pow = |mut base: u64, mut exp: u64| {
+        let mut result = 1;
+        while exp > 0 {
+            if exp & 1 == 1 { result = mul(result, base); }
+            base = mul(base, base);
+            exp >>= 1;
+        }
+        result
+    };
+    let (d, r) = ((n - 1) >> (n - 1).trailing_zeros(), (n - 1).trailing_zeros());
+    BASES.iter().all(|&base| {
+        let mut x = pow(base, d);
+        if x == 1 || x == n - 1 { return true; }
+        (1..r).any(|_| { x = mul(x, x); x == n - 1 })
+    })
+}
+
+// FNV-1a of the item: a stable per-item seed for the simulation workloads
+fn seed(item: &str) -> u64 {
+    item.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// SplitMix64: a tiny, fast generator with good statistical quality for simulation (not crypto)
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Top 53 bits as a float in [0, 1)
+fn unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Parse a JSON document, project and rename fields per the mapping, and re-serialize: parse and
+// allocation heavy. An array maps every element; an object maps itself. Items that are not JSON
+// (such as the generated ones) transform a built-in array of records. The output is the
+// transformed document; source pointers that match nothing become null
+struct JsonTransform {
+    mapping: JsonMapping,
+}
+
+// Serialized once per container, so items only pay for parsing, mapping and serializing
+static TRANSFORM_DOCUMENT: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+impl Workload for JsonTransform {
+    fn name(&self) -> &'static str { "json_transform" }
+    // Parsed tree, the projected one and the output, roughly 1 KiB per built-in record
+    fn working_set_bytes(&self) -> usize { TRANSFORM_RECORDS * 1024 }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let document = match serde_json::from_str::<serde_json::Value>(item) {
+            Ok(document @ (serde_json::Value::Array(_) | serde_json::Value::Object(_))) => document,
+            _ => serde_json::from_str(transform_document())?,
+        };
+        let project = |record: &serde_json::Value| -> serde_json::Value {
+            self.mapping.0.iter()
+                .map(|(field, pointer)| (field.clone(), record.pointer(pointer).cloned().unwrap_or_default()))
+                .collect::<serde_json::Map<_, _>>()
+                .into()
+        };
+        let transformed = match &document {
+            serde_json::Value::Array(records) => records.iter().map(project).collect(),
+            record => project(record),
+        };
+        Ok(serde_json::to_string(&transformed)?)
+    }
+}
+
+struct JsonTransformExecutor;
+
+impl WorkloadExecutor for JsonTransformExecutor {
+    fn name(&self) -> &'static str { "json_transform" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(JsonTransform { mapping: params.mapping.clone() }))
+    }
+}
+
+register_workload!(JsonTransformExecutor);
+
+fn transform_document() -> &'static str {
+    const CITIES: [&str; 4] = ["Seattle", "Dublin", "Tokyo", "Sydney"];
+    TRANSFORM_DOCUMENT.get_or_init(|| {
+        let records: Vec<serde_json::Value> = (0..TRANSFORM_RECORDS)
+            .map(|i| serde_json::json!({
+                "id": i,
+                "user": { "name": format!("customer_{:04}", i), "email": format!("customer_{:04}@example.com", i),
+                          "address": { "city": CITIES[i % CITIES.len()], "zip": format!("{:05}", i * 37) } },
+                "order": { "total": (i * 7919 % 100_000) as f64 / 100.0, "items": [i % 5, i % 7, i % 11], "paid": i % 3 != 0 },
+                "tags": ["a", "b", "c"],
+            }))
+            .collect();
+        serde_json::to_string(&records).expect("the built-in document serializes")
+    })
+}
+
+// PBKDF2-HMAC-SHA256 with a tunable round count: pure CPU, negligible memory
+struct Pbkdf2Sha256 {
+    settings: Pbkdf2Settings,
+}
+
+impl Workload for Pbkdf2Sha256 {
+    fn name(&self) -> &'static str { "pbkdf2" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let salt = random_salt()?;
+        let params = pbkdf2::Params { rounds: self.settings.rounds, output_length: 32 };
+        let hash = pbkdf2::Pbkdf2
+            .hash_password_customized(item.as_bytes(), Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()), None, params, &salt)
+            .map_err(|e| e.to_string())?;
+        Ok(hash.to_string())
+    }
+
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        verify_phc(&pbkdf2::Pbkdf2, item, output)
+    }
+}
+
+struct Pbkdf2Executor;
+
+impl WorkloadExecutor for Pbkdf2Executor {
+    fn name(&self) -> &'static str { "pbkdf2" }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Pbkdf2Sha256 { settings: params.pbkdf2 }))
+    }
+}
+
+register_workload!(Pbkdf2Executor);
+
+// scrypt with tunable cost, block size and parallelization: memory-hard like Argon2id
+// p > 1 repeats the memory-hard mix p times within the item (sequentially)
+struct Scrypt {
+    settings: ScryptSettings,
+}
+
+impl Workload for Scrypt {
+    fn name(&self) -> &'static str { "scrypt" }
+    fn working_set_bytes(&self) -> usize { 128 * self.settings.r as usize * (1usize << self.settings.log_n) }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let salt = random_salt()?;
+        let params = self.settings.params().map_err(|e| e.to_string())?;
+        let hash = scrypt::Scrypt
+            .hash_password_customized(item.as_bytes(), None, None, params, &salt)
+            .map_err(|e| e.to_string())?;
+        Ok(hash.to_string())
+    }
+
+    fn verify(&self, item: &str, output: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        verify_phc(&scrypt::Scrypt, item, output)
+    }
+}
+
+struct ScryptExecutor;
+
+impl WorkloadExecutor for ScryptExecutor {
+    fn name(&self) -> &'static str { "scrypt" }
+
+    // Same per-item ceiling as Argon2id's memory_kib
+    fn validate(&self, _request: &ProcessRequest, params: &WorkloadParams, errors: &mut Vec<ValidationError>) {
+        let scrypt = params.scrypt;
+        let memory_kib = 128 * scrypt.r as u64 * (1u64 << scrypt.log_n) / 1024;
+        if memory_kib > *ARGON2_MEMORY_KIB_RANGE.end() as u64 {
+            errors.push(ValidationError::new("log_n", format!("scrypt memory 128 x r x 2^log_n ({} KiB) exceeds {} KiB", memory_kib, ARGON2_MEMORY_KIB_RANGE.end())));
+        } else if scrypt.params().is_err() {
+            errors.push(ValidationError::new("log_n", format!("log_n must be less than 16 x r ({})", 16 * scrypt.r)));
+        }
+    }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Scrypt { settings: params.scrypt }))
+    }
+}
+
+register_workload!(ScryptExecutor);
+
+/// SHA-256 of object bodies fetched before processing (the s3_checksum workload), looked up by
+/// item; the output is the object's hex digest. Built with the "s3" feature only, which downloads
+/// the objects. I/O happens up front, so the workers only hash
+#[cfg(feature = "s3")]
+pub struct ObjectSha256 {
+    pub objects: Objects,
+}
+
+#[cfg(feature = "s3")]
+impl Workload for ObjectSha256 {
+    fn name(&self) -> &'static str { "s3_checksum" }
+    fn payload_len(&self, item: &str) -> usize { self.objects.get(item).map_or(0, Vec::len) }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let body = self.objects.get(item).ok_or_else(|| format!("object '{}' was not downloaded", item))?;
+        Ok(format!("{:x}", Sha256::digest(body)))
+    }
+}
+
+#[cfg(feature = "s3")]
+struct ObjectSha256Executor;
+
+#[cfg(feature = "s3")]
+impl WorkloadExecutor for ObjectSha256Executor {
+    fn name(&self) -> &'static str { "s3_checksum" }
+    fn object_source(&self) -> ObjectSource { ObjectSource::Required }
+
+    // Validation prepares it without objects, which are downloaded after it
+    fn prepare(&self, _params: &WorkloadParams, objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(ObjectSha256 { objects: objects.unwrap_or_default() }))
+    }
+}
+
+#[cfg(feature = "s3")]
+register_workload!(ObjectSha256Executor);
+
+// Fresh 16-byte salt per hash, as every real password store would use
+fn random_salt() -> Result<SaltString, Box<dyn std::error::Error + Send + Sync>> {
+    let mut salt_bytes = [0u8; 16];
+    getrandom::getrandom(&mut salt_bytes)?;
+    Ok(SaltString::encode_b64(&salt_bytes).map_err(|e| e.to_string())?)
+}
+
+// Check `item` against a PHC string; parameters and salt are read back from the string
+fn verify_phc(verifier: &dyn PasswordVerifier, item: &str, output: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let hash = PasswordHash::new(output).map_err(|e| e.to_string())?;
+    match verifier.verify_password(item.as_bytes(), &hash) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(e.to_string().into()),
+    }
+}
+
+// Dense f64 matrix product seeded from the item bytes: cache and memory-bandwidth bound
+// Larger N moves the working set out of L2 (N=256 is 1.5 MiB) and then out of the shared L3, so
+// scaling with workers stops at the memory bandwidth instead of the core count
+// "matmul" and "matrix_multiply" name the same workload; the response reports the requested name
+struct MatrixMultiply {
+    name: &'static str,
+    size: usize,
+}
+
+impl Workload for MatrixMultiply {
+    fn name(&self) -> &'static str { self.name }
+    fn working_set_bytes(&self) -> usize { 3 * self.size * self.size * std::mem::size_of::<f64>() }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let n = self.size;
+        let seed = item.bytes().fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
+        let a: Vec<f64> = (0..n * n).map(|i| ((seed + i as u64) % 97) as f64 / 97.0).collect();
+        let b: Vec<f64> = (0..n * n).map(|i| ((seed ^ i as u64) % 89) as f64 / 89.0).collect();
+        let mut c = vec![0.0f64; n * n];
+        for i in 0..n {
+            for k in 0..n {
+                let aik = a[i * n + k];
+                for j in 0..n {
+                    c[i * n + j] += aik * b[k * n + j];
+                }
+            }
+        }
+        let trace: f64 = (0..n).map(|i| c[i * n + i]).sum();
+        Ok(format!("{:.6}", trace))
+    }
+}
+
+// Registered once per name
+struct MatrixMultiplyExecutor(&'static str);
+
+impl WorkloadExecutor for MatrixMultiplyExecutor {
+    fn name(&self) -> &'static str { self.0 }
+
+    fn prepare(&self, params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(MatrixMultiply { name: self.0, size: params.matrix_size as usize }))
+    }
+}
+
+register_workload!(MatrixMultiplyExecutor("matmul"));
+register_workload!(MatrixMultiplyExecutor("matrix_multiply"));
+
+// Serialize and re-parse a generated JSON document: allocation heavy
+struct JsonParse;
+
+impl Workload for JsonParse {
+    fn name(&self) -> &'static str { "json_parse" }
+    // Two Value trees plus the serialized document, roughly 1 KiB per record
+    fn working_set_bytes(&self) -> usize { JSON_RECORDS * 1024 }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let records: Vec<serde_json::Value> = (0..JSON_RECORDS)
+            .map(|i| serde_json::json!({ "id": i, "name": item, "tags": ["a", "b", "c"], "score": i as f64 * 0.5 }))
+            .collect();
+        let document = serde_json::to_string(&records)?;
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&document)?;
+        Ok(format!("{}:{}", parsed.len(), document.len()))
+    }
+}
+
+struct JsonParseExecutor;
+
+impl WorkloadExecutor for JsonParseExecutor {
+    fn name(&self) -> &'static str { "json_parse" }
+
+    fn prepare(&self, _params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(JsonParse))
+    }
+}
+
+register_workload!(JsonParseExecutor);
@@ -0,0 +1,89 @@
+use serde_json::{json, Map, Value};
+use std::env;
+use std::net::UdpSocket;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The X-Ray daemon's UDP protocol: a JSON header line followed by one segment document
+const DAEMON_HEADER: &str = "{\"format\": \"json\", \"version\": 1}\n";
+const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:2000";
+
+// Phases of the current invocation, sent as X-Ray subsegments of the Lambda function segment.
+// Only set while the invocation is sampled (active tracing enabled), so untraced invocations
+// skip all of this
+static ACTIVE: Mutex<Option<Trace>> = Mutex::new(None);
+
+struct Trace {
+    trace_id: String,
+    parent_id: String,  // the function segment Lambda created for this invocation
+    phases: Vec<(&'static str, f64, f64)>,  // name, start and end in epoch seconds
+    annotations: Map<String, Value>,
+}
+
+// Start collecting for an invocation, given its trace header ("Root=...;Parent=...;Sampled=1")
+pub fn begin(trace_header: Option<&str>) {
+    let field = |name: &str| trace_header?.split(';').find_map(|part| part.trim().strip_prefix(name)?.strip_prefix('='));
+    let trace = match (field("Root"), field("Parent"), field("Sampled")) {
+        (Some(root), Some(parent), Some("1")) => Some(Trace {
+            trace_id: root.to_string(), parent_id: parent.to_string(), phases: Vec::new(), annotations: Map::new(),
+        }),
+        _ => None,
+    };
+    *ACTIVE.lock().unwrap() = trace;
+}
+
+pub fn record(name: &'static str, start: SystemTime, end: SystemTime) {
+    if let Some(trace) = ACTIVE.lock().unwrap().as_mut() {
+        trace.phases.push((name, epoch_secs(start), epoch_secs(end)));
+    }
+}
+
+// Indexed annotation added to every subsegment of the invocation (searchable in trace filters)
+pub fn annotate(key: &str, value: impl Into<Value>) {
+    if let Some(trace) = ACTIVE.lock().unwrap().as_mut() {
+        trace.annotations.insert(key.to_string(), value.into());
+    }
+}
+
+// Send the recorded phases to the X-Ray daemon and end the invocation's trace
+// Best effort: a failed send only costs the trace, never the invocation
+pub fn flush() {
+    let Some(trace) = ACTIVE.lock().unwrap().take() else { return };
+    let Ok(socket) = UdpSocket::bind("0.0.0.0:0") else { return };
+    let address = daemon_address();
+    for (name, start_time, end_time) in trace.phases {
+        let subsegment = json!({
+            "type": "subsegment",
+            "name": name,
+            "id": segment_id(),
+            "trace_id": trace.trace_id,
+            "parent_id": trace.parent_id,
+            "start_time": start_time,
+            "end_time": end_time,
+            "annotations": trace.annotations,
+        });
+        if let Err(e) = socket.send_to(format!("{}{}", DAEMON_HEADER, subsegment).as_bytes(), &address) {
+            tracing::debug!(error = %e, "failed to send X-Ray subsegment");
+        }
+    }
+}
+
+// AWS_XRAY_DAEMON_ADDRESS is "host:port" or "tcp:host:port udp:host:port"
+fn daemon_address() -> String {
+    let address = env::var("AWS_XRAY_DAEMON_ADDRESS").unwrap_or_else(|_| DEFAULT_DAEMON_ADDRESS.to_string());
+    let udp = address.split_whitespace().find_map(|part| part.strip_prefix("udp:"));
+    udp.or_else(|| address.split_whitespace().find(|part| !part.contains("tcp:")))
+        .unwrap_or(DEFAULT_DAEMON_ADDRESS)
+        .to_string()
+}
+
+// 64-bit random identifier as 16 hex digits
+fn segment_id() -> String {
+    let mut bytes = [0u8; 8];
+    let _ = getrandom::getrandom(&mut bytes);
+    format!("{:016x}", u64::from_be_bytes(bytes))
+}
+
+fn epoch_secs(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
@@ -0,0 +1,85 @@
+// Shared by the integration tests: fixtures are the events under tests/fixtures, the same files
+// `cargo lambda invoke --data-file` sends to a running function, and they are routed in-process
+// exactly as the Lambda entrypoint routes them, without the runtime
+#![allow(dead_code)]
+
+use lambda_runtime::Context;
+use serde_json::Value;
+use tokio::sync::OnceCell;
+use rust_multithread_lambda::error::HandlerError;
+use rust_multithread_lambda::{lifecycle, prewarm, router};
+
+static INIT: OnceCell<()> = OnceCell::const_new();
+
+// The fixture at tests/fixtures/<name>.json
+pub fn fixture(name: &str) -> Value {
+    let path = format!("{}/tests/fixtures/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    let body = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+    serde_json::from_str(&body).unwrap_or_else(|e| panic!("{} is not JSON: {}", path, e))
+}
+
+// Names of the fixtures in a directory of tests/fixtures, sorted
+pub fn fixtures(dir: &str) -> Vec<String> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), dir);
+    let mut names: Vec<String> = std::fs::read_dir(&path).unwrap_or_else(|e| panic!("cannot list {}: {}", path, e))
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok()?.strip_suffix(".json").map(|name| format!("{}/{}", dir, name)))
+        .collect();
+    names.sort();
+    names
+}
+
+// Route an event as one invocation, after the same cold start as the Lambda entrypoint (once per
+// test binary); a default Context has no deadline, so every item runs
+pub async fn invoke(payload: Value) -> Result<Value, HandlerError> {
+    invoke_with(payload, &Context::default()).await
+}
+
+pub async fn invoke_with(payload: Value, context: &Context) -> Result<Value, HandlerError> {
+    INIT.get_or_init(|| async {
+        lifecycle::mark_process_start();
+        prewarm::initialize().await.expect("init failed");
+    }).await;
+    lifecycle::begin_invocation();
+    router::route(payload, context).await
+}
+
+pub async fn invoke_fixture(name: &str) -> Value {
+    invoke(fixture(name)).await.unwrap_or_else(|e| panic!("{} failed: {}", name, e.to_json()))
+}
+
+pub async fn invoke_fixture_err(name: &str) -> HandlerError {
+    match invoke(fixture(name)).await {
+        Ok(response) => panic!("{} succeeded: {}", name, response),
+        Err(e) => e,
+    }
+}
+
+pub fn number(response: &Value, field: &str) -> f64 {
+    response[field].as_f64().unwrap_or_else(|| panic!("{} is not a number in {}", field, response))
+}
+
+// What every item-processing response must hold: the items run either completed or failed, the
+// latency percentiles are in order, and no phase outlasts the run
+pub fn assert_process_invariants(response: &Value) {
+    let (processed, completed) = (number(response, "processed"), number(response, "completed"));
+    let failed = response["failures"].as_array().map_or(0, Vec::len) as f64;
+    assert_eq!(completed + failed, processed, "completed and failed items do not add up in {}", response);
+    assert!(number(response, "threads_used") <= processed, "more threads than items in {}", response);
+
+    let latency = &response["latency"];
+    let percentiles: Vec<f64> = ["p50_ms", "p90_ms", "p99_ms", "max_ms"].iter().map(|field| number(latency, field)).collect();
+    assert!(percentiles.windows(2).all(|pair| pair[0] <= pair[1]), "latency percentiles out of order in {}", latency);
+    assert!(number(latency, "mean_ms") <= number(latency, "max_ms"), "mean above max in {}", latency);
+
+    // duration_ms is truncated to whole milliseconds
+    let duration_ms = number(response, "duration_ms") + 1.0;
+    let processing_ms = number(&response["phases"], "processing");
+    assert!(processing_ms <= duration_ms, "processing phase {} ms outlasts duration_ms in {}", processing_ms, response);
+    assert!(number(response, "init_duration_ms") <= number(&response["container_stats"], "lifetime_ms"), "init outlasts the container in {}", response);
+}
+
+// The field of every violation of a ValidationError
+pub fn violated_fields(error: &HandlerError) -> Vec<String> {
+    let body = error.to_json();
+    body["errors"].as_array().into_iter().flatten().filter_map(|error| error["field"].as_str().map(str::to_string)).collect()
+}
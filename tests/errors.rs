@@ -0,0 +1,137 @@
+// Rejected requests (tests/fixtures/invalid) and the limits of the handler: every error carries
+// the code Lambda reports as errorType, and a ValidationError names the fields at fault
+mod common;
+
+use common::{fixture, fixtures, invoke, invoke_fixture_err, invoke_with, violated_fields};
+use lambda_runtime::Context;
+use rust_multithread_lambda::error::ValidationError;
+use rust_multithread_lambda::handler::ProcessRequest;
+use rust_multithread_lambda::register_workload;
+use rust_multithread_lambda::registry::{Objects, WorkloadExecutor};
+use rust_multithread_lambda::workload::{Workload, WorkloadParams};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A workload registered from outside the crate, with checks of its own: evenly sized batches only
+struct Pairs;
+
+impl Workload for Pairs {
+    fn name(&self) -> &'static str { "pairs" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(item.to_string())
+    }
+}
+
+struct PairsExecutor;
+
+impl WorkloadExecutor for PairsExecutor {
+    fn name(&self) -> &'static str { "pairs" }
+
+    fn validate(&self, request: &ProcessRequest, _params: &WorkloadParams, errors: &mut Vec<ValidationError>) {
+        if !request.count.is_multiple_of(2) { errors.push(ValidationError::new("count", "pairs needs an even count")); }
+    }
+
+    fn prepare(&self, _params: &WorkloadParams, _objects: Option<Objects>) -> Option<Arc<dyn Workload>> {
+        Some(Arc::new(Pairs))
+    }
+}
+
+register_workload!(PairsExecutor);
+
+// Each invalid fixture and a field its ValidationError has to name
+const INVALID: &[(&str, &str)] = &[
+    ("invalid/best_effort_without_budget", "time_budget_ms"),
+    ("invalid/cost_out_of_range", "cost"),
+    ("invalid/scrypt_memory", "log_n"),
+    ("invalid/stress_without_duration", "duration_seconds"),
+    ("invalid/sweep_with_workers", "workers"),
+    ("invalid/too_many_items", "count"),
+    ("invalid/unknown_mode", "request"),
+    ("invalid/unknown_workload", "workload"),
+    ("invalid/verify_bad_hash", "pairs[0].hash"),
+    ("invalid/workers_out_of_range", "workers"),
+];
+
+#[tokio::test(flavor = "multi_thread")]
+async fn invalid_fixtures_are_rejected() {
+    for (name, field) in INVALID {
+        let error = invoke_fixture_err(name).await;
+        assert_eq!(error.code(), "ValidationError", "{}: {}", name, error.to_json());
+        assert!(violated_fields(&error).iter().any(|violated| violated == field), "{} does not name {}: {}", name, field, error.to_json());
+    }
+}
+
+// A fixture added to tests/fixtures/invalid has to say which field it breaks
+#[tokio::test(flavor = "multi_thread")]
+async fn every_invalid_fixture_is_listed() {
+    for name in fixtures("invalid") {
+        assert!(INVALID.iter().any(|(listed, _)| *listed == name), "{} is not in INVALID", name);
+    }
+}
+
+// Validation reports every violation at once rather than the first
+#[tokio::test(flavor = "multi_thread")]
+async fn every_violation_is_reported() {
+    let error = invoke(json!({ "mode": "parallel", "workload": "bcrypt", "cost": 40, "count": 4, "workers": 65 })).await.unwrap_err();
+    let fields = violated_fields(&error);
+    assert!(fields.contains(&"cost".to_string()) && fields.contains(&"workers".to_string()), "{}", error.to_json());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn item_limit_is_reported_with_its_details() {
+    let error = invoke_fixture_err("invalid/too_many_items").await;
+    let body = error.to_json();
+    let details = &body["errors"][0]["details"];
+    assert_eq!(details["limit"], details["max_items"], "{}", body);
+    let limit = details["limit"].as_u64().expect("limit");
+
+    let mut request = fixture("invalid/too_many_items");
+    request["count"] = (limit + 1).into();
+    assert_eq!(invoke(request).await.unwrap_err().code(), "ValidationError");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn worker_limit_is_inclusive() {
+    let describe = invoke(json!({ "mode": "describe" })).await.unwrap();
+    let max = describe["limits"]["max_request_workers"].as_u64().expect("max_request_workers");
+    let response = invoke(json!({ "mode": "parallel", "workload": "bcrypt", "cost": 4, "count": 2, "workers": max })).await
+        .unwrap_or_else(|e| panic!("{}", e.to_json()));
+    assert_eq!(response["workers"], max);
+    let error = invoke(json!({ "mode": "parallel", "workload": "bcrypt", "cost": 4, "count": 2, "workers": max + 1 })).await.unwrap_err();
+    assert_eq!(violated_fields(&error), ["workers"]);
+}
+
+// An invocation whose deadline is already inside the safety margin starts no item
+#[tokio::test(flavor = "multi_thread")]
+async fn past_deadline_is_a_timeout() {
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+    let mut context = Context::default();
+    context.deadline = now_ms + 1;
+    let error = invoke_with(json!({ "mode": "parallel", "workload": "bcrypt", "cost": 4, "count": 8 }), &context).await.unwrap_err();
+    assert_eq!(error.code(), "TimeoutError", "{}", error.to_json());
+    assert_eq!(error.to_json()["details"]["skipped"], 8);
+}
+
+// A stored response only answers the request that produced it; a key reused for another is refused
+#[tokio::test(flavor = "multi_thread")]
+async fn reused_idempotency_key_is_rejected() {
+    let request = json!({ "mode": "parallel", "workload": "bcrypt", "cost": 4, "count": 2, "idempotency_key": "errors-reused-key" });
+    invoke(request.clone()).await.unwrap_or_else(|e| panic!("{}", e.to_json()));
+    let replayed = invoke(request.clone()).await.unwrap_or_else(|e| panic!("{}", e.to_json()));
+    assert_eq!(replayed["idempotency"]["replayed"], true, "{}", replayed);
+
+    let mut different = request;
+    different["count"] = 3.into();
+    let error = invoke(different).await.unwrap_err();
+    assert_eq!(violated_fields(&error), ["idempotency_key"], "{}", error.to_json());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dependent_crate_workloads_are_validated() {
+    let response = invoke(json!({ "mode": "parallel", "workload": "pairs", "count": 4 })).await.unwrap_or_else(|e| panic!("{}", e.to_json()));
+    assert_eq!(response["completed"], 4);
+    let error = invoke(json!({ "mode": "parallel", "workload": "pairs", "count": 3 })).await.unwrap_err();
+    assert_eq!(violated_fields(&error), ["count"], "{}", error.to_json());
+}
@@ -0,0 +1,78 @@
+// The event shapes routed besides a single request (tests/fixtures/events): batch envelopes, SQS
+// and Kinesis batches, EventBridge schedules and schema v1 requests
+mod common;
+
+use common::{fixture, invoke, invoke_fixture, number};
+use rust_multithread_lambda::handler::process_each;
+use rust_multithread_lambda::workload::Workload;
+
+// A batch envelope answers every job, a failed one in place with its error body
+#[tokio::test(flavor = "multi_thread")]
+async fn batch_jobs_fail_independently() {
+    let response = invoke(fixture("events/batch")).await.unwrap_or_else(|e| panic!("{}", e.to_json()));
+    let jobs = response["jobs"].as_array().expect("jobs");
+    assert_eq!(jobs.len(), 2);
+    assert_eq!(jobs[0]["completed"], 2);
+    assert_eq!(jobs[1]["code"], "ValidationError");
+}
+
+// The empty message fails alone, so SQS only retries that one
+#[tokio::test(flavor = "multi_thread")]
+async fn sqs_reports_failed_messages() {
+    let response = invoke_fixture("events/sqs").await;
+    let failures = response["batchItemFailures"].as_array().expect("batchItemFailures");
+    assert_eq!(failures.len(), 1, "{}", response);
+    assert_eq!(failures[0]["itemIdentifier"], "msg-2");
+}
+
+// Fails on the record "panic" the way a buggy workload would
+struct PanicsOnRecord;
+
+impl Workload for PanicsOnRecord {
+    fn name(&self) -> &'static str { "panics_on_record" }
+
+    fn execute_item(&self, item: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        if item == "panic" { panic!("bad record"); }
+        Ok(item.to_string())
+    }
+}
+
+// A record whose workload panics fails alone, as the SQS and Kinesis handlers report it
+#[test]
+fn panicking_record_fails_alone() {
+    let items = ["a", "panic", "b"].map(str::to_string).to_vec();
+    let (results, _) = process_each(items, &PanicsOnRecord);
+    assert_eq!(results[0].as_deref(), Ok("a"));
+    assert_eq!(results[1].as_ref().unwrap_err(), "panics_on_record panicked: bad record");
+    assert_eq!(results[2].as_deref(), Ok("b"));
+}
+
+// Kinesis resumes a shard from its first failed record
+#[tokio::test(flavor = "multi_thread")]
+async fn kinesis_reports_the_first_failed_record() {
+    let response = invoke_fixture("events/kinesis").await;
+    let failures = response["batchItemFailures"].as_array().expect("batchItemFailures");
+    assert_eq!(failures.len(), 1, "{}", response);
+    assert_eq!(failures[0]["itemIdentifier"], "49590338271490256608559692540925702759324208523137515618");
+}
+
+// SWEEP_COUNTS is only read by the schedule handler, so setting it affects no other test
+#[tokio::test(flavor = "multi_thread")]
+async fn schedule_runs_the_self_benchmark() {
+    std::env::set_var("SWEEP_COUNTS", "2,1");
+    let response = invoke_fixture("events/schedule").await;
+    let points = response["sweep"].as_array().unwrap_or_else(|| panic!("no points in {}", response));
+    let counts: Vec<u64> = points.iter().map(|point| point["count"].as_u64().unwrap()).collect();
+    assert_eq!(counts, [1, 2]);
+    for point in points {
+        assert!(number(point, "sequential_ms") > 0.0 && number(point, "parallel_ms") > 0.0, "{}", point);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn schema_v1_is_answered_in_v1() {
+    let response = invoke_fixture("events/schema_v1").await;
+    assert_eq!(response["processed"], 2);
+    assert!(response.get("schema_version").is_none(), "{}", response);
+    assert!(response.get("phases").is_none(), "{}", response);
+}
@@ -0,0 +1,164 @@
+// Every execution mode on a representative request (tests/fixtures/modes), with the invariants
+// its response has to keep as the modes grow
+mod common;
+
+use common::{assert_process_invariants, fixtures, invoke, invoke_fixture, number, violated_fields};
+use serde_json::json;
+
+// Item-processing modes that run items on a pool of the request's `workers`
+const POOLED_MODES: &[&str] = &["parallel", "tokio_blocking", "std_threads", "pipeline", "channels", "async_limited"];
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sequential_runs_on_one_thread() {
+    let response = invoke_fixture("modes/sequential").await;
+    assert_process_invariants(&response);
+    assert_eq!(response["mode"], "sequential");
+    assert_eq!(response["processed"], 8);
+    assert_eq!(response["threads_used"], 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pooled_modes_use_more_than_one_thread() {
+    for mode in POOLED_MODES {
+        let response = invoke_fixture(&format!("modes/{}", mode)).await;
+        assert_process_invariants(&response);
+        assert_eq!(response["mode"], *mode);
+        assert_eq!(response["processed"], 16, "{}", mode);
+        assert_eq!(response["workers"], 4, "{}", mode);
+        assert_eq!(response["worker_source"], "request", "{}", mode);
+        assert!(number(&response, "threads_used") > 1.0, "{} ran on one thread: {}", mode, response);
+    }
+}
+
+// chunk_size runs each chunk on one Rayon task but still returns and counts every item
+#[tokio::test(flavor = "multi_thread")]
+async fn chunked_parallel_runs_every_item() {
+    let response = invoke(json!({ "mode": "parallel", "workload": "bcrypt", "cost": 4, "count": 10, "workers": 4, "chunk_size": 3 })).await
+        .unwrap_or_else(|e| panic!("{}", e.to_json()));
+    assert_process_invariants(&response);
+    assert_eq!(response["completed"], 10);
+    assert!(number(&response, "threads_used") > 1.0, "{}", response);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pipeline_verifies_every_hash() {
+    let response = invoke_fixture("modes/pipeline").await;
+    assert_eq!(response["pipeline"]["verified"], 16);
+    assert_eq!(response["pipeline"]["failed"], 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn async_limited_stays_within_its_permits() {
+    let response = invoke_fixture("modes/async_limited").await;
+    let concurrency = &response["concurrency"];
+    assert_eq!(concurrency["permits"], 4);
+    assert!(number(concurrency, "peak_in_flight") <= 4.0, "{}", concurrency);
+    assert!(number(concurrency, "mean_in_flight") <= number(concurrency, "peak_in_flight"), "{}", concurrency);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn auto_reports_its_choice() {
+    let response = invoke_fixture("modes/auto").await;
+    assert_process_invariants(&response);
+    let chosen = response["auto"]["chosen"].as_str().expect("auto.chosen");
+    assert!(chosen == "sequential" || chosen == "parallel", "{}", response);
+    assert_eq!(response["mode"], chosen);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn benchmark_compares_both_runs() {
+    let response = invoke_fixture("modes/benchmark").await;
+    let (sequential_ms, parallel_ms) = (number(&response, "sequential_ms"), number(&response, "parallel_ms"));
+    assert!(sequential_ms > 0.0 && parallel_ms > 0.0, "{}", response);
+    assert!((number(&response, "speedup") - sequential_ms / parallel_ms).abs() < 1e-6, "{}", response);
+    assert!((number(&response, "efficiency") - number(&response, "speedup") / 4.0).abs() < 1e-6, "{}", response);
+    assert!(number(&response, "threads_used") > 1.0, "{}", response);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sweep_runs_every_pool_size_in_order() {
+    let response = invoke_fixture("modes/sweep").await;
+    let points = response["sweep"].as_array().expect("sweep points");
+    let workers: Vec<u64> = points.iter().map(|point| point["workers"].as_u64().unwrap()).collect();
+    assert_eq!(workers, [1, 2, 4]);
+    for point in points {
+        assert!(number(point, "duration_ms") > 0.0, "{}", point);
+        assert!(number(point, "threads_used") <= number(point, "workers"), "{}", point);
+        assert!((number(point, "efficiency") - number(point, "speedup") / number(point, "workers")).abs() < 1e-6, "{}", point);
+    }
+    assert_eq!(points[0]["threads_used"], 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stress_runs_for_its_duration() {
+    let response = invoke_fixture("modes/stress").await;
+    let elapsed_ms = number(&response, "elapsed_ms");
+    assert!(elapsed_ms >= 1000.0, "{}", response);
+    assert!(number(&response, "processed") > 0.0, "{}", response);
+    assert!((number(&response, "items_per_sec") - number(&response, "processed") / (elapsed_ms / 1000.0)).abs() < 1e-6, "{}", response);
+    assert!(number(&response, "threads_used") > 1.0, "{}", response);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn best_effort_stops_at_its_budget() {
+    let response = invoke_fixture("modes/best_effort").await;
+    assert_process_invariants(&response);
+    let budget = &response["budget"];
+    assert_eq!(budget["exhausted"], true, "{}", response);
+    assert_eq!(budget["remaining"], response["skipped"]);
+    assert_eq!(number(&response, "processed") + number(&response, "skipped"), 1000.0);
+    assert!(number(budget, "elapsed_ms") >= 100.0, "{}", budget);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn describe_lists_what_the_build_serves() {
+    let response = invoke_fixture("modes/describe").await;
+    let modes = response["modes"].as_array().expect("modes");
+    for mode in fixtures("modes") {
+        let mode = mode.strip_prefix("modes/").unwrap();
+        assert!(modes.iter().any(|listed| listed == mode), "describe does not list {}: {:?}", mode, modes);
+    }
+    let workloads = response["workloads"].as_array().expect("workloads");
+    assert!(workloads.iter().any(|workload| workload == "bcrypt"), "{:?}", workloads);
+    assert!(response["unavailable"].as_object().expect("unavailable").keys().all(|workload| !workloads.iter().any(|listed| listed == workload)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn health_checks_the_pool() {
+    let response = invoke_fixture("modes/health").await;
+    assert_eq!(response["healthy"], true, "{}", response);
+    assert_eq!(response["pool_initialized"], true, "{}", response);
+}
+
+// Every workload this build registers runs (the cheapest settings of each); one that only reads
+// its items from S3 (s3_checksum) has to ask for source_s3 instead
+#[tokio::test(flavor = "multi_thread")]
+async fn every_registered_workload_runs() {
+    let describe = invoke_fixture("modes/describe").await;
+    for workload in describe["workloads"].as_array().expect("workloads") {
+        let response = invoke(json!({
+            "mode": "parallel", "workload": workload, "count": 2, "workers": 2,
+            "cost": 4, "rounds": 1000, "memory_kib": 8, "iterations": 1, "log_n": 1, "samples": 1000,
+            "factor_bits": 8, "matrix_size": 16, "buffer_kib": 1, "max_dimension": 16,
+        })).await;
+        let response = match response {
+            Err(e) if violated_fields(&e) == ["source_s3"] => continue,
+            response => response.unwrap_or_else(|e| panic!("{} failed: {}", workload, e.to_json())),
+        };
+        assert_process_invariants(&response);
+        assert_eq!(response["completed"], 2, "{}", workload);
+    }
+}
+
+// Container counters only move forward, whichever invocations run between two of these
+#[tokio::test(flavor = "multi_thread")]
+async fn lifecycle_counters_are_monotonic() {
+    let first = invoke_fixture("modes/sequential").await;
+    let second = invoke_fixture("modes/sequential").await;
+    assert!(number(&second, "invocation_number") > number(&first, "invocation_number"));
+    assert_eq!(second["cold_start"], false);
+    assert!(number(&second["container_stats"], "lifetime_ms") > number(&first["container_stats"], "lifetime_ms"));
+    assert!(number(&second["container_stats"], "jobs") > number(&first["container_stats"], "jobs"));
+    let stats = &second["container_stats"];
+    assert!(number(stats, "min_duration_ms") <= number(stats, "max_duration_ms"), "{}", stats);
+}